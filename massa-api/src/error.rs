@@ -4,6 +4,7 @@ use displaydoc::Display;
 use massa_consensus_exports::error::ConsensusError;
 use massa_execution_exports::ExecutionError;
 use massa_hash::MassaHashError;
+use massa_models::api::error_codes;
 use massa_models::ModelsError;
 use massa_network_exports::NetworkError;
 use massa_pool::PoolError;
@@ -45,10 +46,33 @@ pub enum ApiError {
     WrongAPI,
 }
 
+impl ApiError {
+    /// JSON-RPC error code, so that clients can tell errors apart
+    pub fn code(&self) -> i64 {
+        match self {
+            ApiError::PoolError(_) => error_codes::POOL_ERROR,
+            ApiError::TooManyArguments(_) => error_codes::TOO_MANY_ARGUMENTS,
+            ApiError::SendChannelError(_) => error_codes::SEND_CHANNEL_ERROR,
+            ApiError::ReceiveChannelError(_) => error_codes::RECEIVE_CHANNEL_ERROR,
+            ApiError::MassaHashError(_) => error_codes::MASSA_HASH_ERROR,
+            ApiError::ConsensusError(_) => error_codes::CONSENSUS_ERROR,
+            ApiError::ExecutionError(_) => error_codes::EXECUTION_ERROR,
+            ApiError::NetworkError(_) => error_codes::NETWORK_ERROR,
+            ApiError::ModelsError(_) => error_codes::MODELS_ERROR,
+            ApiError::TimeError(_) => error_codes::TIME_ERROR,
+            ApiError::NotFound => error_codes::NOT_FOUND,
+            ApiError::InconsistencyError(_) => error_codes::INCONSISTENCY_ERROR,
+            ApiError::MissingCommandSender(_) => error_codes::MISSING_COMMAND_SENDER,
+            ApiError::MissingConfig(_) => error_codes::MISSING_CONFIG,
+            ApiError::WrongAPI => error_codes::WRONG_API,
+        }
+    }
+}
+
 impl From<ApiError> for jsonrpc_core::Error {
     fn from(err: ApiError) -> Self {
         jsonrpc_core::Error {
-            code: jsonrpc_core::ErrorCode::ServerError(500),
+            code: jsonrpc_core::ErrorCode::ServerError(err.code()),
             message: err.to_string(),
             data: None,
         }
@@ -11,13 +11,13 @@ use jsonrpc_http_server::{CloseHandle, ServerBuilder};
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, ReadOnlyBytecodeExecution,
-    ReadOnlyCall, TimeInterval,
+    AddressInfo, AsyncMessageFilter, AsyncMessageInfo, BlockInfo, BlockSummary,
+    DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter, NodeStatus,
+    OperationInfo, ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionReceipt, ExecutionTrace};
 use massa_models::node::NodeId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
@@ -38,6 +38,9 @@ mod public;
 mod settings;
 pub use settings::APISettings;
 
+#[cfg(feature = "testing")]
+pub mod test_exports;
+
 /// Public API component
 pub struct Public {
     /// link to the consensus component
@@ -88,31 +91,43 @@ pub trait RpcServer: Endpoints {
 }
 
 fn serve(api: impl Endpoints, url: &SocketAddr) -> StopHandle {
+    start_server(api, url).expect("Unable to start RPC server")
+}
+
+/// Starts serving the API on `url`, whose port can be 0 to let the system pick a free one
+fn start_server(api: impl Endpoints, url: &SocketAddr) -> std::io::Result<StopHandle> {
     let mut io = IoHandler::new();
     io.extend_with(api.to_delegate());
 
     let server = ServerBuilder::new(io)
         .event_loop_executor(tokio::runtime::Handle::current())
         .max_request_body_size(50 * 1024 * 1024)
-        .start_http(url)
-        .expect("Unable to start RPC server");
+        .start_http(url)?;
 
+    let local_addr = *server.address();
     let close_handle = server.close_handle();
     let join_handle = thread::spawn(|| server.wait());
 
-    StopHandle {
+    Ok(StopHandle {
+        local_addr,
         close_handle,
         join_handle,
-    }
+    })
 }
 
 /// Used to be able to stop the API
 pub struct StopHandle {
+    local_addr: SocketAddr,
     close_handle: CloseHandle,
     join_handle: JoinHandle<()>,
 }
 
 impl StopHandle {
+    /// address the API is actually bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
     /// stop the API gracefully
     pub fn stop(self) {
         self.close_handle.close();
@@ -164,6 +179,14 @@ pub trait Endpoints {
     #[rpc(name = "get_staking_addresses")]
     fn get_staking_addresses(&self) -> BoxFuture<Result<Set<Address>, ApiError>>;
 
+    /// Returns the execution traces of recently finalized operations.
+    /// Operation tracing must be enabled in the node configuration.
+    #[rpc(name = "get_operation_execution_traces")]
+    fn get_operation_execution_traces(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionTrace>, ApiError>>;
+
     /// Bans given IP address(es).
     /// No confirmation to expect.
     #[rpc(name = "node_ban_by_ip")]
@@ -261,6 +284,24 @@ pub trait Endpoints {
         &self,
         _: EventFilter,
     ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>>;
+
+    /// Get the execution receipts of smart contract operations:
+    /// success or error, gas used, coins spent and created addresses.
+    #[rpc(name = "get_operation_receipts")]
+    fn get_operation_receipts(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionReceipt>, ApiError>>;
+
+    /// Get the pending asynchronous messages, optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * validity window overlapping a slot range
+    #[rpc(name = "get_async_messages")]
+    fn get_async_messages(
+        &self,
+        _: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>>;
 }
 
 fn wrong_api<T>() -> BoxFuture<Result<T, ApiError>> {
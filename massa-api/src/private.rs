@@ -8,13 +8,13 @@ use jsonrpc_http_server::tokio::sync::mpsc;
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, ReadOnlyBytecodeExecution,
-    ReadOnlyCall, TimeInterval,
+    AddressInfo, AsyncMessageFilter, AsyncMessageInfo, BlockInfo, BlockSummary,
+    DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter, NodeStatus,
+    OperationInfo, ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionReceipt, ExecutionTrace};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::Set;
@@ -108,6 +108,18 @@ impl Endpoints for API<Private> {
         Box::pin(closure())
     }
 
+    fn get_operation_execution_traces(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionTrace>, ApiError>> {
+        let traces = self
+            .0
+            .execution_controller
+            .get_final_operation_traces(operation_ids);
+        let closure = async move || Ok(traces);
+        Box::pin(closure())
+    }
+
     fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         let network_command_sender = self.0.network_command_sender.clone();
         let closure = async move || Ok(network_command_sender.node_ban_by_ips(ips).await?);
@@ -194,6 +206,20 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<Vec<SCOutputEvent>>()
     }
 
+    fn get_operation_receipts(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionReceipt>, ApiError>> {
+        crate::wrong_api::<Vec<ExecutionReceipt>>()
+    }
+
+    fn get_async_messages(
+        &self,
+        _: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>> {
+        crate::wrong_api::<Vec<AsyncMessageInfo>>()
+    }
+
     fn node_whitelist(&self, ips: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         let network_command_sender = self.0.network_command_sender.clone();
         let closure = async move || Ok(network_command_sender.whitelist(ips).await?);
@@ -7,14 +7,15 @@ use futures::{stream::FuturesUnordered, StreamExt};
 use jsonrpc_core::BoxFuture;
 use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::{
-    ExecutionController, ExecutionStackElement, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ExecutionController, ExecutionError, ExecutionOutput, ExecutionStackElement,
+    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
 use massa_graph::{DiscardReason, ExportBlockStatus};
 use massa_models::api::{
-    DatastoreEntryInput, DatastoreEntryOutput, ReadOnlyBytecodeExecution, ReadOnlyCall,
-    SCELedgerInfo,
+    AsyncMessageFilter, AsyncMessageInfo, DatastoreEntryInput, DatastoreEntryOutput,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SCELedgerInfo,
 };
-use massa_models::execution::ReadOnlyResult;
+use massa_models::execution::{ExecutionReceipt, ExecutionTrace, ReadOnlyResult};
 use massa_models::SignedOperation;
 
 use massa_models::{
@@ -37,6 +38,15 @@ use massa_signature::{derive_public_key, generate_random_private_key, PrivateKey
 use massa_time::MassaTime;
 use std::net::{IpAddr, SocketAddr};
 
+/// Gets the trace of a read-only execution, whether it succeeded or not
+fn read_only_trace(result: &Result<ExecutionOutput, ExecutionError>) -> Option<ExecutionTrace> {
+    match result {
+        Ok(output) => output.traces.last().cloned(),
+        Err(ExecutionError::TracedRuntimeError(_, trace)) => Some((**trace).clone()),
+        Err(_) => None,
+    }
+}
+
 impl API<Public> {
     /// generate a new public API
     pub fn new(
@@ -102,6 +112,10 @@ impl Endpoints for API<Public> {
             address,
             simulated_gas_price,
             bytecode,
+            trace,
+            state_overrides,
+            simulated_slot,
+            final_slot,
         } in reqs
         {
             let address = address.unwrap_or_else(|| {
@@ -124,6 +138,10 @@ impl Endpoints for API<Public> {
                     coins: Default::default(),
                     owned_addresses: vec![address],
                 }],
+                trace,
+                state_overrides,
+                simulated_slot,
+                final_slot,
             };
 
             // run
@@ -136,6 +154,7 @@ impl Endpoints for API<Public> {
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
+                trace: read_only_trace(&result),
                 output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
             };
 
@@ -165,6 +184,11 @@ impl Endpoints for API<Public> {
             target_function,
             parameter,
             caller_address,
+            trace,
+            state_overrides,
+            simulated_slot,
+            final_slot,
+            coins,
         } in reqs
         {
             let caller_address = caller_address.unwrap_or_else(|| {
@@ -185,6 +209,7 @@ impl Endpoints for API<Public> {
                     target_func: target_function,
                     target_addr: target_address,
                     parameter,
+                    coins,
                 },
                 call_stack: vec![
                     ExecutionStackElement {
@@ -194,10 +219,14 @@ impl Endpoints for API<Public> {
                     },
                     ExecutionStackElement {
                         address: target_address,
-                        coins: Default::default(),
+                        coins,
                         owned_addresses: vec![target_address],
                     },
                 ],
+                trace,
+                state_overrides,
+                simulated_slot,
+                final_slot,
             };
 
             // run
@@ -210,6 +239,7 @@ impl Endpoints for API<Public> {
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
+                trace: read_only_trace(&result),
                 output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
             };
 
@@ -229,6 +259,13 @@ impl Endpoints for API<Public> {
         crate::wrong_api::<Set<Address>>()
     }
 
+    fn get_operation_execution_traces(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionTrace>, ApiError>> {
+        crate::wrong_api::<Vec<ExecutionTrace>>()
+    }
+
     fn node_ban_by_ip(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
@@ -255,6 +292,7 @@ impl Endpoints for API<Public> {
         let mut pool_command_sender = self.0.pool_command_sender.clone();
         let node_id = self.0.node_id;
         let config = CompactConfig::default();
+        let execution_stats = self.0.execution_controller.get_stats();
         let closure = async move || {
             let now = MassaTime::compensated_now(compensation_millis)?;
             let last_slot = get_latest_block_slot_at_timestamp(
@@ -291,7 +329,7 @@ impl Endpoints for API<Public> {
                 consensus_stats: consensus_stats?,
                 network_stats: network_stats?,
                 pool_stats: pool_stats?,
-
+                execution_stats,
                 config,
                 current_cycle: last_slot
                     .unwrap_or_else(|| Slot::new(0, 0))
@@ -756,6 +794,30 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn get_operation_receipts(
+        &self,
+        ops: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionReceipt>, ApiError>> {
+        if ops.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+
+        let receipts = self.0.execution_controller.get_operation_receipts(ops);
+        let closure = async move || Ok(receipts);
+        Box::pin(closure())
+    }
+
+    fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>> {
+        let messages = self.0.execution_controller.get_async_messages(filter);
+        let closure = async move || Ok(messages);
+        Box::pin(closure())
+    }
+
     fn node_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
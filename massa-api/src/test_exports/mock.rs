@@ -0,0 +1,580 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::error::ApiError;
+use crate::{Endpoints, StopHandle};
+use jsonrpc_core::BoxFuture;
+use massa_execution_exports::EventStore;
+use massa_hash::Hash;
+use massa_models::api::{
+    AddressInfo, AsyncMessageFilter, AsyncMessageInfo, BlockInfo, BlockInfoContent, BlockSummary,
+    DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter, NodeStatus,
+    OperationInfo, ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+};
+use massa_models::clique::Clique;
+use massa_models::composite::PubkeySig;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionReceipt, ExecutionTrace};
+use massa_models::node::NodeId;
+use massa_models::output_event::SCOutputEvent;
+use massa_models::prehash::{Map, Set};
+use massa_models::signed::{Signable, Signed};
+use massa_models::stats::{ConsensusStats, NetworkStats, PoolStats};
+use massa_models::timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp};
+use massa_models::{
+    Address, Block, BlockHeader, BlockId, CompactConfig, EndorsementId, ModelsError, OperationId,
+    SignedOperation, Slot, Version,
+};
+use massa_signature::{derive_public_key, generate_random_private_key, sign, PrivateKey};
+use massa_time::MassaTime;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// In-memory state served by a `MockNode`.
+///
+/// Fields can be set directly, or through the helpers below
+/// to simulate the life of operations sent to the node.
+pub struct MockNodeState {
+    /// key of the node, used to sign messages and produced blocks
+    pub node_private_key: PrivateKey,
+    /// status returned by `get_status`, time related fields are updated on each call
+    pub status: NodeStatus,
+    /// known blocks
+    pub blocks: Map<BlockId, BlockInfo>,
+    /// known addresses
+    pub addresses: Map<Address, AddressInfo>,
+    /// known operations, in pool or in blocks
+    pub operations: Map<OperationId, OperationInfo>,
+    /// smart contract events
+    pub events: EventStore,
+    /// datastore entries (address, key) -> (final value, active value)
+    pub datastore: HashMap<(Address, Hash), DatastoreEntryOutput>,
+    /// active stakers and their roll counts
+    pub stakers: Map<Address, u64>,
+    /// addresses the node stakes with
+    pub staking_addresses: Set<Address>,
+    /// response returned to every read-only execution request, if any
+    pub read_only_response: Option<ExecuteReadOnlyResponse>,
+    /// execution traces of final operations
+    pub operation_traces: Map<OperationId, ExecutionTrace>,
+    /// execution receipts of operations
+    pub operation_receipts: Map<OperationId, ExecutionReceipt>,
+    /// pending asynchronous messages, by decreasing priority
+    pub async_messages: Vec<AsyncMessageInfo>,
+    /// if set, sent operations are immediately included in a new block, final if true
+    pub include_sent_operations: Option<bool>,
+    /// true once `stop_node` was called
+    pub stopped: bool,
+}
+
+impl MockNodeState {
+    /// Empty node state with the given configuration
+    pub fn new(config: CompactConfig) -> Self {
+        let node_private_key = generate_random_private_key();
+        let status = NodeStatus {
+            node_id: NodeId(derive_public_key(&node_private_key)),
+            node_ip: None,
+            version: Version::from_str("TEST.0.0").unwrap(),
+            current_time: MassaTime::from(0),
+            current_cycle: 0,
+            connected_nodes: HashMap::new(),
+            last_slot: None,
+            next_slot: Slot::new(0, 0),
+            consensus_stats: ConsensusStats {
+                start_timespan: MassaTime::from(0),
+                end_timespan: MassaTime::from(0),
+                final_block_count: 0,
+                final_operation_count: 0,
+                stale_block_count: 0,
+                clique_count: 1,
+                staker_count: 0,
+            },
+            pool_stats: PoolStats {
+                operation_count: 0,
+                endorsement_count: 0,
+            },
+            network_stats: NetworkStats {
+                in_connection_count: 0,
+                out_connection_count: 0,
+                known_peer_count: 0,
+                banned_peer_count: 0,
+                active_node_count: 0,
+            },
+            execution_stats: Default::default(),
+            config,
+        };
+        MockNodeState {
+            node_private_key,
+            status,
+            blocks: Default::default(),
+            addresses: Default::default(),
+            operations: Default::default(),
+            events: Default::default(),
+            datastore: Default::default(),
+            stakers: Default::default(),
+            staking_addresses: Default::default(),
+            read_only_response: None,
+            operation_traces: Default::default(),
+            operation_receipts: Default::default(),
+            async_messages: Default::default(),
+            include_sent_operations: None,
+            stopped: false,
+        }
+    }
+
+    /// Adds operations to the pool, as `send_operations` does
+    pub fn add_operations(
+        &mut self,
+        operations: Vec<SignedOperation>,
+    ) -> Result<Vec<OperationId>, ModelsError> {
+        let mut ids = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let id = operation.verify_integrity()?;
+            self.operations.entry(id).or_insert(OperationInfo {
+                id,
+                in_pool: true,
+                in_blocks: Vec::new(),
+                is_final: false,
+                operation,
+            });
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Creates a block in the blockclique at the given slot, signed by the node,
+    /// containing the given known operations, which leave the pool.
+    ///
+    /// Fails with `ApiError::NotFound` if an operation is unknown.
+    pub fn produce_block(
+        &mut self,
+        slot: Slot,
+        operation_ids: &[OperationId],
+        is_final: bool,
+    ) -> Result<BlockId, ApiError> {
+        let operations = operation_ids
+            .iter()
+            .map(|id| match self.operations.get(id) {
+                Some(info) => Ok(info.operation.clone()),
+                None => Err(ApiError::NotFound),
+            })
+            .collect::<Result<_, _>>()?;
+        let (id, header) = Signed::new_signed(
+            BlockHeader {
+                creator: derive_public_key(&self.node_private_key),
+                slot,
+                parents: Vec::new(),
+                operation_merkle_root: Hash::compute_from(
+                    &operation_ids
+                        .iter()
+                        .flat_map(|id| id.into_bytes())
+                        .collect::<Vec<_>>(),
+                ),
+                endorsements: Vec::new(),
+            },
+            &self.node_private_key,
+        )?;
+        self.blocks.insert(
+            id,
+            BlockInfo {
+                id,
+                content: Some(BlockInfoContent {
+                    is_final,
+                    is_stale: false,
+                    is_in_blockclique: true,
+                    block: Block { header, operations },
+                }),
+            },
+        );
+        for operation_id in operation_ids {
+            if let Some(info) = self.operations.get_mut(operation_id) {
+                info.in_pool = false;
+                info.in_blocks.push(id);
+                info.is_final |= is_final;
+            }
+        }
+        Ok(id)
+    }
+
+    /// Marks a known block and its operations as final.
+    ///
+    /// Fails with `ApiError::NotFound` if the block is unknown.
+    pub fn finalize_block(&mut self, block_id: BlockId) -> Result<(), ApiError> {
+        let content = self
+            .blocks
+            .get_mut(&block_id)
+            .and_then(|info| info.content.as_mut())
+            .ok_or(ApiError::NotFound)?;
+        content.is_final = true;
+        let operation_ids = content
+            .block
+            .operations
+            .iter()
+            .map(|op| op.content.compute_id())
+            .collect::<Result<Vec<_>, _>>()?;
+        for operation_id in operation_ids {
+            if let Some(info) = self.operations.get_mut(&operation_id) {
+                info.is_final = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a smart contract event
+    pub fn push_event(&mut self, event: SCOutputEvent) {
+        self.events.push(event);
+    }
+
+    /// Status with time related fields computed from the current time
+    fn current_status(&self) -> Result<NodeStatus, ApiError> {
+        let mut status = self.status.clone();
+        let cfg = &status.config;
+        let now = MassaTime::now()?;
+        let last_slot = get_latest_block_slot_at_timestamp(
+            cfg.thread_count,
+            cfg.t0,
+            cfg.genesis_timestamp,
+            now,
+        )?;
+        let slot = last_slot.unwrap_or_else(|| Slot::new(0, 0));
+        status.next_slot = slot.get_next_slot(cfg.thread_count)?;
+        status.current_cycle = slot.get_cycle(cfg.periods_per_cycle);
+        status.current_time = now;
+        status.last_slot = last_slot;
+        status.pool_stats.operation_count =
+            self.operations.values().filter(|op| op.in_pool).count() as u64;
+        status.consensus_stats.staker_count = self.stakers.len() as u64;
+        Ok(status)
+    }
+}
+
+/// JSON-RPC node serving a scriptable in-memory state,
+/// implementing both the public and the private endpoints.
+#[derive(Clone)]
+pub struct MockNode {
+    state: Arc<Mutex<MockNodeState>>,
+}
+
+impl MockNode {
+    /// Mock node serving the given state
+    pub fn new(state: MockNodeState) -> Self {
+        MockNode {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Shared state, to script the node while it is served
+    pub fn state(&self) -> Arc<Mutex<MockNodeState>> {
+        self.state.clone()
+    }
+
+    /// Starts serving the node on the given address,
+    /// whose port can be 0 to let the system pick a free one, see `StopHandle::local_addr`
+    pub fn serve(self, url: &SocketAddr) -> std::io::Result<StopHandle> {
+        crate::start_server(self, url)
+    }
+
+    /// Applies `f` to the state and returns its result as a future
+    fn with_state<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut MockNodeState) -> Result<T, ApiError>,
+    ) -> BoxFuture<Result<T, ApiError>> {
+        let res = match self.state.lock() {
+            Ok(mut state) => f(&mut state),
+            Err(_) => Err(ApiError::InconsistencyError(
+                "mock node state lock poisoned".into(),
+            )),
+        };
+        let closure = async move || res;
+        Box::pin(closure())
+    }
+}
+
+impl Endpoints for MockNode {
+    fn stop_node(&self) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|state| {
+            state.stopped = true;
+            Ok(())
+        })
+    }
+
+    fn node_sign_message(&self, message: Vec<u8>) -> BoxFuture<Result<PubkeySig, ApiError>> {
+        self.with_state(|state| {
+            Ok(PubkeySig {
+                public_key: derive_public_key(&state.node_private_key),
+                signature: sign(&Hash::compute_from(&message), &state.node_private_key)
+                    .map_err(ModelsError::from)?,
+            })
+        })
+    }
+
+    fn add_staking_private_keys(&self, keys: Vec<PrivateKey>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|state| {
+            state.staking_addresses.extend(
+                keys.iter()
+                    .map(|key| Address::from_public_key(&derive_public_key(key))),
+            );
+            Ok(())
+        })
+    }
+
+    fn execute_read_only_bytecode(
+        &self,
+        reqs: Vec<ReadOnlyBytecodeExecution>,
+    ) -> BoxFuture<Result<Vec<ExecuteReadOnlyResponse>, ApiError>> {
+        self.with_state(|state| read_only_responses(state, reqs.len()))
+    }
+
+    fn execute_read_only_call(
+        &self,
+        reqs: Vec<ReadOnlyCall>,
+    ) -> BoxFuture<Result<Vec<ExecuteReadOnlyResponse>, ApiError>> {
+        self.with_state(|state| read_only_responses(state, reqs.len()))
+    }
+
+    fn remove_staking_addresses(&self, addresses: Vec<Address>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|state| {
+            for address in addresses {
+                state.staking_addresses.remove(&address);
+            }
+            Ok(())
+        })
+    }
+
+    fn get_staking_addresses(&self) -> BoxFuture<Result<Set<Address>, ApiError>> {
+        self.with_state(|state| Ok(state.staking_addresses.clone()))
+    }
+
+    fn get_operation_execution_traces(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionTrace>, ApiError>> {
+        self.with_state(|state| {
+            Ok(operation_ids
+                .iter()
+                .filter_map(|id| state.operation_traces.get(id).cloned())
+                .collect())
+        })
+    }
+
+    fn node_ban_by_ip(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn node_ban_by_id(&self, _: Vec<NodeId>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn node_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn node_remove_from_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn node_unban_by_ip(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn node_unban_by_id(&self, _: Vec<NodeId>) -> BoxFuture<Result<(), ApiError>> {
+        self.with_state(|_| Ok(()))
+    }
+
+    fn get_status(&self) -> BoxFuture<Result<NodeStatus, ApiError>> {
+        self.with_state(|state| state.current_status())
+    }
+
+    fn get_cliques(&self) -> BoxFuture<Result<Vec<Clique>, ApiError>> {
+        self.with_state(|state| {
+            Ok(vec![Clique {
+                block_ids: state
+                    .blocks
+                    .values()
+                    .filter(|info| {
+                        info.content
+                            .as_ref()
+                            .map_or(false, |content| content.is_in_blockclique)
+                    })
+                    .map(|info| info.id)
+                    .collect(),
+                fitness: 0,
+                is_blockclique: true,
+            }])
+        })
+    }
+
+    fn get_stakers(&self) -> BoxFuture<Result<Vec<(Address, u64)>, ApiError>> {
+        self.with_state(|state| {
+            let mut stakers: Vec<(Address, u64)> =
+                state.stakers.iter().map(|(a, r)| (*a, *r)).collect();
+            stakers.sort_by(|&(_, a), &(_, b)| b.cmp(&a));
+            Ok(stakers)
+        })
+    }
+
+    fn get_operations(
+        &self,
+        ops: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<OperationInfo>, ApiError>> {
+        self.with_state(|state| {
+            Ok(ops
+                .iter()
+                .filter_map(|id| state.operations.get(id).cloned())
+                .collect())
+        })
+    }
+
+    fn get_endorsements(
+        &self,
+        _: Vec<EndorsementId>,
+    ) -> BoxFuture<Result<Vec<EndorsementInfo>, ApiError>> {
+        self.with_state(|_| Ok(Vec::new()))
+    }
+
+    fn get_block(&self, id: BlockId) -> BoxFuture<Result<BlockInfo, ApiError>> {
+        self.with_state(|state| {
+            Ok(state
+                .blocks
+                .get(&id)
+                .cloned()
+                .unwrap_or(BlockInfo { id, content: None }))
+        })
+    }
+
+    fn get_graph_interval(
+        &self,
+        time: TimeInterval,
+    ) -> BoxFuture<Result<Vec<BlockSummary>, ApiError>> {
+        self.with_state(|state| {
+            let cfg = &state.status.config;
+            let mut summaries = Vec::new();
+            for info in state.blocks.values() {
+                let content = match &info.content {
+                    Some(content) => content,
+                    None => continue,
+                };
+                let header = &content.block.header.content;
+                let timestamp = get_block_slot_timestamp(
+                    cfg.thread_count,
+                    cfg.t0,
+                    cfg.genesis_timestamp,
+                    header.slot,
+                )?;
+                if time.start.map_or(false, |start| timestamp < start)
+                    || time.end.map_or(false, |end| timestamp >= end)
+                {
+                    continue;
+                }
+                summaries.push(BlockSummary {
+                    id: info.id,
+                    is_final: content.is_final,
+                    is_stale: content.is_stale,
+                    is_in_blockclique: content.is_in_blockclique,
+                    slot: header.slot,
+                    creator: Address::from_public_key(&header.creator),
+                    parents: header.parents.clone(),
+                });
+            }
+            Ok(summaries)
+        })
+    }
+
+    fn get_datastore_entry(
+        &self,
+        entry: DatastoreEntryInput,
+    ) -> BoxFuture<Result<DatastoreEntryOutput, ApiError>> {
+        self.with_state(|state| {
+            Ok(state
+                .datastore
+                .get(&(entry.address, entry.key))
+                .cloned()
+                .unwrap_or(DatastoreEntryOutput {
+                    final_value: None,
+                    active_value: None,
+                }))
+        })
+    }
+
+    fn get_addresses(
+        &self,
+        addresses: Vec<Address>,
+    ) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>> {
+        self.with_state(|state| {
+            addresses
+                .iter()
+                .map(|address| {
+                    state
+                        .addresses
+                        .get(address)
+                        .cloned()
+                        .ok_or(ApiError::NotFound)
+                })
+                .collect()
+        })
+    }
+
+    fn send_operations(
+        &self,
+        ops: Vec<SignedOperation>,
+    ) -> BoxFuture<Result<Vec<OperationId>, ApiError>> {
+        self.with_state(|state| {
+            let ids = state.add_operations(ops)?;
+            if let Some(is_final) = state.include_sent_operations {
+                let slot = state.current_status()?.next_slot;
+                state.produce_block(slot, &ids, is_final)?;
+            }
+            Ok(ids)
+        })
+    }
+
+    fn get_filtered_sc_output_event(
+        &self,
+        filter: EventFilter,
+    ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>> {
+        self.with_state(|state| {
+            Ok(state
+                .events
+                .get_filtered_sc_output_event(&filter)
+                .into_iter()
+                .collect())
+        })
+    }
+
+    fn get_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<ExecutionReceipt>, ApiError>> {
+        self.with_state(|state| {
+            Ok(operation_ids
+                .iter()
+                .filter_map(|id| state.operation_receipts.get(id).cloned())
+                .collect())
+        })
+    }
+
+    fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+    ) -> BoxFuture<Result<Vec<AsyncMessageInfo>, ApiError>> {
+        self.with_state(|state| {
+            Ok(state
+                .async_messages
+                .iter()
+                .filter(|message| filter.matches(message))
+                .cloned()
+                .collect())
+        })
+    }
+}
+
+/// Scripted response repeated for each read-only execution request
+fn read_only_responses(
+    state: &MockNodeState,
+    count: usize,
+) -> Result<Vec<ExecuteReadOnlyResponse>, ApiError> {
+    let response = state.read_only_response.clone().ok_or_else(|| {
+        ApiError::InconsistencyError("no scripted read-only execution response".into())
+    })?;
+    Ok(vec![response; count])
+}
@@ -0,0 +1,15 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module exposes useful tooling for testing.
+//! It is only compiled and exported by the crate if the "testing" feature is enabled.
+//!
+//!
+//! # Architecture
+//!
+//! ## `mock.rs`
+//! Provides `MockNode`, a JSON-RPC server implementing `Endpoints` over a scriptable
+//! in-memory state (blocks, addresses, operations, events), so that SDK and client
+//! tests can run offline without a real node.
+
+mod mock;
+pub use mock::*;
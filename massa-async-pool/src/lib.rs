@@ -26,7 +26,8 @@
 //!     "max_gas": 12334,  // max gas available when the handler is called
 //!     "gas_price": "124.23",  // gas price for the handler call
 //!     "coins": "1111.11",  // amount of coins to transfer to the destination address when calling its handler
-//!     "data": { ... any object ... }  // data payload of the message, passed as the sole parameter of the destination handler when called
+//!     "data": { ... any object ... },  // data payload of the message, passed as the sole parameter of the destination handler when called
+//!     "recurrence": {"period": 10, "remaining_count": 5, "gas_budget": 61670}  // optional: reschedules the message after each execution
 //! }
 //! ```
 //!
@@ -61,6 +62,13 @@
 //!   * on any failure, cancel all the effects of execution and credit M.coins back to the sender
 //!   * if there is a block at slot S, the execution of the block happens here
 //!
+//! ## Recurring messages
+//!
+//! * a message with a `recurrence` is not removed from the pool when it is taken for execution at slot S:
+//!   * if `remaining_count > 0` and `gas_budget >= max_gas`, it is replaced (with the same ID) by its next occurrence, whose validity range is shifted to start `period` periods after S, whose `remaining_count` is decremented, whose `gas_budget` is decreased by `max_gas` and which carries no coins
+//!   * otherwise, it is removed from the pool and `gas_budget * gas_price` is credited back to the sender
+//! * when a recurring message is deleted from the pool (expiry or overflow), `gas_budget * gas_price` is credited back to the sender along with "coins"
+//!
 //! ## How to receive a message (inside the smart contract)
 //!
 //! * define a public exported handler function taking 1 parameter (the message data)
@@ -74,6 +82,8 @@
 //!
 //! ## pool.rs
 //! Defines the `AsyncPool` that manipulates a list of `AsyncMessages` sorted by priority.
+//! The messages are stored in a RocksDB column family, with keys ordered by `AsyncMessageId`,
+//! next to a metadata column family holding the slot at the output of which the pool is attached.
 //!
 //! ## changes.rs
 //! Represents and manipulates changes (message additions/deletions) in the `AsyncPool`.
@@ -86,10 +96,6 @@
 //! When the crate feature `testing` is enabled, tooling useful for testing purposes is exported.
 //! See `test_exports/mod.rs` for details.
 
-#![feature(map_first_last)]
-#![feature(btree_drain_filter)]
-#![feature(drain_filter)]
-
 mod changes;
 mod config;
 mod message;
@@ -101,6 +107,7 @@ pub use changes::{
 pub use config::AsyncPoolConfig;
 pub use message::{
     AsyncMessage, AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer,
+    AsyncMessageRecurrence,
 };
 pub use pool::AsyncPool;
 
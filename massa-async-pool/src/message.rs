@@ -159,6 +159,23 @@ pub struct AsyncMessage {
 
     /// Raw payload data of the message
     pub data: Vec<u8>,
+
+    /// Recurrence of the message, or None if the message is handled only once
+    pub recurrence: Option<AsyncMessageRecurrence>,
+}
+
+/// Recurrence of an asynchronous message that is rescheduled automatically after being taken for execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AsyncMessageRecurrence {
+    /// Number of periods between two occurrences of the message
+    pub period: u64,
+
+    /// Maximal number of occurrences that remain to be scheduled after the current one
+    pub remaining_count: u64,
+
+    /// Prepaid gas remaining for the next occurrences, each of which consumes `max_gas` from it.
+    /// `gas_budget * gas_price` is reimbursed to the sender when the message is discarded.
+    pub gas_budget: u64,
 }
 
 impl AsyncMessage {
@@ -171,6 +188,50 @@ impl AsyncMessage {
             self.emission_index,
         )
     }
+
+    /// Takes the next occurrence of a recurring message that was taken for execution at `slot`.
+    /// The next occurrence keeps the ID of the message, does not carry any coins,
+    /// and has its validity range shifted to start `recurrence.period` periods after `slot`.
+    ///
+    /// If the message can be rescheduled, its recurrence is moved to the returned occurrence.
+    /// Otherwise, it is left untouched so that the remaining gas budget can be reimbursed.
+    ///
+    /// # Returns
+    /// The next occurrence of the message, or None if there is no recurrence, no remaining repetition or not enough gas budget
+    pub fn take_next_occurrence(&mut self, slot: Slot) -> Option<AsyncMessage> {
+        let recurrence = self.recurrence.as_ref()?;
+        if recurrence.period == 0
+            || recurrence.remaining_count == 0
+            || recurrence.gas_budget < self.max_gas
+        {
+            return None;
+        }
+        let shift = slot
+            .period
+            .saturating_sub(self.validity_start.period)
+            .checked_add(recurrence.period)?;
+        let validity_start = Slot::new(
+            self.validity_start.period.checked_add(shift)?,
+            self.validity_start.thread,
+        );
+        let validity_end = Slot::new(
+            self.validity_end.period.checked_add(shift)?,
+            self.validity_end.thread,
+        );
+        let recurrence = AsyncMessageRecurrence {
+            period: recurrence.period,
+            remaining_count: recurrence.remaining_count - 1,
+            gas_budget: recurrence.gas_budget - self.max_gas,
+        };
+        self.recurrence = None;
+        Some(AsyncMessage {
+            coins: Amount::default(),
+            validity_start,
+            validity_end,
+            recurrence: Some(recurrence),
+            ..self.clone()
+        })
+    }
 }
 
 impl SerializeCompact for AsyncMessage {
@@ -222,6 +283,17 @@ impl SerializeCompact for AsyncMessage {
         // data
         res.extend(&self.data);
 
+        // recurrence
+        match &self.recurrence {
+            Some(recurrence) => {
+                res.push(1u8);
+                res.extend(recurrence.period.to_varint_bytes());
+                res.extend(recurrence.remaining_count.to_varint_bytes());
+                res.extend(recurrence.gas_budget.to_varint_bytes());
+            }
+            None => res.push(0u8),
+        }
+
         Ok(res)
     }
 }
@@ -302,6 +374,33 @@ impl DeserializeCompact for AsyncMessage {
             ));
         };
 
+        // recurrence
+        let has_recurrence = *buffer
+            .get(cursor)
+            .ok_or_else(|| ModelsError::SerializeError("buffer ended prematurely".into()))?;
+        cursor += 1;
+        let recurrence = match has_recurrence {
+            0u8 => None,
+            1u8 => {
+                let (period, delta) = u64::from_varint_bytes(&buffer[cursor..])?;
+                cursor += delta;
+                let (remaining_count, delta) = u64::from_varint_bytes(&buffer[cursor..])?;
+                cursor += delta;
+                let (gas_budget, delta) = u64::from_varint_bytes(&buffer[cursor..])?;
+                cursor += delta;
+                Some(AsyncMessageRecurrence {
+                    period,
+                    remaining_count,
+                    gas_budget,
+                })
+            }
+            _ => {
+                return Err(ModelsError::SerializeError(
+                    "invalid recurrence flag".into(),
+                ))
+            }
+        };
+
         Ok((
             AsyncMessage {
                 emission_slot,
@@ -315,6 +414,7 @@ impl DeserializeCompact for AsyncMessage {
                 validity_start,
                 validity_end,
                 data,
+                recurrence,
             },
             cursor,
         ))
@@ -8,33 +8,198 @@ use crate::{
     message::{AsyncMessage, AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer},
 };
 use massa_models::{
-    constants::default::ASYNC_POOL_PART_SIZE_MESSAGE_BYTES, DeserializeCompact, ModelsError,
-    SerializeCompact, Slot,
+    constants::default::ASYNC_POOL_PART_SIZE_MESSAGE_BYTES, Amount, DeserializeCompact,
+    ModelsError, SerializeCompact, Slot,
 };
 use massa_serialization::{Deserializer, Serializer};
 use nom::multi::many0;
+use rocksdb::{ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB};
+use std::cmp::Reverse;
 use std::collections::BTreeMap;
-use std::ops::Bound::{Excluded, Unbounded};
+use std::sync::Arc;
+
+const ASYNC_POOL_CF: &str = "async_pool";
+const VALIDITY_START_INDEX_CF: &str = "async_pool_validity_start";
+const VALIDITY_END_INDEX_CF: &str = "async_pool_validity_end";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const MESSAGE_ERROR: &str = "critical: stored asynchronous message is malformed";
+const SERIALIZE_ERROR: &str = "critical: asynchronous message serialization failed";
+
+/// Size in bytes of a message key: `(u64::MAX - priority, period, thread, emission_index)`
+const MESSAGE_KEY_SIZE_BYTES: usize = 8 + 8 + 1 + 8;
+
+/// Size in bytes of a slot key: `(period, thread)`
+const SLOT_KEY_SIZE_BYTES: usize = 8 + 1;
+
+/// Serializes an `AsyncMessageId` into a key whose lexicographical byte order
+/// matches the order of `AsyncMessageId` (decreasing priority)
+fn message_key(id: &AsyncMessageId) -> [u8; MESSAGE_KEY_SIZE_BYTES] {
+    let (Reverse(priority), slot, index) = id;
+    let mut key = [0u8; MESSAGE_KEY_SIZE_BYTES];
+    key[..8].copy_from_slice(&(u64::MAX - priority.to_raw()).to_be_bytes());
+    key[8..SLOT_KEY_SIZE_BYTES + 8].copy_from_slice(&slot_key(slot));
+    key[17..].copy_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Deserializes an `AsyncMessageId` from a key produced by `message_key`
+fn message_id_from_key(key: &[u8]) -> AsyncMessageId {
+    let read_u64 = |bytes: &[u8]| u64::from_be_bytes(bytes.try_into().expect(MESSAGE_ERROR));
+    (
+        Reverse(Amount::from_raw(u64::MAX - read_u64(&key[..8]))),
+        Slot::new(read_u64(&key[8..16]), key[16]),
+        read_u64(&key[17..MESSAGE_KEY_SIZE_BYTES]),
+    )
+}
+
+/// Serializes a `Slot` into a key whose lexicographical byte order matches the order of slots
+fn slot_key(slot: &Slot) -> [u8; SLOT_KEY_SIZE_BYTES] {
+    let mut key = [0u8; SLOT_KEY_SIZE_BYTES];
+    key[..8].copy_from_slice(&slot.period.to_be_bytes());
+    key[8] = slot.thread;
+    key
+}
+
+/// Serializes the key of a message in a validity index: the indexed validity bound followed by the message key,
+/// so that the index is sorted by validity bound first and by decreasing priority second
+fn index_key(
+    bound: &Slot,
+    id: &AsyncMessageId,
+) -> [u8; SLOT_KEY_SIZE_BYTES + MESSAGE_KEY_SIZE_BYTES] {
+    let mut key = [0u8; SLOT_KEY_SIZE_BYTES + MESSAGE_KEY_SIZE_BYTES];
+    key[..SLOT_KEY_SIZE_BYTES].copy_from_slice(&slot_key(bound));
+    key[SLOT_KEY_SIZE_BYTES..].copy_from_slice(&message_key(id));
+    key
+}
 
 /// Represents a pool of sorted messages in a deterministic way.
 /// The final asynchronous pool is attached to the output of the latest final slot within the context of massa-final-state.
 /// Nodes must bootstrap the final message pool when they join the network.
-#[derive(Debug, Clone)]
+///
+/// Messages are stored on disk in the final ledger RocksDB instance, keyed so that they are iterated by decreasing priority.
+/// Two index column families sort them by `validity_start` and by `validity_end`,
+/// so that the messages that can be executed or that expired at a given slot are found without scanning the whole pool.
+#[derive(Debug)]
 pub struct AsyncPool {
     /// Asynchronous pool configuration
     config: AsyncPoolConfig,
 
-    /// Messages sorted by decreasing ID (decreasing priority)
-    pub(crate) messages: BTreeMap<AsyncMessageId, AsyncMessage>,
+    /// Shared handle to the final ledger database, in which the messages and their indexes are stored
+    db: Arc<DB>,
+
+    /// Number of messages in the pool
+    length: u64,
 }
 
 impl AsyncPool {
-    /// Creates an empty `AsyncPool`
-    pub fn new(config: AsyncPoolConfig) -> AsyncPool {
-        AsyncPool {
-            config,
-            messages: Default::default(),
-        }
+    /// Gets the descriptors of the column families in which the pool is stored.
+    /// The database given to `AsyncPool::new` must have been opened with them.
+    pub fn column_families() -> Vec<ColumnFamilyDescriptor> {
+        vec![
+            ColumnFamilyDescriptor::new(ASYNC_POOL_CF, Options::default()),
+            ColumnFamilyDescriptor::new(VALIDITY_START_INDEX_CF, Options::default()),
+            ColumnFamilyDescriptor::new(VALIDITY_END_INDEX_CF, Options::default()),
+        ]
+    }
+
+    /// Opens the `AsyncPool` stored in `db`, which is empty if it was never written to
+    ///
+    /// # Arguments
+    /// * `config`: asynchronous pool configuration
+    /// * `db`: database opened with the column families of `AsyncPool::column_families()`
+    pub fn new(config: AsyncPoolConfig, db: Arc<DB>) -> AsyncPool {
+        // count the messages that were already stored
+        let handle = db.cf_handle(ASYNC_POOL_CF).expect(CF_ERROR);
+        let length = db.iterator_cf(handle, IteratorMode::Start).count() as u64;
+
+        AsyncPool { config, db, length }
+    }
+
+    /// Gets the maximal number of messages in the pool
+    pub fn max_length(&self) -> u64 {
+        self.config.max_length
+    }
+
+    /// Gets the number of messages in the pool
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Checks whether the pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Gets a message of the pool by its ID
+    pub fn get_message(&self, id: &AsyncMessageId) -> Option<AsyncMessage> {
+        let handle = self.db.cf_handle(ASYNC_POOL_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, message_key(id))
+            .expect(CRUD_ERROR)
+            .map(|bytes| {
+                AsyncMessage::from_bytes_compact(&bytes)
+                    .expect(MESSAGE_ERROR)
+                    .0
+            })
+    }
+
+    /// Iterates over the messages of the pool starting at `mode`
+    fn iter_mode(
+        &self,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = (AsyncMessageId, AsyncMessage)> + '_ {
+        let handle = self.db.cf_handle(ASYNC_POOL_CF).expect(CF_ERROR);
+        self.db.iterator_cf(handle, mode).map(|(key, bytes)| {
+            (
+                message_id_from_key(&key),
+                AsyncMessage::from_bytes_compact(&bytes)
+                    .expect(MESSAGE_ERROR)
+                    .0,
+            )
+        })
+    }
+
+    /// Iterates over the messages of the pool by decreasing priority
+    pub fn iter(&self) -> impl Iterator<Item = (AsyncMessageId, AsyncMessage)> + '_ {
+        self.iter_mode(IteratorMode::Start)
+    }
+
+    /// Iterates over the messages of the pool by increasing priority
+    pub fn iter_rev(&self) -> impl Iterator<Item = (AsyncMessageId, AsyncMessage)> + '_ {
+        self.iter_mode(IteratorMode::End)
+    }
+
+    /// Iterates over the entries of a validity index whose indexed bound is lower or equal to `slot`
+    fn iter_index_until(
+        &self,
+        index_cf: &str,
+        slot: Slot,
+    ) -> impl Iterator<Item = (AsyncMessageId, Box<[u8]>)> + '_ {
+        let handle = self.db.cf_handle(index_cf).expect(CF_ERROR);
+        let max_key = slot_key(&slot);
+        self.db
+            .iterator_cf(handle, IteratorMode::Start)
+            .take_while(move |(key, _)| key[..SLOT_KEY_SIZE_BYTES] <= max_key[..])
+            .map(|(key, value)| (message_id_from_key(&key[SLOT_KEY_SIZE_BYTES..]), value))
+    }
+
+    /// Gets the IDs of the messages whose validity interval has ended at a given slot, that is with `validity_end <= slot`.
+    /// Only the beginning of the `validity_end` index is read.
+    pub fn get_expired_message_ids(&self, slot: Slot) -> Vec<AsyncMessageId> {
+        self.iter_index_until(VALIDITY_END_INDEX_CF, slot)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Gets the IDs of the messages that can be executed at a given slot, that is with `validity_start <= slot < validity_end`.
+    /// Only the beginning of the `validity_start` index is read, the `validity_end` of each message being stored as the index value.
+    pub fn get_executable_message_ids(&self, slot: Slot) -> Vec<AsyncMessageId> {
+        let min_end_key = slot_key(&slot);
+        self.iter_index_until(VALIDITY_START_INDEX_CF, slot)
+            .filter(|(_, validity_end)| validity_end[..] > min_end_key[..])
+            .map(|(id, _)| id)
+            .collect()
     }
 
     /// Applies pre-compiled `AsyncPoolChanges` to the pool without checking for overflows.
@@ -43,93 +208,93 @@ impl AsyncPool {
     /// # arguments
     /// * `changes`: `AsyncPoolChanges` listing all asynchronous pool changes (message insertions/deletions)
     pub fn apply_changes_unchecked(&mut self, changes: AsyncPoolChanges) {
+        let mut batch = WriteBatch::default();
+        self.apply_changes_to_batch(changes, &mut batch);
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    /// Adds the operations applying pre-compiled `AsyncPoolChanges` to the pool to a batch, without checking for overflows.
+    /// The message count is updated right away: the batch must be written to the database before the pool is read again.
+    ///
+    /// # arguments
+    /// * `changes`: `AsyncPoolChanges` listing all asynchronous pool changes (message insertions/deletions)
+    /// * `batch`: the operation batch to update
+    pub fn apply_changes_to_batch(&mut self, changes: AsyncPoolChanges, batch: &mut WriteBatch) {
+        // only the last change of each message matters
+        let mut new_messages = BTreeMap::new();
         for change in changes.0.into_iter() {
             match change {
                 // add a new message to the pool
-                Change::Add(msg_id, msg) => {
-                    self.messages.insert(msg_id, msg);
-                }
+                Change::Add(msg_id, msg) => new_messages.insert(msg_id, Some(msg)),
 
                 // delete a message from the pool
-                Change::Delete(msg_id) => {
-                    self.messages.remove(&msg_id);
+                Change::Delete(msg_id) => new_messages.insert(msg_id, None),
+            };
+        }
+
+        let handle = self.db.cf_handle(ASYNC_POOL_CF).expect(CF_ERROR);
+        let start_handle = self.db.cf_handle(VALIDITY_START_INDEX_CF).expect(CF_ERROR);
+        let end_handle = self.db.cf_handle(VALIDITY_END_INDEX_CF).expect(CF_ERROR);
+        for (msg_id, new_message) in new_messages {
+            // remove the index entries of the stored version of the message
+            match (self.get_message(&msg_id), &new_message) {
+                (Some(previous), _) => {
+                    batch.delete_cf(start_handle, index_key(&previous.validity_start, &msg_id));
+                    batch.delete_cf(end_handle, index_key(&previous.validity_end, &msg_id));
+                    if new_message.is_none() {
+                        batch.delete_cf(handle, message_key(&msg_id));
+                        self.length -= 1;
+                    }
                 }
+                (None, Some(_)) => self.length += 1,
+                (None, None) => {}
+            }
+
+            // write the new version of the message along with its index entries
+            if let Some(msg) = new_message {
+                batch.put_cf(
+                    handle,
+                    message_key(&msg_id),
+                    msg.to_bytes_compact().expect(SERIALIZE_ERROR),
+                );
+                batch.put_cf(
+                    start_handle,
+                    index_key(&msg.validity_start, &msg_id),
+                    slot_key(&msg.validity_end),
+                );
+                batch.put_cf(end_handle, index_key(&msg.validity_end, &msg_id), b"");
             }
         }
     }
 
-    /// Settles a slot, adding new messages to the pool and returning expired and excess ones.
-    /// This method is called at the end of a slot execution to apply the list of emitted messages,
-    /// and get the list of pruned messages for `coins` reimbursement.
-    ///
-    /// # arguments
-    /// * `slot`: used to filter out expired messages, not stored
-    /// * `new_messages`: list of `AsyncMessage` to add to the pool
-    ///
-    /// # returns
-    /// The list of `(message_id, message)` that were eliminated from the pool after the changes were applied, sorted in the following order:
-    /// * expired messages from the pool, in priority order (from highest to lowest priority)
-    /// * expired messages from `new_messages` (in the order they appear in `new_messages`)
-    /// * excess messages after inserting all remaining `new_messages`, in priority order (from highest to lowest priority)
-    pub fn settle_slot(
+    /// Inserts messages into the pool without checking for overflows
+    pub(crate) fn insert_messages_unchecked(
         &mut self,
-        slot: Slot,
-        new_messages: &mut Vec<(AsyncMessageId, AsyncMessage)>,
-    ) -> Vec<(AsyncMessageId, AsyncMessage)> {
-        // Filter out all messages for which the validity end is expired.
-        // Note that the validity_end bound is NOT included in the validity interval of the message.
-        let mut eliminated: Vec<_> = self
-            .messages
-            .drain_filter(|_k, v| slot >= v.validity_end)
-            .chain(new_messages.drain_filter(|(_k, v)| slot >= v.validity_end))
-            .collect();
-
-        // Insert new messages into the pool
-        self.messages.extend(new_messages.clone());
-
-        // Truncate message pool to its max size, removing non-prioritary items
-        let excess_count = self
-            .messages
-            .len()
-            .saturating_sub(self.config.max_length as usize);
-        eliminated.reserve_exact(excess_count);
-        for _ in 0..excess_count {
-            eliminated.push(self.messages.pop_last().unwrap()); // will not panic (checked at excess_count computation)
-        }
-        eliminated
+        messages: Vec<(AsyncMessageId, AsyncMessage)>,
+    ) {
+        self.apply_changes_unchecked(AsyncPoolChanges(
+            messages
+                .into_iter()
+                .map(|(msg_id, msg)| Change::Add(msg_id, msg))
+                .collect(),
+        ));
     }
 
-    /// Takes the best possible batch of messages to execute, with gas limits and slot validity filtering.
-    /// The returned messages are removed from the pool.
-    /// This method is used at the beginning of a slot execution to list asynchronous messages to execute.
-    ///
-    /// # arguments
-    /// * `slot`: select only messages that are valid within this slot
-    /// * `available_gas`: maximum amount of available gas
-    ///
-    /// # returns
-    /// A vector of messages, sorted from the most priority to the least priority
-    pub fn take_batch_to_execute(
-        &mut self,
-        slot: Slot,
-        mut available_gas: u64,
-    ) -> Vec<(AsyncMessageId, AsyncMessage)> {
-        // gather all selected items and remove them from self.messages
-        // iterate in decreasing priority order
-        self.messages
-            .drain_filter(|_, msg| {
-                // check available gas and validity period
-                if available_gas >= msg.max_gas
-                    && slot >= msg.validity_start
-                    && slot < msg.validity_end
-                {
-                    available_gas -= msg.max_gas;
-                    true
-                } else {
-                    false
-                }
-            })
-            .collect()
+    /// Deletes every message of the pool
+    pub fn reset(&mut self) {
+        let mut batch = WriteBatch::default();
+        for cf in [
+            ASYNC_POOL_CF,
+            VALIDITY_START_INDEX_CF,
+            VALIDITY_END_INDEX_CF,
+        ] {
+            let handle = self.db.cf_handle(cf).expect(CF_ERROR);
+            for (key, _) in self.db.iterator_cf(handle, IteratorMode::Start) {
+                batch.delete_cf(handle, key);
+            }
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+        self.length = 0;
     }
 
     /// Used for bootstrap
@@ -139,22 +304,27 @@ impl AsyncPool {
         &self,
         last_id: Option<AsyncMessageId>,
     ) -> Result<(Vec<u8>, Option<AsyncMessageId>), ModelsError> {
-        let last_id = if let Some(last_id) = last_id {
-            Excluded(last_id)
-        } else if self.messages.first_key_value().is_some() {
-            Unbounded
-        } else {
-            return Ok((Vec::new(), None));
+        let messages: Box<dyn Iterator<Item = (AsyncMessageId, AsyncMessage)>> = match last_id {
+            Some(last_id) => {
+                let key = message_key(&last_id);
+                Box::new(
+                    self.iter_mode(IteratorMode::From(&key, Direction::Forward))
+                        .skip_while(move |(id, _)| *id == last_id),
+                )
+            }
+            None => Box::new(self.iter()),
         };
         let mut part = Vec::new();
         let mut next_last_id = None;
         let id_async_message_serializer = AsyncMessageIdSerializer::new();
 
-        for (id, message) in self.messages.range((last_id, Unbounded)) {
+        for (id, message) in messages {
             if part.len() < ASYNC_POOL_PART_SIZE_MESSAGE_BYTES as usize {
-                part.extend(id_async_message_serializer.serialize(id)?);
+                part.extend(id_async_message_serializer.serialize(&id)?);
                 part.extend(message.to_bytes_compact()?);
-                next_last_id = Some(*id);
+                next_last_id = Some(id);
+            } else {
+                break;
             }
         }
         Ok((part, next_last_id))
@@ -193,8 +363,9 @@ impl AsyncPool {
             Ok((&rest[delta..], (id, message)))
         })(part)?;
         if rest.is_empty() {
-            self.messages.extend(messages);
-            Ok(self.messages.last_key_value().map(|(id, _)| *id))
+            let last_id = messages.last().map(|(id, _)| *id);
+            self.insert_messages_unchecked(messages);
+            Ok(last_id)
         } else {
             Err(ModelsError::SerializeError(
                 "pool part deserialization has data left".to_string(),
@@ -203,33 +374,107 @@ impl AsyncPool {
     }
 }
 
-#[test]
-fn test_take_batch() {
+#[cfg(test)]
+mod tests {
+    use super::{message_id_from_key, message_key, AsyncPool};
+    use crate::{AsyncMessage, AsyncPoolChanges, AsyncPoolConfig, Change};
     use massa_hash::Hash;
     use massa_models::{Address, Amount, Slot};
+    use rocksdb::{Options, DB};
+    use std::sync::Arc;
+    use tempfile::TempDir;
 
-    let config = AsyncPoolConfig { max_length: 10 };
-    let mut pool = AsyncPool::new(config);
-    let address = Address(Hash::compute_from(b"abc"));
-    for i in 1..10 {
-        pool.messages.insert(
-            (std::cmp::Reverse(Amount::from_raw(i)), Slot::new(0, 0), 0),
-            AsyncMessage {
-                emission_slot: Slot::new(0, 0),
-                emission_index: 0,
-                sender: address,
-                destination: address,
-                handler: "function".to_string(),
-                validity_start: Slot::new(1, 0),
-                validity_end: Slot::new(3, 0),
-                max_gas: i,
-                gas_price: Amount::from_raw(1),
-                coins: Amount::from_raw(0),
-                data: Vec::new(),
-            },
-        );
-    }
-    assert_eq!(pool.messages.len(), 9);
-    pool.take_batch_to_execute(Slot::new(2, 0), 19);
-    assert_eq!(pool.messages.len(), 6);
+    /// Opens a database holding the column families of the pool
+    fn open_db(temp_dir: &TempDir) -> Arc<DB> {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        Arc::new(
+            DB::open_cf_descriptors(&db_opts, temp_dir.path(), AsyncPool::column_families())
+                .unwrap(),
+        )
+    }
+
+    /// Checks that the pool is iterated by decreasing priority, that its validity indexes are kept up to date
+    /// and that it survives being reopened
+    #[test]
+    fn test_pool_db() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = AsyncPoolConfig { max_length: 100 };
+        let address = Address(Hash::compute_from(b"abc"));
+        let messages: Vec<_> = (0..10)
+            .map(|i| {
+                let message = AsyncMessage {
+                    emission_slot: Slot::new(i % 3, (i % 2) as u8),
+                    emission_index: i,
+                    sender: address,
+                    destination: address,
+                    handler: "function".to_string(),
+                    validity_start: Slot::new(1 + i % 2, 0),
+                    validity_end: Slot::new(3 + i % 2, 0),
+                    max_gas: i % 4,
+                    gas_price: Amount::from_raw(1),
+                    coins: Amount::from_raw(0),
+                    data: Vec::new(),
+                    recurrence: None,
+                };
+                (message.compute_id(), message)
+            })
+            .collect();
+        for (id, _) in &messages {
+            assert_eq!(message_id_from_key(&message_key(id)), *id);
+        }
+
+        let mut pool = AsyncPool::new(config.clone(), open_db(&temp_dir));
+        let mut changes = AsyncPoolChanges::default();
+        for (id, message) in &messages {
+            changes.push_add(*id, message.clone());
+        }
+        changes.push_delete(messages[0].0);
+        pool.apply_changes_unchecked(changes);
+        assert_eq!(pool.len(), 9);
+        assert!(pool.get_message(&messages[0].0).is_none());
+        assert!(pool.get_message(&messages[1].0).is_some());
+
+        let mut expected_ids: Vec<_> = messages[1..].iter().map(|(id, _)| *id).collect();
+        expected_ids.sort();
+        let ids: Vec<_> = pool.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, expected_ids);
+        expected_ids.reverse();
+        let ids: Vec<_> = pool.iter_rev().map(|(id, _)| id).collect();
+        assert_eq!(ids, expected_ids);
+
+        // query the validity indexes
+        let ids_matching = |predicate: &dyn Fn(&AsyncMessage) -> bool| {
+            let mut ids: Vec<_> = messages[1..]
+                .iter()
+                .filter(|(_, msg)| predicate(msg))
+                .map(|(id, _)| *id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        for period in 0..6 {
+            let slot = Slot::new(period, 1);
+            let mut ids = pool.get_executable_message_ids(slot);
+            ids.sort();
+            assert_eq!(
+                ids,
+                ids_matching(&|msg| msg.validity_start <= slot && slot < msg.validity_end)
+            );
+            let mut ids = pool.get_expired_message_ids(slot);
+            ids.sort();
+            assert_eq!(ids, ids_matching(&|msg| msg.validity_end <= slot));
+        }
+
+        // reopen the pool from disk
+        drop(pool);
+        let mut pool = AsyncPool::new(config, open_db(&temp_dir));
+        assert_eq!(pool.len(), 9);
+        pool.apply_changes_unchecked(AsyncPoolChanges(vec![Change::Delete(messages[1].0)]));
+        assert_eq!(pool.len(), 8);
+        assert!(!pool
+            .get_expired_message_ids(Slot::new(5, 0))
+            .contains(&messages[1].0));
+    }
 }
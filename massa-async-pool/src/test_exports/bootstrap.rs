@@ -1,21 +1,23 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use std::{cmp::Reverse, collections::BTreeMap, str::FromStr};
+use std::{cmp::Reverse, collections::BTreeMap, str::FromStr, sync::Arc};
 
 use crate::{AsyncMessage, AsyncPool, AsyncPoolConfig};
 use massa_models::{constants::THREAD_COUNT, Address, Amount, Slot};
 use massa_signature::{derive_public_key, generate_random_private_key};
 use rand::Rng;
+use rocksdb::DB;
 
 /// This file defines tools to test the asynchronous pool bootstrap
 
-/// Creates a `AsyncPool` from pre-set values
+/// Creates a `AsyncPool` from pre-set values, stored in a database opened with the column families of `AsyncPool::column_families()`
 pub fn create_async_pool(
     config: AsyncPoolConfig,
+    db: Arc<DB>,
     messages: BTreeMap<(Reverse<Amount>, Slot, u64), AsyncMessage>,
 ) -> AsyncPool {
-    let mut async_pool = AsyncPool::new(config);
-    async_pool.messages = messages;
+    let mut async_pool = AsyncPool::new(config, db);
+    async_pool.insert_messages_unchecked(messages.into_iter().collect());
     async_pool
 }
 
@@ -39,6 +41,7 @@ pub fn get_random_message() -> AsyncMessage {
         validity_start: Slot::new(2, 0),
         validity_end: Slot::new(4, 0),
         data: vec![1, 2, 3],
+        recurrence: None,
     }
 }
 
@@ -61,16 +64,14 @@ pub fn assert_eq_async_message(v1: &AsyncMessage, v2: &AsyncMessage) {
     );
     assert_eq!(v1.validity_end, v2.validity_end, "validity_end mismatch");
     assert_eq!(v1.data, v2.data, "data mismatch");
+    assert_eq!(v1.recurrence, v2.recurrence, "recurrence mismatch");
 }
 
 /// asserts that two `AsyncPool` are equal
 pub fn assert_eq_async_pool_bootstrap_state(v1: &AsyncPool, v2: &AsyncPool) {
-    assert_eq!(
-        v1.messages.len(),
-        v2.messages.len(),
-        "message count mismatch"
-    );
-    for (val1, val2) in v1.messages.iter().zip(v2.messages.iter()) {
-        assert_eq_async_message(val1.1, val2.1);
+    assert_eq!(v1.len(), v2.len(), "message count mismatch");
+    for (val1, val2) in v1.iter().zip(v2.iter()) {
+        assert_eq!(val1.0, val2.0, "message id mismatch");
+        assert_eq_async_message(&val1.1, &val2.1);
     }
 }
@@ -70,13 +70,7 @@ async fn stream_final_state(
                     let last_last_async_id = write_final_state
                         .async_pool
                         .set_pool_part(async_pool_part.as_bytes())?;
-                    write_final_state
-                        .ledger
-                        .apply_changes(final_state_changes.ledger_changes.clone(), slot);
-                    write_final_state
-                        .async_pool
-                        .apply_changes_unchecked(final_state_changes.async_pool_changes.clone());
-                    write_final_state.slot = slot;
+                    write_final_state.apply_changes_unchecked(final_state_changes.clone(), slot);
                     if let Some(BootstrapClientMessage::AskFinalStatePart {
                         last_key: old_key,
                         last_async_message_id: old_message_id,
@@ -365,6 +359,8 @@ pub async fn get_state(
             slot: None,
             last_async_message_id: None,
         });
+    // the final state stored on disk is replaced by the bootstrapped one
+    final_state.write().reset();
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state.clone());
     loop {
         for (addr, pub_key) in shuffled_list.iter() {
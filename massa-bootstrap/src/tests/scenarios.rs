@@ -40,7 +40,7 @@ async fn test_bootstrap_server() {
 
     let (consensus_cmd_tx, mut consensus_cmd_rx) = mpsc::channel::<ConsensusCommand>(5);
     let (network_cmd_tx, mut network_cmd_rx) = mpsc::channel::<NetworkCommand>(5);
-    let final_state_bootstrap = get_random_final_state_bootstrap(2);
+    let (final_state_bootstrap, _keep_dir) = get_random_final_state_bootstrap(2);
     let final_state = Arc::new(RwLock::new(final_state_bootstrap));
 
     let (bootstrap_establisher, bootstrap_interface) = mock_establisher::new();
@@ -58,7 +58,8 @@ async fn test_bootstrap_server() {
     .unwrap()
     .unwrap();
 
-    let final_state_client = Arc::new(RwLock::new(FinalState::default()));
+    let (final_state_client, _keep_client_dir) = FinalState::sample();
+    let final_state_client = Arc::new(RwLock::new(final_state_client));
     let final_state_client_thread = final_state_client.clone();
 
     // launch the get_state process
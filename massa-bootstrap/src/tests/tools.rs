@@ -4,6 +4,7 @@ use super::mock_establisher::Duplex;
 use crate::settings::BootstrapSettings;
 use bitvec::prelude::*;
 use massa_async_pool::test_exports::{create_async_pool, get_random_message};
+use massa_async_pool::AsyncPool;
 use massa_consensus_exports::commands::ConsensusCommand;
 use massa_final_state::test_exports::create_final_state;
 use massa_final_state::FinalState;
@@ -35,6 +36,7 @@ use std::{
     collections::BTreeMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
 };
+use tempfile::TempDir;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 use tokio::{sync::mpsc::Receiver, time::sleep};
@@ -64,11 +66,12 @@ fn get_random_ledger_entry() -> LedgerEntry {
         parallel_balance,
         bytecode,
         datastore,
+        locked_storage_cost: Amount::from_raw(rng.gen::<u64>()),
     }
 }
 
-/// generates a random bootstrap state for the final state
-pub fn get_random_final_state_bootstrap(thread_count: u8) -> FinalState {
+/// generates a random bootstrap state for the final state, along with its disk directory
+pub fn get_random_final_state_bootstrap(thread_count: u8) -> (FinalState, TempDir) {
     let mut rng = rand::thread_rng();
 
     let mut sorted_ledger = HashMap::new();
@@ -82,14 +85,21 @@ pub fn get_random_final_state_bootstrap(thread_count: u8) -> FinalState {
     }
 
     let slot = Slot::new(rng.gen::<u64>(), rng.gen_range(0..thread_count));
-    let final_ledger = create_final_ledger(Some(sorted_ledger), Default::default());
-    let async_pool = create_async_pool(Default::default(), messages);
-    create_final_state(
+    let (final_ledger, disk_ledger) = create_final_ledger(
+        Some(sorted_ledger),
         Default::default(),
-        slot,
-        final_ledger,
-        async_pool,
-        VecDeque::new(),
+        AsyncPool::column_families(),
+    );
+    let async_pool = create_async_pool(Default::default(), final_ledger.get_db(), messages);
+    (
+        create_final_state(
+            Default::default(),
+            slot,
+            final_ledger,
+            async_pool,
+            VecDeque::new(),
+        ),
+        disk_ledger,
     )
 }
 
@@ -0,0 +1,292 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Non-interactive batch mode: runs a sequence of commands over a single client connection.
+//!
+//! Batch input has one command per line, written as in the interactive mode.
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! The output of a command can be kept in a variable with `name = command args...`
+//! and reused in the parameters of the following commands:
+//! * `${name}`: the whole output (strings are inserted as is, other values as JSON)
+//! * `${name.field.0}`: a part of the JSON output, fields and array indexes separated by dots
+//! * `${_}`: the output of the previous successful command
+
+use crate::cmds::Command;
+use anyhow::{anyhow, bail, Result};
+use console::style;
+use massa_sdk::Client;
+use massa_wallet::Wallet;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+/// Name of the variable holding the output of the previous successful command
+const PREVIOUS_OUTPUT: &str = "_";
+
+/// What to do when a command of the batch fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// stop the batch at the first failure
+    Stop,
+    /// report the failure and run the next commands
+    Continue,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stop" => Ok(ErrorPolicy::Stop),
+            "continue" => Ok(ErrorPolicy::Continue),
+            _ => bail!(
+                "invalid error policy '{}', expected 'stop' or 'continue'",
+                s
+            ),
+        }
+    }
+}
+
+/// JSON line printed for every command in JSON mode
+#[derive(Serialize)]
+struct BatchJsonLine {
+    /// line number in the batch input
+    line: usize,
+    /// command as written in the batch input
+    command: String,
+    /// output of the command, if it succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    /// error, if the command failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A batch line, parsed
+#[derive(Debug, PartialEq, Eq)]
+struct BatchLine {
+    /// variable receiving the command output
+    variable: Option<String>,
+    /// command name
+    command: String,
+    /// command parameters, with variables not yet substituted
+    parameters: Vec<String>,
+}
+
+/// Parses a batch line. Returns `None` for empty lines and comments.
+fn parse_line(line: &str) -> Result<Option<BatchLine>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let variable = if words.len() >= 2 && words[1] == "=" {
+        let name = words[0];
+        if name == PREVIOUS_OUTPUT || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            bail!("invalid variable name '{}'", name);
+        }
+        words.drain(..2);
+        Some(name.to_string())
+    } else {
+        None
+    };
+    let (command, parameters) = words
+        .split_first()
+        .ok_or_else(|| anyhow!("missing command after variable assignment"))?;
+    Ok(Some(BatchLine {
+        variable,
+        command: command.to_string(),
+        parameters: parameters.iter().map(|p| p.to_string()).collect(),
+    }))
+}
+
+/// Replaces the `${...}` references of a parameter by the corresponding variable values
+fn substitute(parameter: &str, variables: &HashMap<String, Value>) -> Result<String> {
+    let mut res = String::new();
+    let mut rest = parameter;
+    while let Some(start) = rest.find("${") {
+        res.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unterminated variable reference in '{}'", parameter))?
+            + start;
+        let reference = &rest[start + 2..end];
+        let mut path = reference.split('.');
+        // does not panic: split always yields at least one item
+        let name = path.next().unwrap();
+        let mut value = variables
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown variable '{}'", name))?;
+        for key in path {
+            value = match value {
+                Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                Value::Object(fields) => fields.get(key),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow!("'{}' not found in the output", reference))?;
+        }
+        match value {
+            Value::String(s) => res.push_str(s),
+            other => res.push_str(&other.to_string()),
+        }
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    Ok(res)
+}
+
+/// Runs the commands of a batch file, or of the standard input if `path` is `-`.
+///
+/// In JSON mode, one JSON object is printed per command.
+/// Fails if any command failed.
+pub(crate) async fn run(
+    client: &Client,
+    wallet: &mut Wallet,
+    path: &Path,
+    json: bool,
+    error_policy: ErrorPolicy,
+) -> Result<()> {
+    let input: Box<dyn AsyncBufRead + Unpin> = if path == Path::new("-") {
+        Box::new(BufReader::new(tokio::io::stdin()))
+    } else {
+        Box::new(BufReader::new(tokio::fs::File::open(path).await?))
+    };
+    let mut lines = input.lines();
+    let mut variables: HashMap<String, Value> = HashMap::new();
+    let mut line_number = 0;
+    let mut failures = 0;
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        let res = match parse_line(&line) {
+            Ok(Some(batch_line)) => run_line(client, wallet, &batch_line, &variables, json)
+                .await
+                .map(|output| (batch_line.variable, output)),
+            Ok(None) => continue,
+            Err(e) => Err(e),
+        };
+        match res {
+            Ok((variable, output)) => {
+                if json {
+                    print_json_line(BatchJsonLine {
+                        line: line_number,
+                        command: line.trim().to_string(),
+                        result: Some(output.clone()),
+                        error: None,
+                    })?;
+                }
+                if let Some(variable) = variable {
+                    variables.insert(variable, output.clone());
+                }
+                variables.insert(PREVIOUS_OUTPUT.to_string(), output);
+            }
+            Err(e) => {
+                failures += 1;
+                if json {
+                    print_json_line(BatchJsonLine {
+                        line: line_number,
+                        command: line.trim().to_string(),
+                        result: None,
+                        error: Some(format!("{:?}", e)),
+                    })?;
+                } else {
+                    println!(
+                        "{}",
+                        style(format!("Error at line {}: {}", line_number, e)).red()
+                    );
+                }
+                if error_policy == ErrorPolicy::Stop {
+                    bail!("batch stopped at line {}", line_number);
+                }
+            }
+        }
+    }
+    if failures > 0 {
+        bail!("{} command(s) of the batch failed", failures);
+    }
+    Ok(())
+}
+
+/// Runs one batch command and returns its output as JSON
+async fn run_line(
+    client: &Client,
+    wallet: &mut Wallet,
+    batch_line: &BatchLine,
+    variables: &HashMap<String, Value>,
+    json: bool,
+) -> Result<Value> {
+    let command: Command = batch_line
+        .command
+        .parse()
+        .map_err(|_| anyhow!("command '{}' not found", batch_line.command))?;
+    let parameters = batch_line
+        .parameters
+        .iter()
+        .map(|p| substitute(p, variables))
+        .collect::<Result<Vec<String>>>()?;
+    let output = command.run(client, wallet, &parameters, json).await?;
+    if !json {
+        output.pretty_print();
+    }
+    output.to_json_value()
+}
+
+fn print_json_line(line: BatchJsonLine) -> Result<()> {
+    println!("{}", serde_json::to_string(&line)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_lines() {
+        assert_eq!(parse_line("  ").unwrap(), None);
+        assert_eq!(parse_line("# get_status").unwrap(), None);
+        assert_eq!(
+            parse_line("get_addresses A B").unwrap(),
+            Some(BatchLine {
+                variable: None,
+                command: "get_addresses".into(),
+                parameters: vec!["A".into(), "B".into()],
+            })
+        );
+        assert_eq!(
+            parse_line("ops = send_transaction A B 1 0").unwrap(),
+            Some(BatchLine {
+                variable: Some("ops".into()),
+                command: "send_transaction".into(),
+                parameters: vec!["A".into(), "B".into(), "1".into(), "0".into()],
+            })
+        );
+        assert!(parse_line("ops =").is_err());
+        assert!(parse_line("_ = get_status").is_err());
+        assert!(parse_line("a-b = get_status").is_err());
+    }
+
+    #[test]
+    fn substitute_variables() {
+        let mut variables = HashMap::new();
+        variables.insert("addr".to_string(), Value::String("A12".into()));
+        variables.insert("ops".to_string(), serde_json::json!(["O1", "O2"]));
+        variables.insert(
+            "status".to_string(),
+            serde_json::json!({"config": {"thread_count": 32}}),
+        );
+
+        assert_eq!(substitute("plain", &variables).unwrap(), "plain");
+        assert_eq!(substitute("${addr}", &variables).unwrap(), "A12");
+        assert_eq!(substitute("x${ops.1}y", &variables).unwrap(), "xO2y");
+        assert_eq!(
+            substitute("${status.config.thread_count}", &variables).unwrap(),
+            "32"
+        );
+        assert_eq!(substitute("${ops}", &variables).unwrap(), r#"["O1","O2"]"#);
+        assert!(substitute("${missing}", &variables).is_err());
+        assert!(substitute("${ops.5}", &variables).is_err());
+        assert!(substitute("${addr", &variables).is_err());
+    }
+}
@@ -0,0 +1,565 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Bulk payouts: sends a transaction to every recipient of a CSV file and follows them to finality.
+//!
+//! The CSV file has one `ReceiverAddress,Amount` line per payment.
+//! Empty lines, lines starting with `#` and a first line starting with `recipient` (header) are ignored.
+//!
+//! Operations are sent in chunks of at most `max_operations_per_message` operations
+//! (bounded by the client `max_operations_per_request` setting).
+//! Successive chunks get decreasing expire periods, within half of the operation validity window,
+//! so that they do not all expire at the same period.
+//! Identical payments get the previous expire period so that every payment has its own operation ID.
+//!
+//! Operations are followed until they are final, expired or rejected.
+//! An operation still not final a few periods after its expire period
+//! (included in a block that never became final, or in a stale block) is reported as unsettled.
+//!
+//! The report is a CSV file with one line per payment, rewritten after every status update.
+
+use crate::settings::SETTINGS;
+use anyhow::{anyhow, bail, Result};
+use console::style;
+use massa_models::api::OperationInfo;
+use massa_models::prehash::{Map, Set};
+use massa_models::signed::Signable;
+use massa_models::timeslots::get_current_latest_block_slot;
+use massa_models::{Address, Amount, BlockId, OperationId};
+use massa_sdk::{get_expire_period, get_finality_margin, Client, OperationBuilder};
+use massa_wallet::Wallet;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// A payment read from the CSV file
+#[derive(Debug, PartialEq, Eq)]
+struct Payment {
+    /// recipient of the coins
+    recipient: Address,
+    /// amount of coins sent
+    amount: Amount,
+}
+
+/// Status of a payment operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// the node refused the operation
+    Rejected,
+    /// sent but not in a block yet
+    Pending,
+    /// included in a block that is not final yet
+    Included,
+    /// included in a final block
+    Final,
+    /// expired before being included in a block
+    Expired,
+    /// still not known to be final long after the expire period
+    /// (included in a block that never became final, or node unreachable)
+    Unsettled,
+}
+
+impl TransactionStatus {
+    /// true if the status cannot change anymore
+    fn is_settled(&self) -> bool {
+        matches!(
+            self,
+            TransactionStatus::Rejected
+                | TransactionStatus::Final
+                | TransactionStatus::Expired
+                | TransactionStatus::Unsettled
+        )
+    }
+}
+
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            TransactionStatus::Rejected => "rejected",
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Included => "included",
+            TransactionStatus::Final => "final",
+            TransactionStatus::Expired => "expired",
+            TransactionStatus::Unsettled => "unsettled",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// Outcome of one payment
+#[derive(Debug, Serialize)]
+pub struct TransactionReport {
+    /// recipient of the coins
+    pub recipient: Address,
+    /// amount of coins sent
+    pub amount: Amount,
+    /// operation ID
+    pub operation_id: OperationId,
+    /// last period the operation can be included in
+    pub expire_period: u64,
+    /// current status
+    pub status: TransactionStatus,
+    /// block the operation was included in, if any
+    pub block_id: Option<BlockId>,
+    /// reason of the rejection, if any
+    pub error: Option<String>,
+}
+
+/// Outcome of a bulk payout
+#[derive(Debug, Serialize)]
+pub struct BatchTransactionsReport {
+    /// where the report was written
+    pub report_path: PathBuf,
+    /// outcome of every payment, in the CSV file order
+    pub transactions: Vec<TransactionReport>,
+}
+
+impl BatchTransactionsReport {
+    /// number of payments with the given status
+    fn count(&self, status: TransactionStatus) -> usize {
+        self.transactions
+            .iter()
+            .filter(|tx| tx.status == status)
+            .count()
+    }
+
+    /// writes the report as CSV
+    async fn write(&self) -> Result<()> {
+        let mut content =
+            "recipient,amount,operation_id,expire_period,status,block_id,error\n".to_string();
+        for tx in &self.transactions {
+            content.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                tx.recipient,
+                tx.amount,
+                tx.operation_id,
+                tx.expire_period,
+                tx.status,
+                tx.block_id.map(|id| id.to_string()).unwrap_or_default(),
+                csv_escape(tx.error.as_deref().unwrap_or_default())
+            ));
+        }
+        tokio::fs::write(&self.report_path, content).await?;
+        Ok(())
+    }
+}
+
+impl Display for BatchTransactionsReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Transactions: {}", self.transactions.len())?;
+        writeln!(f, "    Final: {}", self.count(TransactionStatus::Final))?;
+        writeln!(
+            f,
+            "    Included: {}",
+            self.count(TransactionStatus::Included)
+        )?;
+        writeln!(f, "    Pending: {}", self.count(TransactionStatus::Pending))?;
+        writeln!(f, "    Expired: {}", self.count(TransactionStatus::Expired))?;
+        writeln!(
+            f,
+            "    Unsettled: {}",
+            self.count(TransactionStatus::Unsettled)
+        )?;
+        writeln!(
+            f,
+            "    Rejected: {}",
+            self.count(TransactionStatus::Rejected)
+        )?;
+        writeln!(f, "Report written to {}", self.report_path.display())?;
+        Ok(())
+    }
+}
+
+/// quotes a CSV field if needed
+fn csv_escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parses the payments of a CSV file
+fn parse_payments(content: &str) -> Result<Vec<Payment>> {
+    let mut payments = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (index == 0 && line.starts_with("recipient"))
+        {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 2 {
+            bail!("line {}: expected 'ReceiverAddress,Amount'", index + 1);
+        }
+        payments.push(Payment {
+            recipient: fields[0]
+                .parse()
+                .map_err(|e| anyhow!("line {}: invalid address: {}", index + 1, e))?,
+            amount: fields[1]
+                .parse()
+                .map_err(|e| anyhow!("line {}: invalid amount: {}", index + 1, e))?,
+        });
+    }
+    Ok(payments)
+}
+
+/// Status and block of an operation from what the node knows about it
+///
+/// # Arguments
+/// * `info`: operation info returned by the node, if any
+/// * `expire_period`: last period the operation can be included in
+/// * `current_period`: period of the current wall-clock slot
+/// * `finality_margin`: number of periods after `expire_period` an included operation
+///   is waited for before being reported as unsettled
+fn operation_status(
+    info: Option<&OperationInfo>,
+    expire_period: u64,
+    current_period: u64,
+    finality_margin: u64,
+) -> (TransactionStatus, Option<BlockId>) {
+    match info {
+        Some(info) if info.is_final => (TransactionStatus::Final, info.in_blocks.first().copied()),
+        Some(info) if !info.in_blocks.is_empty() => {
+            if current_period > expire_period + finality_margin {
+                (
+                    TransactionStatus::Unsettled,
+                    info.in_blocks.first().copied(),
+                )
+            } else {
+                (TransactionStatus::Included, info.in_blocks.first().copied())
+            }
+        }
+        _ if current_period > expire_period => (TransactionStatus::Expired, None),
+        _ => (TransactionStatus::Pending, None),
+    }
+}
+
+/// Sends a transaction from `sender` to every recipient of the CSV file,
+/// waits until every transaction is final, expired or rejected, and writes the report.
+pub(crate) async fn send_batch_transactions(
+    client: &Client,
+    wallet: &Wallet,
+    sender: Address,
+    csv_path: &Path,
+    fee: Amount,
+    report_path: Option<PathBuf>,
+    json: bool,
+) -> Result<BatchTransactionsReport> {
+    let payments = parse_payments(&tokio::fs::read_to_string(csv_path).await?)?;
+    if payments.is_empty() {
+        bail!("no payment found in {}", csv_path.display());
+    }
+    let cfg = match client.public.get_status().await {
+        Ok(node_status) => node_status.config,
+        Err(e) => bail!("check if your node is running: {}", e),
+    };
+    if wallet.find_associated_private_key(sender).is_none() {
+        bail!("Missing private key");
+    }
+
+    if !json {
+        let total = payments.iter().try_fold(Amount::default(), |acc, payment| {
+            acc.checked_add(payment.amount)?.checked_add(fee)
+        });
+        match total {
+            Some(total) => {
+                if let Ok(addresses_info) = client.public.get_addresses(vec![sender]).await {
+                    match addresses_info.get(0) {
+                        Some(info) => {
+                            if info.ledger_info.candidate_ledger_info.balance < total {
+                                println!("{}: some operations may be rejected due to insufficient balance", style("WARNING").yellow());
+                            }
+                        }
+                        None => println!(
+                            "{}: address {} not found",
+                            style("WARNING").yellow(),
+                            sender
+                        ),
+                    }
+                }
+            }
+            None => println!(
+                "{}: the total amount hit the limit overflow, some operations will certainly be rejected",
+                style("WARNING").yellow()
+            ),
+        }
+    }
+
+    // older nodes do not expose `max_operations_per_message` (read as 0)
+    let chunk_size = match cfg.max_operations_per_message as usize {
+        0 => SETTINGS.max_operations_per_request,
+        max_operations_per_message => {
+            max_operations_per_message.min(SETTINGS.max_operations_per_request)
+        }
+    }
+    .max(1);
+    let chunk_count = (payments.len() + chunk_size - 1) / chunk_size;
+    let mut report = BatchTransactionsReport {
+        report_path: report_path.unwrap_or_else(|| csv_path.with_extension("report.csv")),
+        transactions: Vec::with_capacity(payments.len()),
+    };
+    let expire_spread = (cfg.operation_validity_periods / 2).max(1);
+    let mut operation_ids: Set<OperationId> = Set::default();
+    for (chunk_index, chunk) in payments.chunks(chunk_size).enumerate() {
+        let max_expire_period = get_expire_period(&cfg, sender)?;
+        let chunk_expire_period =
+            max_expire_period.saturating_sub(chunk_index as u64 % expire_spread);
+        let mut operations = Vec::with_capacity(chunk.len());
+        for payment in chunk {
+            let mut expire_period = chunk_expire_period;
+            loop {
+                let operation = OperationBuilder::transaction(payment.recipient, payment.amount)
+                    .fee(fee)
+                    .expire_period(expire_period)
+                    .sign_with_wallet(&client.public, wallet, sender)
+                    .await?;
+                let operation_id = operation.content.compute_id()?;
+                // identical payments would get the same ID: spread them over the previous periods
+                if !operation_ids.insert(operation_id) {
+                    if max_expire_period - expire_period + 1 >= cfg.operation_validity_periods {
+                        bail!(
+                            "too many identical payments of {} to {}: they cannot all get their own operation ID",
+                            payment.amount,
+                            payment.recipient
+                        );
+                    }
+                    expire_period -= 1;
+                    continue;
+                }
+                report.transactions.push(TransactionReport {
+                    recipient: payment.recipient,
+                    amount: payment.amount,
+                    operation_id,
+                    expire_period,
+                    status: TransactionStatus::Pending,
+                    block_id: None,
+                    error: None,
+                });
+                operations.push(operation);
+                break;
+            }
+        }
+        let sent = report.transactions.len() - chunk.len()..report.transactions.len();
+        match client.public.send_operations(operations).await {
+            Ok(_) => {
+                if !json {
+                    println!(
+                        "Sent chunk {}/{} ({} operations)",
+                        chunk_index + 1,
+                        chunk_count,
+                        chunk.len()
+                    );
+                }
+            }
+            Err(e) => {
+                if !json {
+                    println!(
+                        "{}",
+                        style(format!(
+                            "Chunk {}/{} rejected: {}",
+                            chunk_index + 1,
+                            chunk_count,
+                            e
+                        ))
+                        .red()
+                    );
+                }
+                for tx in &mut report.transactions[sent] {
+                    tx.status = TransactionStatus::Rejected;
+                    tx.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+    report.write().await?;
+
+    let finality_margin = get_finality_margin(&cfg);
+
+    // follow the operations until they are all settled
+    while report.transactions.iter().any(|tx| !tx.status.is_settled()) {
+        tokio::time::sleep(cfg.t0.to_duration()).await;
+        let current_period =
+            get_current_latest_block_slot(cfg.thread_count, cfg.t0, cfg.genesis_timestamp, 0)? // clock compensation is zero
+                .map(|slot| slot.period)
+                .unwrap_or(0);
+        let unsettled: Vec<OperationId> = report
+            .transactions
+            .iter()
+            .filter(|tx| !tx.status.is_settled())
+            .map(|tx| tx.operation_id)
+            .collect();
+        let mut infos: Map<OperationId, OperationInfo> = Map::default();
+        let mut poll_failed = false;
+        for ids in unsettled.chunks(chunk_size) {
+            match client.public.get_operations(ids.to_vec()).await {
+                Ok(operations_info) => {
+                    for info in operations_info {
+                        match infos.get_mut(&info.id) {
+                            Some(known) => known.extend(&info),
+                            None => {
+                                infos.insert(info.id, info);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    if !json {
+                        println!(
+                            "{}: could not get the operations status: {}",
+                            style("WARNING").yellow(),
+                            e
+                        );
+                    }
+                    poll_failed = true;
+                    break;
+                }
+            }
+        }
+        if poll_failed {
+            // without news from the node, give up once every deadline has passed
+            if report
+                .transactions
+                .iter()
+                .filter(|tx| !tx.status.is_settled())
+                .all(|tx| current_period > tx.expire_period + finality_margin)
+            {
+                for tx in report
+                    .transactions
+                    .iter_mut()
+                    .filter(|tx| !tx.status.is_settled())
+                {
+                    tx.status = TransactionStatus::Unsettled;
+                }
+                report.write().await?;
+            }
+            continue;
+        }
+        let mut changed = false;
+        for tx in report
+            .transactions
+            .iter_mut()
+            .filter(|tx| !tx.status.is_settled())
+        {
+            let (status, block_id) = operation_status(
+                infos.get(&tx.operation_id),
+                tx.expire_period,
+                current_period,
+                finality_margin,
+            );
+            if status != tx.status || block_id != tx.block_id {
+                tx.status = status;
+                tx.block_id = block_id;
+                changed = true;
+            }
+        }
+        if changed {
+            report.write().await?;
+            if !json {
+                println!(
+                    "Final: {}, included: {}, pending: {}, expired: {}, unsettled: {}, rejected: {}",
+                    report.count(TransactionStatus::Final),
+                    report.count(TransactionStatus::Included),
+                    report.count(TransactionStatus::Pending),
+                    report.count(TransactionStatus::Expired),
+                    report.count(TransactionStatus::Unsettled),
+                    report.count(TransactionStatus::Rejected)
+                );
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_csv() {
+        let recipient = Address::from_public_key(&massa_signature::derive_public_key(
+            &massa_signature::generate_random_private_key(),
+        ));
+        let content = format!(
+            "recipient,amount\n# pool rewards\n\n{},1.5\n {} , 2 \n",
+            recipient, recipient
+        );
+        assert_eq!(
+            parse_payments(&content).unwrap(),
+            vec![
+                Payment {
+                    recipient,
+                    amount: "1.5".parse().unwrap()
+                },
+                Payment {
+                    recipient,
+                    amount: "2".parse().unwrap()
+                }
+            ]
+        );
+        assert!(parse_payments(&format!("{}", recipient)).is_err());
+        assert!(parse_payments(&format!("{},abc", recipient)).is_err());
+        assert!(parse_payments("recipient,amount\nrecipient,1").is_err());
+    }
+
+    #[test]
+    fn follow_operation_status() {
+        massa_models::init_serialization_context(massa_models::SerializationContext::default());
+        let private_key = massa_signature::generate_random_private_key();
+        let public_key = massa_signature::derive_public_key(&private_key);
+        let (id, operation) = massa_models::signed::Signed::new_signed(
+            massa_models::Operation {
+                sender_public_key: public_key,
+                fee: Amount::default(),
+                expire_period: 10,
+                op: massa_models::OperationType::Transaction {
+                    recipient_address: Address::from_public_key(&public_key),
+                    amount: Amount::default(),
+                },
+            },
+            &private_key,
+        )
+        .unwrap();
+        let block_id = BlockId(massa_hash::Hash::compute_from(b"block"));
+        let mut info = OperationInfo {
+            id,
+            in_pool: false,
+            in_blocks: vec![block_id],
+            is_final: false,
+            operation,
+        };
+
+        // not included: pending until expired
+        assert_eq!(
+            operation_status(None, 10, 10, 4),
+            (TransactionStatus::Pending, None)
+        );
+        assert_eq!(
+            operation_status(None, 10, 11, 4),
+            (TransactionStatus::Expired, None)
+        );
+
+        // included but never final: given up on after the finality margin
+        assert_eq!(
+            operation_status(Some(&info), 10, 14, 4),
+            (TransactionStatus::Included, Some(block_id))
+        );
+        assert_eq!(
+            operation_status(Some(&info), 10, 15, 4),
+            (TransactionStatus::Unsettled, Some(block_id))
+        );
+
+        info.is_final = true;
+        assert_eq!(
+            operation_status(Some(&info), 10, 15, 4),
+            (TransactionStatus::Final, Some(block_id))
+        );
+    }
+
+    #[test]
+    fn escape_csv_fields() {
+        assert_eq!(csv_escape("plain error"), "plain error");
+        assert_eq!(csv_escape("a, b"), "\"a, b\"");
+        assert_eq!(csv_escape("say \"no\""), "\"say \"\"no\"\"\"");
+    }
+}
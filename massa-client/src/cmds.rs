@@ -1,27 +1,28 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::batch_transactions::send_batch_transactions;
 use crate::repl::Output;
 use anyhow::{anyhow, bail, Result};
 use console::style;
-use massa_models::api::{AddressInfo, CompactAddressInfo, EventFilter};
+use massa_models::api::{
+    AddressInfo, AsyncMessageFilter, CompactAddressInfo, EventFilter, NodeStatus,
+};
 use massa_models::api::{ReadOnlyBytecodeExecution, ReadOnlyCall};
 use massa_models::node::NodeId;
 use massa_models::prehash::Map;
-use massa_models::timeslots::get_current_latest_block_slot;
-use massa_models::{
-    Address, Amount, BlockId, EndorsementId, Operation, OperationId, OperationType, Slot,
-};
-use massa_sdk::Client;
+use massa_models::{Address, Amount, BlockId, EndorsementId, OperationId, OperationType, Slot};
+use massa_sdk::{generate_private_key_in_thread, Client, OperationBuilder, WaitMode};
 use massa_signature::{generate_random_private_key, PrivateKey, PublicKey};
 use massa_time::MassaTime;
 use massa_wallet::{Wallet, WalletError};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write as _;
 use std::fmt::{Debug, Display};
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumProperty, EnumString};
 
@@ -140,6 +141,13 @@ pub enum Command {
     )]
     get_operations,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "OperationId1 OperationId2 ..."),
+        message = "show the execution receipts of smart contract operations (success, gas used, coins spent ...)"
+    )]
+    get_operation_receipts,
+
     #[strum(
         ascii_case_insensitive,
         props(
@@ -149,6 +157,29 @@ pub enum Command {
     )]
     get_filtered_sc_output_event,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "sender=Address destination=Address start=Slot end=Slot"),
+        message = "show pending asynchronous messages with various filters"
+    )]
+    get_async_messages,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(
+            args = "start=Slot emitter_address=Address caller_address=Address operation_id=OperationId"
+        ),
+        message = "follow new events emitted by smart contracts with various filters, until Ctrl+C is pressed"
+    )]
+    watch_events,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[RefreshSeconds]"),
+        message = "show a dashboard of the node state, refreshed every RefreshSeconds (at least 1, default 5) until Ctrl+C is pressed"
+    )]
+    dashboard,
+
     #[strum(
         ascii_case_insensitive,
         message = "show wallet info (private keys, public keys, addresses, balances ...)"
@@ -157,7 +188,8 @@ pub enum Command {
 
     #[strum(
         ascii_case_insensitive,
-        message = "generate a private key and add it into the wallet"
+        props(args = "[--thread N] [--workers N]"),
+        message = "generate a private key and add it into the wallet, optionally searching with N workers for a key whose address is in thread N"
     )]
     wallet_generate_private_key,
 
@@ -184,28 +216,37 @@ pub enum Command {
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "Address RollCount Fee"),
+        props(args = "Address RollCount Fee [--wait included|final]"),
         message = "buy rolls with wallet address"
     )]
     buy_rolls,
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "Address RollCount Fee"),
+        props(args = "Address RollCount Fee [--wait included|final]"),
         message = "sell rolls with wallet address"
     )]
     sell_rolls,
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "SenderAddress ReceiverAddress Amount Fee"),
+        props(args = "SenderAddress ReceiverAddress Amount Fee [--wait included|final]"),
         message = "send coins from a wallet address"
     )]
     send_transaction,
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "SenderAddress PathToBytecode MaxGas GasPrice Coins Fee",),
+        props(args = "SenderAddress PathToCsv Fee PathToReport"),
+        message = "send coins from a wallet address to every recipient of a CSV file (ReceiverAddress,Amount lines) and follow them until final, report path is optional"
+    )]
+    send_batch_transactions,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(
+            args = "SenderAddress PathToBytecode MaxGas GasPrice Coins Fee [--wait included|final]",
+        ),
         message = "create and send an operation containing byte code"
     )]
     send_smart_contract,
@@ -213,7 +254,7 @@ pub enum Command {
     #[strum(
         ascii_case_insensitive,
         props(
-            args = "SenderAddress TargetAddress FunctionName Parameter MaxGas GasPrice Coins Fee",
+            args = "SenderAddress TargetAddress FunctionName Parameter MaxGas GasPrice Coins Fee [--wait included|final]",
         ),
         message = "create and send an operation to call a function of a smart contract"
     )]
@@ -221,15 +262,17 @@ pub enum Command {
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "PathToBytecode MaxGas GasPrice Address",),
-        message = "execute byte code, address is optional. Nothing is really executed on chain"
+        props(args = "PathToBytecode MaxGas GasPrice Address [--trace]",),
+        message = "execute byte code, address is optional. Nothing is really executed on chain. --trace shows the ABI calls made by the execution"
     )]
     read_only_smart_contract,
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "TargetAddress TargetFunction Parameter MaxGas GasPrice SenderAddress",),
-        message = "call a smart contract function, sender address is optional. Nothing is really executed on chain"
+        props(
+            args = "TargetAddress TargetFunction Parameter MaxGas GasPrice SenderAddress [--trace]",
+        ),
+        message = "call a smart contract function, sender address is optional. Nothing is really executed on chain. --trace shows the ABI calls made by the execution"
     )]
     read_only_call,
 
@@ -325,6 +368,18 @@ impl Display for ExtendedWallet {
 }
 
 impl Command {
+    /// true if the command sends an operation
+    fn sends_operation(&self) -> bool {
+        matches!(
+            self,
+            Command::buy_rolls
+                | Command::sell_rolls
+                | Command::send_transaction
+                | Command::send_smart_contract
+                | Command::call_smart_contract
+        )
+    }
+
     /// Display the help of the command
     /// with fancy colors and so on
     pub(crate) fn help(&self) {
@@ -360,6 +415,11 @@ impl Command {
         parameters: &[String],
         json: bool,
     ) -> Result<Box<dyn Output>> {
+        let (parameters, wait) = extract_option::<WaitMode>(parameters, "--wait")?;
+        if wait.is_some() && !self.sends_operation() {
+            bail!("--wait is only supported by the commands sending an operation");
+        }
+        let parameters = &parameters[..];
         match self {
             Command::exit => process::exit(0),
 
@@ -549,6 +609,14 @@ impl Command {
                 }
             }
 
+            Command::get_operation_receipts => {
+                let operations = parse_vec::<OperationId>(parameters)?;
+                match client.public.get_operation_receipts(operations).await {
+                    Ok(receipts) => Ok(Box::new(receipts)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_filtered_sc_output_event => {
                 let p_list: [&str; 5] = [
                     "start",
@@ -557,15 +625,7 @@ impl Command {
                     "caller_address",
                     "operation_id",
                 ];
-                let mut p: HashMap<&str, &str> = HashMap::new();
-                for v in parameters {
-                    let s: Vec<&str> = v.split('=').collect();
-                    if s.len() == 2 && p_list.contains(&s[0]) {
-                        p.insert(s[0], s[1]);
-                    } else {
-                        bail!("invalid parameter");
-                    }
-                }
+                let p = parse_key_value_parameters(parameters, &p_list)?;
                 let filter = EventFilter {
                     start: parse_value(&p, p_list[0]),
                     end: parse_value(&p, p_list[1]),
@@ -579,6 +639,54 @@ impl Command {
                 }
             }
 
+            Command::get_async_messages => {
+                let p_list: [&str; 4] = ["sender", "destination", "start", "end"];
+                let p = parse_key_value_parameters(parameters, &p_list)?;
+                let filter = AsyncMessageFilter {
+                    sender: parse_value(&p, p_list[0]),
+                    destination: parse_value(&p, p_list[1]),
+                    start: parse_value(&p, p_list[2]),
+                    end: parse_value(&p, p_list[3]),
+                };
+                match client.public.get_async_messages(filter).await {
+                    Ok(messages) => Ok(Box::new(messages)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::watch_events => {
+                let p_list: [&str; 4] =
+                    ["start", "emitter_address", "caller_address", "operation_id"];
+                let p = parse_key_value_parameters(parameters, &p_list)?;
+                let filter = EventFilter {
+                    start: parse_value(&p, p_list[0]),
+                    end: None,
+                    emitter_address: parse_value(&p, p_list[1]),
+                    original_caller_address: parse_value(&p, p_list[2]),
+                    original_operation_id: parse_value(&p, p_list[3]),
+                };
+                let status = match client.public.get_status().await {
+                    Ok(node_status) => node_status,
+                    Err(e) => rpc_error!(e),
+                };
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => Ok(Box::new(())),
+                    res = watch_events(client, filter, &status, json) => res,
+                }
+            }
+
+            Command::dashboard => {
+                let refresh = match parameters {
+                    [] => 5,
+                    [seconds] => seconds.parse::<u64>()?,
+                    _ => bail!("wrong param numbers"),
+                };
+                if refresh == 0 {
+                    bail!("the refresh period must be at least 1 second");
+                }
+                crate::dashboard::run(client, Duration::from_secs(refresh), json).await
+            }
+
             Command::wallet_info => {
                 if !json {
                     client_warning!("do not share your private key");
@@ -596,7 +704,26 @@ impl Command {
             }
 
             Command::wallet_generate_private_key => {
-                let key = generate_random_private_key();
+                let (parameters, thread) = extract_option::<u8>(parameters, "--thread")?;
+                let (parameters, workers) = extract_option::<usize>(&parameters, "--workers")?;
+                if !parameters.is_empty() {
+                    bail!("wrong param numbers")
+                }
+                let key = match thread {
+                    Some(thread) => {
+                        let thread_count = match client.public.get_status().await {
+                            Ok(status) => status.config.thread_count,
+                            Err(e) => rpc_error!(e),
+                        };
+                        let workers = workers.unwrap_or(1);
+                        tokio::task::spawn_blocking(move || {
+                            generate_private_key_in_thread(thread, thread_count, workers)
+                        })
+                        .await??
+                    }
+                    None if workers.is_some() => bail!("--workers requires --thread"),
+                    None => generate_random_private_key(),
+                };
                 let ad = wallet.add_private_key(key)?;
                 if json {
                     Ok(Box::new(ad.to_string()))
@@ -689,6 +816,7 @@ impl Command {
                     OperationType::RollBuy { roll_count },
                     fee,
                     addr,
+                    wait,
                     json,
                 )
                 .await
@@ -723,6 +851,7 @@ impl Command {
                     OperationType::RollSell { roll_count },
                     fee,
                     addr,
+                    wait,
                     json,
                 )
                 .await
@@ -770,10 +899,35 @@ impl Command {
                     },
                     fee,
                     addr,
+                    wait,
                     json,
                 )
                 .await
             }
+            Command::send_batch_transactions => {
+                if parameters.len() != 3 && parameters.len() != 4 {
+                    bail!("wrong number of parameters");
+                }
+                let addr = parameters[0].parse::<Address>()?;
+                let csv_path = parameters[1].parse::<PathBuf>()?;
+                let fee = parameters[2].parse::<Amount>()?;
+                let report_path = if let Some(path) = parameters.get(3) {
+                    Some(path.parse::<PathBuf>()?)
+                } else {
+                    None
+                };
+                let report = send_batch_transactions(
+                    client,
+                    wallet,
+                    addr,
+                    &csv_path,
+                    fee,
+                    report_path,
+                    json,
+                )
+                .await?;
+                Ok(Box::new(report))
+            }
             Command::when_episode_ends => {
                 let end = match client.public.get_status().await {
                     Ok(node_status) => node_status.config.end_timestamp,
@@ -860,6 +1014,7 @@ impl Command {
                     },
                     fee,
                     addr,
+                    wait,
                     json,
                 )
                 .await
@@ -921,6 +1076,7 @@ impl Command {
                     },
                     fee,
                     addr,
+                    wait,
                     json,
                 )
                 .await
@@ -938,6 +1094,7 @@ impl Command {
                 }
             }
             Command::read_only_smart_contract => {
+                let (parameters, trace) = extract_flag(parameters, "--trace");
                 if parameters.len() != 3 && parameters.len() != 4 {
                     bail!("wrong number of parameters");
                 }
@@ -958,6 +1115,10 @@ impl Command {
                         simulated_gas_price,
                         bytecode,
                         address,
+                        trace,
+                        state_overrides: Vec::new(),
+                        simulated_slot: None,
+                        final_slot: None,
                     })
                     .await
                 {
@@ -966,6 +1127,7 @@ impl Command {
                 }
             }
             Command::read_only_call => {
+                let (parameters, trace) = extract_flag(parameters, "--trace");
                 if parameters.len() != 5 && parameters.len() != 6 {
                     bail!("wrong number of parameters");
                 }
@@ -989,6 +1151,11 @@ impl Command {
                         parameter,
                         max_gas,
                         simulated_gas_price,
+                        trace,
+                        state_overrides: Vec::new(),
+                        simulated_slot: None,
+                        final_slot: None,
+                        coins: Amount::default(),
                     })
                     .await
                 {
@@ -1031,44 +1198,146 @@ async fn send_operation(
     op: OperationType,
     fee: Amount,
     addr: Address,
+    wait: Option<WaitMode>,
     json: bool,
 ) -> Result<Box<dyn Output>> {
-    let cfg = match client.public.get_status().await {
-        Ok(node_status) => node_status,
-        Err(e) => rpc_error!(e),
+    let operation = OperationBuilder::new(op).fee(fee);
+    match wait {
+        Some(mode) => {
+            if !json {
+                println!("Sending operation, waiting until it is {}...", mode);
+            }
+            let outcome = operation
+                .send_and_wait(&client.public, wallet, addr, mode)
+                .await?;
+            Ok(Box::new(outcome))
+        }
+        None => {
+            let operation_id = match operation.send(&client.public, wallet, addr).await {
+                Ok(operation_id) => operation_id,
+                Err(e) => rpc_error!(e),
+            };
+            if !json {
+                println!("Sent operation IDs:");
+            }
+            Ok(Box::new(vec![operation_id]))
+        }
     }
-    .config;
+}
 
-    let slot = get_current_latest_block_slot(cfg.thread_count, cfg.t0, cfg.genesis_timestamp, 0)? // clock compensation is zero
-        .unwrap_or_else(|| Slot::new(0, 0));
-    let mut expire_period = slot.period + cfg.operation_validity_periods;
-    if slot.thread >= addr.get_thread(cfg.thread_count) {
-        expire_period += 1;
-    };
-    let sender_public_key = match wallet.find_associated_public_key(addr) {
-        Some(pk) => *pk,
-        None => bail!("Missing public key"),
-    };
+/// removes an option and its value, such as `--wait included|final`, from the parameters
+fn extract_option<T>(parameters: &[String], name: &str) -> Result<(Vec<String>, Option<T>)>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match parameters.iter().position(|p| p == name) {
+        Some(index) => {
+            let value = parameters
+                .get(index + 1)
+                .ok_or_else(|| anyhow!("missing value of {}", name))?
+                .parse()?;
+            let mut remaining = parameters.to_vec();
+            remaining.drain(index..index + 2);
+            Ok((remaining, Some(value)))
+        }
+        None => Ok((parameters.to_vec(), None)),
+    }
+}
 
-    let op = wallet.create_operation(
-        Operation {
-            sender_public_key,
-            fee,
-            expire_period,
-            op,
-        },
-        addr,
-    )?;
-
-    match client.public.send_operations(vec![op]).await {
-        Ok(operation_ids) => {
-            if !json {
-                println!("Sent operation IDs:");
+/// removes a flag from the parameters, returns the remaining parameters and whether the flag was present
+fn extract_flag(parameters: &[String], name: &str) -> (Vec<String>, bool) {
+    let remaining: Vec<String> = parameters.iter().filter(|p| *p != name).cloned().collect();
+    let present = remaining.len() != parameters.len();
+    (remaining, present)
+}
+
+/// prints the events matching the filter as they are emitted, forever
+///
+/// By default, only the events emitted from the next slot are printed.
+/// The events of a slot that is not final yet change if the slot is re-executed,
+/// so the recently printed events are remembered by slot and index along with their content:
+/// an event is printed again only if its content changed, with a warning in pretty mode.
+/// Slots more than `delta_f0` slots older than the latest printed event are assumed final and forgotten.
+async fn watch_events(
+    client: &Client,
+    mut filter: EventFilter,
+    status: &NodeStatus,
+    json: bool,
+) -> Result<Box<dyn Output>> {
+    let start = *filter.start.get_or_insert(status.next_slot);
+    let thread_count = status.config.thread_count as u64;
+    // poll once per slot
+    let interval = status
+        .config
+        .t0
+        .checked_div_u64(thread_count)?
+        .to_duration();
+    // recently printed events by slot and index, with their JSON content
+    let mut printed: BTreeMap<(Slot, u64), String> = BTreeMap::new();
+    if !json {
+        println!("Watching events from slot {}...", start);
+    }
+    loop {
+        match client
+            .public
+            .get_filtered_sc_output_event(filter.clone())
+            .await
+        {
+            Ok(mut events) => {
+                events.sort_by_key(|event| (event.context.slot, event.context.index_in_slot));
+                for event in events {
+                    let position = (event.context.slot, event.context.index_in_slot);
+                    let content = serde_json::to_string(&event)?;
+                    match printed.get(&position) {
+                        Some(printed_content) if *printed_content == content => continue,
+                        Some(_) if !json => client_warning!(format!(
+                            "slot {} was re-executed, event {} changed",
+                            position.0, position.1
+                        )),
+                        _ => {}
+                    }
+                    if json {
+                        println!("{}", content);
+                    } else {
+                        println!("{}", event);
+                    }
+                    printed.insert(position, content);
+                }
+                // forget the slots assumed final and only poll the more recent ones
+                if let Some(((latest_slot, _), _)) = printed.iter().next_back() {
+                    let first_tracked_index = (latest_slot.period * thread_count
+                        + latest_slot.thread as u64)
+                        .saturating_sub(status.config.delta_f0);
+                    let first_tracked_slot = Slot::new(
+                        first_tracked_index / thread_count,
+                        (first_tracked_index % thread_count) as u8,
+                    );
+                    printed = printed.split_off(&(first_tracked_slot, 0));
+                    filter.start = Some(start.max(first_tracked_slot));
+                }
             }
-            Ok(Box::new(operation_ids))
+            Err(e) => client_warning!(format!("could not get events: {}", e)),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// parses `key=value` parameters, the keys must be in `keys`
+fn parse_key_value_parameters<'a>(
+    parameters: &'a [String],
+    keys: &[&str],
+) -> Result<HashMap<&'a str, &'a str>> {
+    let mut p: HashMap<&str, &str> = HashMap::new();
+    for v in parameters {
+        let s: Vec<&str> = v.split('=').collect();
+        if s.len() == 2 && keys.contains(&s[0]) {
+            p.insert(s[0], s[1]);
+        } else {
+            bail!("invalid parameter");
         }
-        Err(e) => rpc_error!(e),
     }
+    Ok(p)
 }
 
 /// TODO: ugly utilities functions
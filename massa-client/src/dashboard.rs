@@ -0,0 +1,344 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Terminal dashboard refreshing the state of a node until Ctrl+C is pressed.
+//!
+//! Everything is fetched from the public and private APIs:
+//! `get_status`, `get_graph_interval`, `get_staking_addresses` and `get_addresses`.
+
+use crate::repl::Output;
+use anyhow::Result;
+use console::{style, Term};
+use massa_models::api::{AddressInfo, BlockSummary, IndexedSlot, NodeStatus, TimeInterval};
+use massa_models::{Address, Slot};
+use massa_sdk::Client;
+use serde::Serialize;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Latest known blocks of a thread
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ThreadSlots {
+    /// slot of the latest final block
+    pub final_slot: Option<Slot>,
+    /// slot of the latest block of the blockclique
+    pub candidate_slot: Option<Slot>,
+}
+
+/// State of a staking address of the node
+#[derive(Debug, Serialize)]
+pub struct StakingAddressState {
+    /// the address
+    pub address: Address,
+    /// rolls taken into account for the current cycle
+    pub active_rolls: u64,
+    /// rolls at the latest blocks
+    pub candidate_rolls: u64,
+    /// next slot at which the address creates a block
+    pub next_block_draw: Option<Slot>,
+    /// next slot at which the address creates an endorsement
+    pub next_endorsement_draw: Option<IndexedSlot>,
+    /// (cycle, created blocks, missed blocks) of the latest cycle with production stats
+    pub last_production: Option<(u64, u64, u64)>,
+}
+
+impl StakingAddressState {
+    fn new(info: &AddressInfo) -> Self {
+        StakingAddressState {
+            address: info.address,
+            active_rolls: info.rolls.active_rolls,
+            candidate_rolls: info.rolls.candidate_rolls,
+            next_block_draw: info.block_draws.iter().min().copied(),
+            next_endorsement_draw: info
+                .endorsement_draws
+                .iter()
+                .min_by_key(|draw| (draw.slot, draw.index))
+                .copied(),
+            last_production: info
+                .production_stats
+                .iter()
+                .max_by_key(|stats| stats.cycle)
+                .map(|stats| (stats.cycle, stats.ok_count, stats.nok_count)),
+        }
+    }
+}
+
+/// Everything shown by the dashboard
+#[derive(Debug, Serialize)]
+pub struct Dashboard {
+    /// node status
+    pub status: NodeStatus,
+    /// latest blocks of each thread, empty if the graph could not be fetched
+    pub threads: Vec<ThreadSlots>,
+    /// staking addresses of the node
+    pub staking_addresses: Vec<StakingAddressState>,
+    /// errors of the optional requests (graph, private API)
+    pub warnings: Vec<String>,
+}
+
+impl Dashboard {
+    /// Fetches the state of the node.
+    /// Only the status is required, other failures are reported as warnings.
+    async fn fetch(client: &Client) -> Result<Dashboard> {
+        let status = client.public.get_status().await?;
+        let mut warnings = Vec::new();
+
+        // final blocks lag about `delta_f0 / thread_count` periods behind the latest ones
+        let cfg = &status.config;
+        let periods = cfg.delta_f0 / cfg.thread_count as u64 + 3;
+        let start = status
+            .current_time
+            .saturating_sub(cfg.t0.saturating_mul(periods));
+        let threads = match client
+            .public
+            .get_graph_interval(TimeInterval {
+                start: Some(start),
+                end: None,
+            })
+            .await
+        {
+            Ok(blocks) => thread_slots(&blocks, cfg.thread_count),
+            Err(e) => {
+                warnings.push(format!("could not get the block graph: {}", e));
+                Vec::new()
+            }
+        };
+
+        let staking_addresses = match client.private.get_staking_addresses().await {
+            Ok(addresses) if addresses.is_empty() => Vec::new(),
+            Ok(addresses) => match client
+                .public
+                .get_addresses(addresses.into_iter().collect())
+                .await
+            {
+                Ok(infos) => infos.iter().map(StakingAddressState::new).collect(),
+                Err(e) => {
+                    warnings.push(format!("could not get the staking addresses info: {}", e));
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                warnings.push(format!("could not get the staking addresses: {}", e));
+                Vec::new()
+            }
+        };
+
+        Ok(Dashboard {
+            status,
+            threads,
+            staking_addresses,
+            warnings,
+        })
+    }
+}
+
+/// Computes the latest final and blockclique slots of each thread
+fn thread_slots(blocks: &[BlockSummary], thread_count: u8) -> Vec<ThreadSlots> {
+    let mut threads = vec![ThreadSlots::default(); thread_count as usize];
+    for block in blocks {
+        let thread = match threads.get_mut(block.slot.thread as usize) {
+            Some(thread) => thread,
+            None => continue,
+        };
+        if block.is_final {
+            thread.final_slot = thread.final_slot.max(Some(block.slot));
+        }
+        if block.is_in_blockclique {
+            thread.candidate_slot = thread.candidate_slot.max(Some(block.slot));
+        }
+    }
+    threads
+}
+
+fn display_option<T: Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+impl Display for Dashboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = &self.status;
+        writeln!(
+            f,
+            "{} {} ({})",
+            style("Node").green().bold(),
+            status.node_id,
+            status.version
+        )?;
+        writeln!(f, "Time: {}", status.current_time.to_utc_string())?;
+        writeln!(
+            f,
+            "Cycle: {} / Last slot: {} / Next slot: {}",
+            status.current_cycle,
+            display_option(&status.last_slot),
+            status.next_slot
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "{}", style("Consensus").green().bold())?;
+        writeln!(
+            f,
+            "Cliques: {} / Stakers: {} / Final blocks: {} / Stale blocks: {}",
+            status.consensus_stats.clique_count,
+            status.consensus_stats.staker_count,
+            status.consensus_stats.final_block_count,
+            status.consensus_stats.stale_block_count
+        )?;
+        if !self.threads.is_empty() {
+            writeln!(
+                f,
+                "{:>6} {:>16} {:>16}",
+                "Thread", "Final period", "Candidate period"
+            )?;
+            for (thread, slots) in self.threads.iter().enumerate() {
+                writeln!(
+                    f,
+                    "{:>6} {:>16} {:>16}",
+                    thread,
+                    display_option(&slots.final_slot.map(|slot| slot.period)),
+                    display_option(&slots.candidate_slot.map(|slot| slot.period))
+                )?;
+            }
+        }
+        writeln!(f)?;
+
+        writeln!(f, "{}", style("Network").green().bold())?;
+        writeln!(
+            f,
+            "Peers: {} connected ({} in / {} out) / {} known / {} banned",
+            status.connected_nodes.len(),
+            status.network_stats.in_connection_count,
+            status.network_stats.out_connection_count,
+            status.network_stats.known_peer_count,
+            status.network_stats.banned_peer_count
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "{}", style("Pool").green().bold())?;
+        writeln!(
+            f,
+            "Operations: {} / Endorsements: {}",
+            status.pool_stats.operation_count, status.pool_stats.endorsement_count
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "{}", style("Staking").green().bold())?;
+        if self.staking_addresses.is_empty() {
+            writeln!(f, "No staking address")?;
+        }
+        for staking in &self.staking_addresses {
+            writeln!(f, "{}", staking.address)?;
+            writeln!(
+                f,
+                "\tRolls: {} active / {} candidate",
+                staking.active_rolls, staking.candidate_rolls
+            )?;
+            writeln!(
+                f,
+                "\tNext block draw: {} / Next endorsement draw: {}",
+                display_option(&staking.next_block_draw),
+                display_option(
+                    &staking
+                        .next_endorsement_draw
+                        .map(|draw| format!("{} (index {})", draw.slot, draw.index))
+                )
+            )?;
+            if let Some((cycle, ok_count, nok_count)) = staking.last_production {
+                writeln!(
+                    f,
+                    "\tCycle {}: {} blocks created, {} missed",
+                    cycle, ok_count, nok_count
+                )?;
+            }
+        }
+
+        for warning in &self.warnings {
+            writeln!(f, "{}: {}", style("WARNING").yellow(), warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Refreshes the dashboard every `refresh` until Ctrl+C is pressed.
+/// In JSON mode, the state is printed as one JSON line per refresh.
+pub(crate) async fn run(client: &Client, refresh: Duration, json: bool) -> Result<Box<dyn Output>> {
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => Ok(Box::new(())),
+        res = refresh_forever(client, refresh, json) => res,
+    }
+}
+
+async fn refresh_forever(
+    client: &Client,
+    refresh: Duration,
+    json: bool,
+) -> Result<Box<dyn Output>> {
+    let term = Term::stdout();
+    loop {
+        let dashboard = Dashboard::fetch(client).await;
+        if json {
+            match dashboard {
+                Ok(dashboard) => println!("{}", serde_json::to_string(&dashboard)?),
+                Err(e) => println!("{}", serde_json::json!({ "error": e.to_string() })),
+            }
+        } else {
+            term.clear_screen()?;
+            match dashboard {
+                Ok(dashboard) => term.write_line(&dashboard.to_string())?,
+                Err(e) => term.write_line(
+                    &style(format!("check if your node is running: {}", e))
+                        .red()
+                        .to_string(),
+                )?,
+            }
+            term.write_line(&style("Press Ctrl+C to exit").dim().to_string())?;
+        }
+        tokio::time::sleep(refresh).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::BlockId;
+    use massa_signature::{derive_public_key, generate_random_private_key};
+
+    fn block(slot: Slot, is_final: bool, is_in_blockclique: bool) -> BlockSummary {
+        BlockSummary {
+            id: BlockId(Hash::compute_from(&slot.to_bytes_key())),
+            is_final,
+            is_stale: false,
+            is_in_blockclique,
+            slot,
+            creator: Address::from_public_key(&derive_public_key(&generate_random_private_key())),
+            parents: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_thread_slots() {
+        let blocks = vec![
+            block(Slot::new(1, 0), true, true),
+            block(Slot::new(2, 0), false, true),
+            block(Slot::new(3, 0), false, false),
+            block(Slot::new(1, 1), false, true),
+            // out of range thread is ignored
+            block(Slot::new(1, 5), true, true),
+        ];
+        assert_eq!(
+            thread_slots(&blocks, 3),
+            vec![
+                ThreadSlots {
+                    final_slot: Some(Slot::new(1, 0)),
+                    candidate_slot: Some(Slot::new(2, 0)),
+                },
+                ThreadSlots {
+                    final_slot: None,
+                    candidate_slot: Some(Slot::new(1, 1)),
+                },
+                ThreadSlots::default(),
+            ]
+        );
+    }
+}
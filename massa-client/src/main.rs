@@ -8,14 +8,17 @@ use anyhow::Result;
 use atty::Stream;
 use cmds::Command;
 use console::style;
-use massa_sdk::Client;
+use massa_sdk::{Client, WaitMode};
 use massa_wallet::Wallet;
 use serde::Serialize;
 use std::net::IpAddr;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+mod batch;
+mod batch_transactions;
 mod cmds;
+mod dashboard;
 mod repl;
 mod settings;
 mod utils;
@@ -34,6 +37,9 @@ struct Args {
     /// Address to listen on.
     #[structopt(long)]
     ip: Option<IpAddr>,
+    /// Node profile defined in the client configuration ("default" is `default_node`).
+    #[structopt(short = "p", long = "profile")]
+    profile: Option<String>,
     /// Command that client would execute (non-interactive mode)
     #[structopt(name = "COMMAND", default_value = "help")]
     command: Command,
@@ -51,6 +57,15 @@ struct Args {
     /// Enable a mode where input/output are serialized as JSON
     #[structopt(short = "j", long = "json")]
     json: bool,
+    /// Wait until the sent operation is "included" in a block or "final" (operation commands only)
+    #[structopt(long = "wait")]
+    wait: Option<WaitMode>,
+    /// Run the commands of a file, one per line ("-" to read them from stdin)
+    #[structopt(short = "b", long = "batch", parse(from_os_str))]
+    batch: Option<PathBuf>,
+    /// What to do when a command of a batch fails: "stop" or "continue"
+    #[structopt(long = "on-error", default_value = "stop")]
+    on_error: batch::ErrorPolicy,
 }
 
 #[derive(Serialize)]
@@ -63,29 +78,39 @@ struct JsonError {
 async fn main(args: Args) -> Result<()> {
     // TODO: move settings loading in another crate ... see #1277
     let settings = SETTINGS.clone();
+    let profile = settings.get_profile(args.profile.as_deref())?;
     let address = match args.ip {
         Some(ip) => ip,
-        None => settings.default_node.ip,
+        None => profile.ip,
     };
     let public_port = match args.public_port {
         Some(public_port) => public_port,
-        None => settings.default_node.public_port,
+        None => profile.public_port,
     };
     let private_port = match args.private_port {
         Some(private_port) => private_port,
-        None => settings.default_node.private_port,
+        None => profile.private_port,
     };
+    let public_failover = settings.get_failover_addresses(profile)?;
     // ...
     let mut wallet = Wallet::new(args.wallet)?;
-    let client = Client::new(address, public_port, private_port).await;
+    let client = Client::with_failover(address, public_port, private_port, &public_failover)?;
+    if let Some(path) = args.batch {
+        // Batch mode
+        return batch::run(&client, &mut wallet, &path, args.json, args.on_error).await;
+    }
     if atty::is(Stream::Stdout) && args.command == Command::help && !args.json {
         // Interactive mode
         repl::run(&client, &mut wallet).await;
     } else {
         // Non-Interactive mode
+        let mut parameters = args.parameters;
+        if let Some(wait) = args.wait {
+            parameters.extend(["--wait".to_string(), wait.to_string()]);
+        }
         match args
             .command
-            .run(&client, &mut wallet, &args.parameters, args.json)
+            .run(&client, &mut wallet, &parameters, args.json)
             .await
         {
             Ok(output) => {
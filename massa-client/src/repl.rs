@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::batch_transactions::BatchTransactionsReport;
 use crate::cmds::{Command, ExtendedWallet};
 use crate::settings::SETTINGS;
 use crate::utils::longest_common_prefix;
@@ -7,13 +8,15 @@ use console::style;
 use dialoguer::{theme::ColorfulTheme, Completion, History, Input};
 use erased_serde::{Serialize, Serializer};
 use glob::glob;
-use massa_models::api::{AddressInfo, BlockInfo, EndorsementInfo, NodeStatus, OperationInfo};
+use massa_models::api::{
+    AddressInfo, AsyncMessageInfo, BlockInfo, EndorsementInfo, NodeStatus, OperationInfo,
+};
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionReceipt};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::Set;
 use massa_models::{Address, OperationId};
-use massa_sdk::Client;
+use massa_sdk::{Client, OperationOutcome};
 use massa_wallet::Wallet;
 use rev_lines::RevLines;
 use std::collections::VecDeque;
@@ -204,6 +207,15 @@ impl dyn Output {
         self.erased_serialize(&mut format)?;
         Ok(())
     }
+
+    pub(crate) fn to_json_value(&self) -> anyhow::Result<serde_json::Value> {
+        let mut bytes = Vec::new();
+        let json = &mut serde_json::Serializer::new(&mut bytes);
+        let mut format: Box<dyn Serializer> = Box::new(<dyn Serializer>::erase(json));
+        self.erased_serialize(&mut format)?;
+        drop(format);
+        Ok(serde_json::from_slice(&bytes)?)
+    }
 }
 
 impl Output for Wallet {
@@ -280,6 +292,22 @@ impl Output for Vec<OperationInfo> {
     }
 }
 
+impl Output for Vec<ExecutionReceipt> {
+    fn pretty_print(&self) {
+        for receipt in self {
+            println!("{}", receipt);
+        }
+    }
+}
+
+impl Output for Vec<AsyncMessageInfo> {
+    fn pretty_print(&self) {
+        for message in self {
+            println!("{}", message);
+        }
+    }
+}
+
 impl Output for Vec<OperationId> {
     fn pretty_print(&self) {
         for operation_id in self {
@@ -315,3 +343,15 @@ impl Output for ExecuteReadOnlyResponse {
         println!("{}", self);
     }
 }
+
+impl Output for BatchTransactionsReport {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for OperationOutcome {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
@@ -1,10 +1,18 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 //! Build here the default client settings from the configuration file toml
+use anyhow::{anyhow, Result};
 use massa_models::constants::build_massa_settings;
 use massa_time::MassaTime;
 use serde::Deserialize;
-use std::{net::IpAddr, path::PathBuf};
+use std::collections::HashMap;
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+
+/// name of the profile built from `default_node`
+pub const DEFAULT_PROFILE: &str = "default";
 
 lazy_static::lazy_static! {
     pub static ref SETTINGS: Settings = build_massa_settings("massa-client", "MASSA_CLIENT");
@@ -12,17 +20,48 @@ lazy_static::lazy_static! {
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
-    pub default_node: DefaultNode,
+    pub default_node: NodeProfile,
+    #[serde(default)]
+    pub profiles: HashMap<String, NodeProfile>,
     pub history: usize,
     pub history_file_path: PathBuf,
     pub timeout: MassaTime,
+    pub max_operations_per_request: usize,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct DefaultNode {
+pub struct NodeProfile {
     pub ip: IpAddr,
     pub private_port: u16,
     pub public_port: u16,
+    /// profiles whose public API is used, in order, when this node cannot be reached
+    #[serde(default)]
+    pub failover: Vec<String>,
+}
+
+impl Settings {
+    /// Node profile with the given name, `default_node` if none is given
+    pub fn get_profile(&self, name: Option<&str>) -> Result<&NodeProfile> {
+        match name {
+            None | Some(DEFAULT_PROFILE) => Ok(&self.default_node),
+            Some(name) => self
+                .profiles
+                .get(name)
+                .ok_or_else(|| anyhow!("unknown node profile '{}'", name)),
+        }
+    }
+
+    /// Public API addresses of the failover nodes of a profile
+    pub fn get_failover_addresses(&self, profile: &NodeProfile) -> Result<Vec<SocketAddr>> {
+        profile
+            .failover
+            .iter()
+            .map(|name| {
+                let failover = self.get_profile(Some(name))?;
+                Ok(SocketAddr::new(failover.ip, failover.public_port))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -30,3 +69,11 @@ pub struct DefaultNode {
 fn test_load_client_config() {
     let _ = *SETTINGS;
 }
+
+#[cfg(test)]
+#[test]
+fn test_get_profile() {
+    assert!(SETTINGS.get_profile(None).is_ok());
+    assert!(SETTINGS.get_profile(Some(DEFAULT_PROFILE)).is_ok());
+    assert!(SETTINGS.get_profile(Some("unknown-profile")).is_err());
+}
@@ -62,6 +62,8 @@ pub enum ConsensusError {
     ProofOfStakeError(#[from] ProofOfStakeError),
     /// slot overflow
     SlotOverflowError,
+    /// remote signer error: {0}
+    RemoteSignerError(#[from] massa_signer::SignerError),
 }
 
 impl std::convert::From<massa_protocol_exports::ProtocolError> for ConsensusError {
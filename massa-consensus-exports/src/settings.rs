@@ -51,6 +51,7 @@ use massa_pool::PoolCommandSender;
 use massa_proof_of_stake_exports::ProofOfStakeConfig;
 use massa_protocol_exports::{ProtocolCommandSender, ProtocolEventReceiver};
 use massa_signature::PrivateKey;
+use massa_signer::RemoteSignerSettings;
 use massa_time::MassaTime;
 use num::rational::Ratio;
 use serde::{Deserialize, Serialize};
@@ -68,6 +69,9 @@ use crate::{
 pub struct ConsensusSettings {
     /// Staking private keys
     pub staking_keys_path: PathBuf,
+    /// Optional remote signer holding staking keys outside of the node
+    #[serde(default)]
+    pub remote_signer: Option<RemoteSignerSettings>,
     /// Maximum number of blocks allowed in discarded blocks.
     pub max_discarded_blocks: usize,
     /// If a block is `future_block_processing_max_periods` periods in the future, it is just discarded.
@@ -128,6 +132,8 @@ pub struct ConsensusConfig {
     pub genesis_key: PrivateKey,
     /// Staking private keys
     pub staking_keys_path: PathBuf,
+    /// Optional remote signer holding staking keys outside of the node
+    pub remote_signer: Option<RemoteSignerSettings>,
     /// Maximum number of blocks allowed in discarded blocks.
     pub max_discarded_blocks: usize,
     /// If a block is `future_block_processing_max_periods` periods in the future, it is just discarded.
@@ -220,6 +226,7 @@ impl Clone for ConsensusConfig {
             t0: self.t0,
             genesis_key: self.genesis_key,
             staking_keys_path: self.staking_keys_path.clone(),
+            remote_signer: self.remote_signer.clone(),
             max_discarded_blocks: self.max_discarded_blocks,
             future_block_processing_max_periods: self.future_block_processing_max_periods,
             max_future_processing_blocks: self.max_future_processing_blocks,
@@ -370,6 +377,7 @@ impl From<&ConsensusSettings> for ConsensusConfig {
             t0,
             genesis_key: *GENESIS_KEY,
             staking_keys_path: settings.staking_keys_path.clone(),
+            remote_signer: settings.remote_signer.clone(),
             max_discarded_blocks: settings.max_discarded_blocks,
             future_block_processing_max_periods: settings.future_block_processing_max_periods,
             max_future_processing_blocks: settings.max_future_processing_blocks,
@@ -431,6 +439,7 @@ impl From<ConsensusSettings> for ConsensusConfig {
             t0,
             genesis_key: *GENESIS_KEY,
             staking_keys_path: settings.staking_keys_path,
+            remote_signer: settings.remote_signer,
             max_discarded_blocks: settings.max_discarded_blocks,
             future_block_processing_max_periods: settings.future_block_processing_max_periods,
             max_future_processing_blocks: settings.max_future_processing_blocks,
@@ -481,6 +490,7 @@ impl Default for ConsensusSettings {
         use massa_models::constants::default_testing::*;
         Self {
             staking_keys_path: Default::default(),
+            remote_signer: None,
             max_discarded_blocks: MAX_DISCARDED_BLOCKS,
             future_block_processing_max_periods: FUTURE_BLOCK_PROCESSING_MAX_PERIODS,
             max_future_processing_blocks: MAX_FUTURE_PROCESSING_BLOCK,
@@ -577,6 +587,7 @@ impl Default for ConsensusConfig {
             t0: T0,
             genesis_key: *GENESIS_KEY,
             staking_keys_path: Default::default(),
+            remote_signer: None,
             max_discarded_blocks: MAX_DISCARDED_BLOCKS,
             future_block_processing_max_periods: FUTURE_BLOCK_PROCESSING_MAX_PERIODS,
             max_future_processing_blocks: MAX_FUTURE_PROCESSING_BLOCK,
@@ -18,19 +18,33 @@ use massa_models::{
 };
 use massa_models::{ledger_models::LedgerData, SignedOperation};
 use massa_models::{
-    Address, Block, BlockHeader, BlockId, Endorsement, EndorsementId, SerializeCompact, Slot,
+    Address, Block, BlockHeader, BlockId, Endorsement, EndorsementId, SerializeCompact,
+    SignedHeader, Slot,
 };
 use massa_proof_of_stake_exports::{error::ProofOfStakeError, ExportProofOfStake, ProofOfStake};
 use massa_protocol_exports::{ProtocolEvent, ProtocolEventReceiver};
-use massa_signature::{derive_public_key, PrivateKey, PublicKey};
+use massa_signature::{derive_public_key, generate_random_private_key, PrivateKey, PublicKey};
+use massa_signer::RemoteSigner;
 use massa_time::MassaTime;
-use std::{cmp::max, collections::HashSet, collections::VecDeque};
+use std::{
+    cmp::max,
+    collections::{hash_map, HashSet, VecDeque},
+};
 use tokio::{
     sync::mpsc::error::SendTimeoutError,
     time::{sleep, sleep_until, Sleep},
 };
 use tracing::{debug, info, warn};
 
+/// Where the private key of a staking address lives
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum StakingKey {
+    /// held by the node
+    Local(PrivateKey),
+    /// held by the remote signer
+    Remote,
+}
+
 /// Manages consensus.
 pub struct ConsensusWorker {
     /// Consensus Configuration
@@ -54,7 +68,9 @@ pub struct ConsensusWorker {
     /// clock compensation
     clock_compensation: i64,
     /// staking keys
-    staking_keys: Map<Address, (PublicKey, PrivateKey)>,
+    staking_keys: Map<Address, (PublicKey, StakingKey)>,
+    /// remote signer holding the `StakingKey::Remote` keys, if configured
+    remote_signer: Option<RemoteSigner>,
     /// stats `(block -> tx_count, creator)`
     final_block_stats: VecDeque<(MassaTime, u64, Address)>,
     /// No idea what this is used for. My guess is one timestamp per stale block
@@ -80,13 +96,16 @@ impl ConsensusWorker {
     /// * `controller_command_rx`: Channel receiving consensus commands.
     /// * `controller_event_tx`: Channel sending out consensus events.
     /// * `controller_manager_rx`: Channel receiving consensus management commands.
+    /// * `staking_keys`: staking keys held by the node
+    /// * `remote_signer`: optional remote signer, whose staking keys are added to the node ones
     pub(crate) async fn new(
         cfg: ConsensusConfig,
         channels: ConsensusWorkerChannels,
         block_db: BlockGraph,
         pos: ProofOfStake,
         clock_compensation: i64,
-        staking_keys: Map<Address, (PublicKey, PrivateKey)>,
+        staking_keys: Map<Address, (PublicKey, StakingKey)>,
+        remote_signer: Option<RemoteSigner>,
     ) -> Result<ConsensusWorker> {
         let now = MassaTime::compensated_now(clock_compensation)?;
         let previous_slot = get_latest_block_slot_at_timestamp(
@@ -163,7 +182,7 @@ impl ConsensusWorker {
                 .collect(),
         );
 
-        Ok(ConsensusWorker {
+        let mut worker = ConsensusWorker {
             genesis_public_key,
             block_db,
             pos,
@@ -174,6 +193,7 @@ impl ConsensusWorker {
             clock_compensation,
             channels,
             staking_keys,
+            remote_signer,
             final_block_stats,
             stale_block_stats: VecDeque::new(),
             stats_desync_detection_timespan,
@@ -181,7 +201,9 @@ impl ConsensusWorker {
             cfg,
             launch_time: MassaTime::compensated_now(clock_compensation)?,
             endorsed_slots: HashSet::new(),
-        })
+        };
+        worker.refresh_remote_staking_keys().await;
+        Ok(worker)
     }
 
     /// Consensus work is managed here.
@@ -324,6 +346,8 @@ impl ConsensusWorker {
         }
         if previous_cycle < Some(observed_cycle) {
             info!("Started cycle {}", observed_cycle);
+            // pick up the keys added to or removed from the remote signer
+            self.refresh_remote_staking_keys().await;
         }
 
         // check if there are any final blocks not produced by us
@@ -364,9 +388,9 @@ impl ConsensusWorker {
                     Err(err) => return Err(err.into()),
                 };
                 if let Some(addr) = block_draw {
-                    if let Some((pub_k, priv_k)) = self.staking_keys.get(&addr).cloned() {
+                    if let Some((pub_k, key)) = self.staking_keys.get(&addr).cloned() {
                         massa_trace!("consensus.consensus_worker.slot_tick.block_creator_addr", { "addr": addr, "pubkey": pub_k, "unlocked": true });
-                        self.create_block(cur_slot, &addr, &pub_k, &priv_k).await?;
+                        self.create_block(cur_slot, &addr, &pub_k, &key).await?;
                         if let Some(next_addr_slot) =
                             self.pos.get_next_selected_slot(self.next_slot, addr)
                         {
@@ -433,7 +457,7 @@ impl ConsensusWorker {
         cur_slot: Slot,
         creator_addr: &Address,
         creator_public_key: &PublicKey,
-        creator_private_key: &PrivateKey,
+        creator_key: &StakingKey,
     ) -> Result<()> {
         // get parents
         let parents = self.block_db.get_best_parents();
@@ -460,6 +484,13 @@ impl ConsensusWorker {
         });
 
         // create empty block
+        // it is only used to measure the block and initialize the state accumulator, and never broadcast:
+        // it is signed with a throwaway key when the creator key is remote,
+        // so that the signer only ever sees the final header of the slot
+        let draft_key = match creator_key {
+            StakingKey::Local(private_key) => *private_key,
+            StakingKey::Remote => generate_random_private_key(),
+        };
         let (_block_id, header) = Signed::new_signed(
             BlockHeader {
                 creator: *creator_public_key,
@@ -468,7 +499,7 @@ impl ConsensusWorker {
                 operation_merkle_root: Hash::compute_from(&Vec::new()[..]),
                 endorsements: endorsements.clone(),
             },
-            creator_private_key,
+            &draft_key,
         )?;
         let block = Block {
             header,
@@ -592,16 +623,22 @@ impl ConsensusWorker {
         }
 
         // compile resulting block
-        let (block_id, header) = Signed::new_signed(
-            BlockHeader {
-                creator: *creator_public_key,
-                slot: cur_slot,
-                parents: parents.iter().map(|(b, _p)| *b).collect(),
-                operation_merkle_root: Hash::compute_from(&total_hash),
-                endorsements,
-            },
-            creator_private_key,
-        )?;
+        let (block_id, header) = match self
+            .sign_block_header(
+                BlockHeader {
+                    creator: *creator_public_key,
+                    slot: cur_slot,
+                    parents: parents.iter().map(|(b, _p)| *b).collect(),
+                    operation_merkle_root: Hash::compute_from(&total_hash),
+                    endorsements,
+                },
+                creator_key,
+            )
+            .await?
+        {
+            Some(signed_header) => signed_header,
+            None => return Ok(()),
+        };
         let block = Block { header, operations };
         let slot = block.header.content.slot;
         massa_trace!("create block", { "block": block });
@@ -868,7 +905,8 @@ impl ConsensusWorker {
                     let public = derive_public_key(&key);
                     let address = Address::from_public_key(&public);
                     info!("Staking with address {}", address);
-                    self.staking_keys.insert(address, (public, key));
+                    self.staking_keys
+                        .insert(address, (public, StakingKey::Local(key)));
                 }
                 self.pos
                     .set_watched_addresses(self.staking_keys.keys().copied().collect());
@@ -947,12 +985,15 @@ impl ConsensusWorker {
         }
     }
 
-    /// Save the staking keys to a file
+    /// Save the staking keys held by the node to a file
     async fn dump_staking_keys(&self) {
         let keys = self
             .staking_keys
             .iter()
-            .map(|(_, (_, key))| *key)
+            .filter_map(|(_, (_, key))| match key {
+                StakingKey::Local(private_key) => Some(*private_key),
+                StakingKey::Remote => None,
+            })
             .collect::<Vec<_>>();
         let json = match serde_json::to_string_pretty(&keys) {
             Ok(json) => json,
@@ -1369,18 +1410,21 @@ impl ConsensusWorker {
                 // actually create endorsements
                 let mut endorsements = Map::default();
                 for (endorsement_index, addr) in endorsement_draws.into_iter().enumerate() {
-                    if let Some((pub_k, priv_k)) = self.staking_keys.get(&addr) {
+                    if let Some((pub_k, key)) = self.staking_keys.get(&addr).cloned() {
                         massa_trace!("consensus.consensus_worker.slot_tick.endorsement_creator_addr",
                             { "index": endorsement_index, "addr": addr, "pubkey": pub_k, "unlocked": true });
-                        let (endorsement_id, endorsement) = create_endorsement(
-                            block_slot,
-                            *pub_k,
-                            priv_k,
-                            endorsement_index as u32,
-                            block_id,
-                        )?;
-                        endorsements.insert(endorsement_id, endorsement);
-                        self.endorsed_slots.insert(block_slot);
+                        let content = Endorsement {
+                            sender_public_key: pub_k,
+                            slot: block_slot,
+                            index: endorsement_index as u32,
+                            endorsed_block: block_id,
+                        };
+                        if let Some((endorsement_id, endorsement)) =
+                            self.sign_endorsement(content, &key).await?
+                        {
+                            endorsements.insert(endorsement_id, endorsement);
+                            self.endorsed_slots.insert(block_slot);
+                        }
                     } else {
                         massa_trace!("consensus.consensus_worker.slot_tick.endorsement_creator_addr",
                             { "index": endorsement_index, "addr": addr, "unlocked": false });
@@ -1410,23 +1454,106 @@ impl ConsensusWorker {
 
         Ok(())
     }
-}
 
-/// A convenient function to create an endorsement
-/// should probably be moved to models (or fully deleted)
-pub fn create_endorsement(
-    slot: Slot,
-    sender_public_key: PublicKey,
-    private_key: &PrivateKey,
-    index: u32,
-    endorsed_block: BlockId,
-) -> Result<(EndorsementId, SignedEndorsement)> {
-    let content = Endorsement {
-        sender_public_key,
-        slot,
-        index,
-        endorsed_block,
-    };
-    let (e_id, endorsement) = Signed::new_signed(content, private_key)?;
-    Ok((e_id, endorsement))
+    /// Registers the staking keys held by the remote signer, if one is configured.
+    /// Remote keys the signer no longer holds are unregistered.
+    /// Failures are only logged: the previously known keys are kept until the next refresh.
+    async fn refresh_remote_staking_keys(&mut self) {
+        let signer = match self.remote_signer.as_mut() {
+            Some(signer) => signer,
+            None => return,
+        };
+        let public_keys = match signer.get_public_keys().await {
+            Ok(public_keys) => public_keys,
+            Err(err) => {
+                warn!(
+                    "could not get the staking keys of the remote signer: {}",
+                    err
+                );
+                return;
+            }
+        };
+        let previous_remote_addresses: Set<Address> = self
+            .staking_keys
+            .iter()
+            .filter(|(_, (_, key))| matches!(key, StakingKey::Remote))
+            .map(|(addr, _)| *addr)
+            .collect();
+        self.staking_keys
+            .retain(|_, (_, key)| !matches!(key, StakingKey::Remote));
+        for public_key in public_keys {
+            let address = Address::from_public_key(&public_key);
+            // keys held by the node take precedence
+            if let hash_map::Entry::Vacant(entry) = self.staking_keys.entry(address) {
+                if !previous_remote_addresses.contains(&address) {
+                    info!("Staking with address {} through the remote signer", address);
+                }
+                entry.insert((public_key, StakingKey::Remote));
+            }
+        }
+        self.pos
+            .set_watched_addresses(self.staking_keys.keys().copied().collect());
+    }
+
+    /// Signs a block header with the key of its creator.
+    /// Returns `None` if the remote signer could not or would not sign it:
+    /// the slot is then missed but consensus keeps running.
+    async fn sign_block_header(
+        &mut self,
+        header: BlockHeader,
+        creator_key: &StakingKey,
+    ) -> Result<Option<(BlockId, SignedHeader)>> {
+        match creator_key {
+            StakingKey::Local(private_key) => Ok(Some(Signed::new_signed(header, private_key)?)),
+            StakingKey::Remote => match self.remote_signer()?.sign_block_header(&header).await {
+                Ok(signature) => Ok(Some(Signed::new_with_signature(header, signature)?)),
+                Err(err) => {
+                    warn!(
+                        "remote signer did not sign the block of address {} at slot {}: {}",
+                        Address::from_public_key(&header.creator),
+                        header.slot,
+                        err
+                    );
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// Signs an endorsement with the key of its sender.
+    /// Returns `None` if the remote signer could not or would not sign it.
+    async fn sign_endorsement(
+        &mut self,
+        endorsement: Endorsement,
+        sender_key: &StakingKey,
+    ) -> Result<Option<(EndorsementId, SignedEndorsement)>> {
+        match sender_key {
+            StakingKey::Local(private_key) => {
+                Ok(Some(Signed::new_signed(endorsement, private_key)?))
+            }
+            StakingKey::Remote => {
+                match self.remote_signer()?.sign_endorsement(&endorsement).await {
+                    Ok(signature) => Ok(Some(Signed::new_with_signature(endorsement, signature)?)),
+                    Err(err) => {
+                        warn!(
+                        "remote signer did not sign the endorsement of address {} at slot {}: {}",
+                        Address::from_public_key(&endorsement.sender_public_key),
+                        endorsement.slot,
+                        err
+                    );
+                        Ok(None)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remote signer, which must be configured when remote staking keys are registered
+    fn remote_signer(&mut self) -> Result<&mut RemoteSigner> {
+        self.remote_signer.as_mut().ok_or_else(|| {
+            ConsensusError::ContainerInconsistency(
+                "remote staking key registered without remote signer".into(),
+            )
+        })
+    }
 }
@@ -21,6 +21,7 @@ mod scenarios_operations_check;
 mod scenarios_parents;
 mod scenarios_pool_commands;
 mod scenarios_pruning;
+mod scenarios_remote_signer;
 mod scenarios_reward_split;
 mod scenarios_send_block;
 mod scenarios_wishlist;
@@ -0,0 +1,140 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use super::tools::random_address_on_thread;
+use crate::tests::tools;
+use massa_consensus_exports::{tools::*, ConsensusConfig};
+use massa_hash::Hash;
+use massa_models::rolls::{RollCounts, RollUpdate, RollUpdates};
+use massa_models::{BlockHeader, Slot};
+use massa_protocol_exports::ProtocolCommand;
+use massa_signature::{derive_public_key, generate_random_private_key};
+use massa_signer::{
+    run_signer_server, RemoteSignerSettings, SignerEndpoint, SignerState, SigningGuard,
+};
+use massa_time::MassaTime;
+use serial_test::serial;
+use std::collections::HashMap;
+
+/// The only staking address is held by a remote signer, whose guard already holds
+/// a different header for slot (1, 0) of that address.
+///
+/// The node must not produce a block at slot (1, 0) since the signer refuses it,
+/// and must produce the block of slot (1, 1), signed remotely.
+#[tokio::test]
+#[serial]
+#[cfg(unix)]
+async fn test_remote_signer_block_creation() {
+    let thread_count = 2;
+    let (address_1, priv_1, pubkey_1) = random_address_on_thread(0, thread_count).into();
+
+    // address 1 holds the only roll, so it is drawn for every slot
+    let mut roll_counts = RollCounts::default();
+    let mut updates = RollUpdates::default();
+    updates
+        .apply(
+            &address_1,
+            &RollUpdate {
+                roll_purchases: 1,
+                roll_sales: 0,
+            },
+        )
+        .unwrap();
+    roll_counts.apply_updates(&updates).unwrap();
+    let initial_rolls_file = generate_roll_counts_file(&roll_counts);
+
+    // start the signer with a conflicting header already signed at slot (1, 0)
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("signer.sock");
+    let node_auth_key = generate_random_private_key();
+    let node_auth_key_file = dir.path().join("node_auth_key");
+    std::fs::write(&node_auth_key_file, node_auth_key.to_bs58_check()).unwrap();
+    let signer_auth_key = generate_random_private_key();
+    let mut guard = SigningGuard::new(u64::MAX, u64::MAX);
+    guard
+        .allow_block_header(
+            &BlockHeader {
+                creator: pubkey_1,
+                slot: Slot::new(1, 0),
+                parents: Vec::new(),
+                operation_merkle_root: Hash::compute_from(b"conflicting"),
+                endorsements: Vec::new(),
+            },
+            0,
+        )
+        .unwrap();
+    let signer_state = SignerState {
+        auth_key: signer_auth_key,
+        authorized_nodes: vec![derive_public_key(&node_auth_key)],
+        staking_keys: vec![(address_1, (pubkey_1, priv_1))].into_iter().collect(),
+        guard: tokio::sync::Mutex::new(guard),
+        signing_history_path: dir.path().join("signing_history.json"),
+        handshake_timeout: std::time::Duration::from_secs(1),
+    };
+    let endpoint = SignerEndpoint::Unix(socket_path.clone());
+    let server_endpoint = endpoint.clone();
+    let signer_handle =
+        tokio::spawn(async move { run_signer_server(&server_endpoint, signer_state).await });
+    while !socket_path.exists() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    // the node holds no staking key itself
+    let mut cfg = ConsensusConfig {
+        disable_block_creation: false,
+        t0: 1000.into(),
+        remote_signer: Some(RemoteSignerSettings {
+            endpoint,
+            auth_key_file: node_auth_key_file,
+            signer_public_key: derive_public_key(&signer_auth_key),
+            request_timeout: 1000.into(),
+        }),
+        ..ConsensusConfig::default_with_staking_keys_and_ledger(&[], &HashMap::new())
+    };
+    cfg.initial_rolls_path = initial_rolls_file.path().to_path_buf();
+    cfg.genesis_timestamp = MassaTime::now().unwrap();
+
+    tools::consensus_without_pool_test_with_storage(
+        cfg.clone(),
+        async move |mut protocol_controller,
+                    consensus_command_sender,
+                    consensus_event_receiver,
+                    storage| {
+            let mut created_slots = Vec::new();
+            loop {
+                let (slot, creator) = protocol_controller
+                    .wait_command(3000.into(), |cmd| match cmd {
+                        ProtocolCommand::IntegratedBlock { block_id, .. } => {
+                            let block = storage
+                                .retrieve_block(&block_id)
+                                .expect("integrated block not found in storage");
+                            let stored_block = block.read();
+                            Some((
+                                stored_block.block.header.content.slot,
+                                stored_block.block.header.content.creator,
+                            ))
+                        }
+                        _ => None,
+                    })
+                    .await
+                    .expect("block of slot (1, 1) was not created in time");
+                created_slots.push(slot);
+                if slot == Slot::new(1, 1) {
+                    assert_eq!(creator, pubkey_1, "wrong block creator");
+                    break;
+                }
+            }
+            assert!(
+                !created_slots.contains(&Slot::new(1, 0)),
+                "a block refused by the signer was created"
+            );
+
+            (
+                protocol_controller,
+                consensus_command_sender,
+                consensus_event_receiver,
+            )
+        },
+    )
+    .await;
+    signer_handle.abort();
+}
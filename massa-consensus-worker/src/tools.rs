@@ -7,12 +7,13 @@ use massa_consensus_exports::{
 };
 use tracing::{debug, error, info};
 
-use crate::consensus_worker::ConsensusWorker;
+use crate::consensus_worker::{ConsensusWorker, StakingKey};
 use massa_consensus_exports::settings::ConsensusConfig;
 use massa_graph::{settings::GraphConfig, BlockGraph, BootstrapableGraph};
 use massa_models::{constants::CHANNEL_SIZE, prehash::Map, Address};
 use massa_proof_of_stake_exports::{ExportProofOfStake, ProofOfStake, ProofOfStakeConfig};
 use massa_signature::{derive_public_key, PrivateKey, PublicKey};
+use massa_signer::RemoteSigner;
 use massa_storage::Storage;
 use std::path::Path;
 use tokio::sync::mpsc;
@@ -24,7 +25,7 @@ use tokio::sync::mpsc;
 /// Maybe it would be worth considering returning the default map
 /// when the read to string or the parse is failing
 /// but eh that's left for another refactor
-async fn load_initial_staking_keys(path: &Path) -> Result<Map<Address, (PublicKey, PrivateKey)>> {
+async fn load_initial_staking_keys(path: &Path) -> Result<Map<Address, (PublicKey, StakingKey)>> {
     if !std::path::Path::is_file(path) {
         return Ok(Map::default());
     }
@@ -34,7 +35,7 @@ async fn load_initial_staking_keys(path: &Path) -> Result<Map<Address, (PublicKe
             let public_key = derive_public_key(private_key);
             Ok((
                 Address::from_public_key(&public_key),
-                (public_key, *private_key),
+                (public_key, StakingKey::Local(*private_key)),
             ))
         })
         .collect()
@@ -82,6 +83,10 @@ pub async fn start_consensus_controller(
         ));
     }
     let staking_keys = load_initial_staking_keys(&cfg.staking_keys_path).await?;
+    let remote_signer = match cfg.remote_signer.clone() {
+        Some(settings) => Some(RemoteSigner::new(settings).await?),
+        None => None,
+    };
 
     // start worker
     let block_db = BlockGraph::new(GraphConfig::from(&cfg), boot_graph, storage).await?;
@@ -112,6 +117,7 @@ pub async fn start_consensus_controller(
             pos,
             clock_compensation,
             staking_keys,
+            remote_signer,
         )
         .await?
         .run_loop()
@@ -7,10 +7,13 @@ use crate::types::ReadOnlyExecutionRequest;
 use crate::ExecutionError;
 use massa_hash::Hash;
 use massa_ledger::LedgerEntry;
-use massa_models::api::EventFilter;
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::{ExecutionReceipt, ExecutionTrace};
 use massa_models::output_event::SCOutputEvent;
+use massa_models::stats::ExecutionStats;
 use massa_models::Address;
 use massa_models::BlockId;
+use massa_models::OperationId;
 use massa_models::Slot;
 use std::collections::HashMap;
 
@@ -35,6 +38,19 @@ pub trait ExecutionController: Send + Sync {
     /// * operation id
     fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent>;
 
+    /// Get the receipts of executed operations, final ones first.
+    /// Operations that were not executed, or whose receipts were pruned, are ignored.
+    fn get_operation_receipts(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionReceipt>;
+
+    /// Get the pending asynchronous messages, optionally filtered by:
+    /// * sender address
+    /// * destination address
+    /// * validity window overlapping a slot range
+    fn get_async_messages(&self, filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo>;
+
+    /// Get the execution performance stats aggregated separately over the latest final and active slot executions
+    fn get_stats(&self) -> ExecutionStats;
+
     /// Get a copy of a single datastore entry with its final and active values
     ///
     /// # Return value
@@ -67,6 +83,11 @@ pub trait ExecutionController: Send + Sync {
         req: ReadOnlyExecutionRequest,
     ) -> Result<ExecutionOutput, ExecutionError>;
 
+    /// Get the execution traces of recently finalized operations.
+    /// Only available if operation tracing is enabled in the configuration,
+    /// operations without a known trace are ignored.
+    fn get_final_operation_traces(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionTrace>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ExecutionController>`.
     fn clone_box(&self) -> Box<dyn ExecutionController>;
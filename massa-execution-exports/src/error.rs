@@ -15,9 +15,18 @@ pub enum ExecutionError {
     /// Runtime error: {0}
     RuntimeError(String),
 
+    /// Runtime error: {0}
+    TracedRuntimeError(String, Box<massa_models::execution::ExecutionTrace>),
+
     /// `MassaHashError`: {0}
     MassaHashError(#[from] massa_hash::MassaHashError),
 
     /// `ModelsError`: {0}
     ModelsError(#[from] massa_models::ModelsError),
+
+    /// Final state error: {0}
+    FinalStateError(String),
+
+    /// read-only execution timed out: {0}
+    ReadOnlyTimeout(String),
 }
@@ -48,34 +48,7 @@ impl EventStore {
     pub fn get_filtered_sc_output_event(&self, filter: &EventFilter) -> VecDeque<SCOutputEvent> {
         self.0
             .iter()
-            .filter(|x| {
-                if let Some(start) = filter.start {
-                    if x.context.slot < start {
-                        return false;
-                    }
-                }
-                if let Some(end) = filter.end {
-                    if x.context.slot >= end {
-                        return false;
-                    }
-                }
-                match (filter.emitter_address, x.context.call_stack.front()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.original_caller_address, x.context.call_stack.back()) {
-                    (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                match (filter.original_operation_id, x.context.origin_operation_id) {
-                    (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
-                    (Some(_), None) => return false,
-                    _ => (),
-                }
-                true
-            })
+            .filter(|x| filter.matches(x))
             .cloned()
             .collect()
     }
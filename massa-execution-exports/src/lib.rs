@@ -34,6 +34,9 @@
 //! ## `event_store.rs`
 //! Defines an indexed, finite-size storage system for execution events.
 //!
+//! ## `receipt_store.rs`
+//! Defines a finite-size storage system for the receipts of executed operations and asynchronous messages.
+//!
 //! ## `types.rs`
 //! Defines useful shared structures.
 //!
@@ -47,12 +50,14 @@
 mod controller_traits;
 mod error;
 mod event_store;
+mod receipt_store;
 mod settings;
 mod types;
 
 pub use controller_traits::{ExecutionController, ExecutionManager};
 pub use error::ExecutionError;
 pub use event_store::EventStore;
+pub use receipt_store::ReceiptStore;
 pub use settings::ExecutionConfig;
 pub use types::{
     ExecutionOutput, ExecutionStackElement, ReadOnlyCallRequest, ReadOnlyExecutionRequest,
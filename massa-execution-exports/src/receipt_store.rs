@@ -0,0 +1,82 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module represents a receipt store allowing to store and retrieve
+//! a config-limited number of execution receipts
+
+use massa_models::execution::{ExecutionReceipt, ReceiptOrigin};
+use massa_models::OperationId;
+use std::collections::VecDeque;
+
+/// Store for the receipts of executed operations and asynchronous messages
+#[derive(Default, Debug, Clone)]
+pub struct ReceiptStore(VecDeque<ExecutionReceipt>);
+
+impl ReceiptStore {
+    /// Push a new receipt to the store
+    pub fn push(&mut self, receipt: ExecutionReceipt) {
+        self.0.push_back(receipt);
+    }
+
+    /// Prune the receipt store if its size is over the given limit
+    pub fn prune(&mut self, max_receipts: usize) {
+        while self.0.len() > max_receipts {
+            self.0.pop_front();
+        }
+    }
+
+    /// Extend the receipt store with another store, marking the new receipts as final or not
+    pub fn extend(&mut self, other: ReceiptStore, is_final: bool) {
+        self.0.extend(other.0.into_iter().map(|mut receipt| {
+            receipt.is_final = is_final;
+            receipt
+        }));
+    }
+
+    /// Get the receipts of the given operations, in execution order
+    pub fn get_operation_receipts(&self, operation_ids: &[OperationId]) -> Vec<ExecutionReceipt> {
+        self.0
+            .iter()
+            .filter(|receipt| match &receipt.origin {
+                ReceiptOrigin::Operation(id) => operation_ids.contains(id),
+                ReceiptOrigin::AsyncMessage { .. } => false,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[test]
+fn test_get_operation_receipts() {
+    use massa_models::{Amount, Slot};
+
+    let receipt = |origin| ExecutionReceipt {
+        origin,
+        slot: Slot::new(1, 0),
+        is_final: false,
+        error: None,
+        gas_used: Some(10),
+        coins_spent: Amount::default(),
+        created_addresses: Vec::new(),
+    };
+    let op_1 = OperationId::from_bytes(&[1; 32]);
+    let op_2 = OperationId::from_bytes(&[2; 32]);
+    let mut active = ReceiptStore::default();
+    active.push(receipt(ReceiptOrigin::Operation(op_1)));
+    active.push(receipt(ReceiptOrigin::AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index: 0,
+    }));
+    active.push(receipt(ReceiptOrigin::Operation(op_2)));
+
+    let mut store = ReceiptStore::default();
+    store.extend(active, true);
+    assert_eq!(store.0.len(), 3);
+    assert!(store.0.iter().all(|receipt| receipt.is_final));
+
+    let receipts = store.get_operation_receipts(&[op_2]);
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].origin, ReceiptOrigin::Operation(op_2));
+
+    store.prune(1);
+    assert!(store.get_operation_receipts(&[op_1]).is_empty());
+}
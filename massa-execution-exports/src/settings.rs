@@ -2,17 +2,37 @@
 
 //! This module provides the structures used to provide configuration parameters to the Execution system
 
+use massa_models::Amount;
 use massa_time::MassaTime;
+use std::path::PathBuf;
 
 /// Execution module configuration
 #[derive(Debug, Clone)]
 pub struct ExecutionConfig {
     /// read-only execution request queue length
     pub readonly_queue_length: usize,
+    /// number of threads executing read-only requests in parallel
+    pub readonly_executor_count: usize,
+    /// wall-clock time after which a read-only execution request times out.
+    /// A request that is already running fails at its next interface call.
+    pub readonly_timeout: MassaTime,
     /// maximum number of SC output events kept in cache
     pub max_final_events: usize,
+    /// path to the disk store of final SC output events, `None` to keep them in cache only
+    pub final_events_db_path: Option<PathBuf>,
+    /// number of latest slots whose final SC output events are kept on disk, `None` for no limit
+    pub final_events_retention_slots: Option<u64>,
+    /// maximum size in bytes of the final SC output events kept on disk, `None` for no limit.
+    /// The events of the latest final slot are always kept, even if they alone exceed it.
+    pub final_events_max_disk_size: Option<u64>,
+    /// max number of SC output events returned while querying
+    pub max_item_return_count: usize,
+    /// maximum number of final execution receipts kept in cache
+    pub max_final_receipts: usize,
     /// maximum available gas for asynchronous messages execution
     pub max_async_gas: u64,
+    /// price locked from the balance of an address for each byte stored in its datastore
+    pub storage_byte_cost: Amount,
     /// number of threads
     pub thread_count: u8,
     /// extra lag to add on the execution cursor to improve performance
@@ -23,4 +43,12 @@ pub struct ExecutionConfig {
     pub genesis_timestamp: MassaTime,
     /// period duration
     pub t0: MassaTime,
+    /// whether to record the execution traces of operations
+    pub trace_operations: bool,
+    /// maximum number of final operation execution traces kept in cache
+    pub max_final_traces: usize,
+    /// number of latest final and of latest active slot executions over which execution stats are aggregated
+    pub stats_history_length: usize,
+    /// wall-clock slot execution time above which a warning is logged with a breakdown
+    pub slow_slot_threshold: MassaTime,
 }
@@ -5,7 +5,13 @@
 use crate::{ExecutionController, ExecutionError, ExecutionOutput, ReadOnlyExecutionRequest};
 use massa_hash::Hash;
 use massa_ledger::LedgerEntry;
-use massa_models::{api::EventFilter, output_event::SCOutputEvent, Address, BlockId, Slot};
+use massa_models::{
+    api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter},
+    execution::{ExecutionReceipt, ExecutionTrace},
+    output_event::SCOutputEvent,
+    stats::ExecutionStats,
+    Address, BlockId, OperationId, Slot,
+};
 use std::{
     collections::HashMap,
     sync::{
@@ -35,6 +41,25 @@ pub enum MockExecutionControllerMessage {
         /// response channel
         response_tx: mpsc::Sender<Vec<SCOutputEvent>>,
     },
+    /// get the receipts of executed operations
+    GetOperationReceipts {
+        /// operation ids
+        operation_ids: Vec<OperationId>,
+        /// response channel
+        response_tx: mpsc::Sender<Vec<ExecutionReceipt>>,
+    },
+    /// get the pending asynchronous messages
+    GetAsyncMessages {
+        /// filter
+        filter: AsyncMessageFilter,
+        /// response channel
+        response_tx: mpsc::Sender<Vec<AsyncMessageInfo>>,
+    },
+    /// get the execution stats
+    GetStats {
+        /// response channel
+        response_tx: mpsc::Sender<ExecutionStats>,
+    },
     /// get full ledger entry
     GetFullLedgerEntry {
         /// address
@@ -49,6 +74,13 @@ pub enum MockExecutionControllerMessage {
         /// response channel
         response_tx: mpsc::Sender<Result<ExecutionOutput, ExecutionError>>,
     },
+    /// get the execution traces of final operations
+    GetFinalOperationTraces {
+        /// operation ids
+        operation_ids: Vec<OperationId>,
+        /// response channel
+        response_tx: mpsc::Sender<Vec<ExecutionTrace>>,
+    },
 }
 
 /// A mocked execution controller that will intercept calls on its methods
@@ -107,6 +139,42 @@ impl ExecutionController for MockExecutionController {
         response_rx.recv().unwrap()
     }
 
+    fn get_operation_receipts(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionReceipt> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockExecutionControllerMessage::GetOperationReceipts {
+                operation_ids,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn get_async_messages(&self, filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockExecutionControllerMessage::GetAsyncMessages {
+                filter,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn get_stats(&self) -> ExecutionStats {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockExecutionControllerMessage::GetStats { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_final_and_active_data_entry(
         &self,
         _addr: &Address,
@@ -144,6 +212,19 @@ impl ExecutionController for MockExecutionController {
         response_rx.recv().unwrap()
     }
 
+    fn get_final_operation_traces(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionTrace> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockExecutionControllerMessage::GetFinalOperationTraces {
+                operation_ids,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn clone_box(&self) -> Box<dyn ExecutionController> {
         Box::new(self.clone())
     }
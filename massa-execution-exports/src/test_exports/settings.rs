@@ -13,14 +13,26 @@ impl Default for ExecutionConfig {
 
         Self {
             readonly_queue_length: READONLY_QUEUE_LENGTH,
+            readonly_executor_count: READONLY_EXECUTOR_COUNT,
+            readonly_timeout: *READONLY_TIMEOUT,
             max_final_events: MAX_FINAL_EVENTS,
+            final_events_db_path: None,
+            final_events_retention_slots: None,
+            final_events_max_disk_size: None,
+            max_item_return_count: MAX_ITEM_RETURN_COUNT,
+            max_final_receipts: MAX_FINAL_RECEIPTS,
             max_async_gas: MAX_ASYNC_GAS,
+            storage_byte_cost: STORAGE_BYTE_COST,
             thread_count: THREAD_COUNT,
             cursor_delay: CURSOR_DELAY,
             clock_compensation: Default::default(),
             // reset genesis timestamp because we are in test mode that can take a while to process
             genesis_timestamp: MassaTime::now().expect("Impossible to reset the timestamp in test"),
             t0: 10.into(),
+            trace_operations: false,
+            max_final_traces: MAX_FINAL_TRACES,
+            stats_history_length: EXECUTION_STATS_HISTORY_LENGTH,
+            slow_slot_threshold: *SLOW_SLOT_THRESHOLD,
         }
     }
 }
@@ -3,7 +3,10 @@
 //! This file exports useful types used to interact with the execution worker
 
 use crate::event_store::EventStore;
+use crate::receipt_store::ReceiptStore;
 use massa_final_state::StateChanges;
+use massa_models::api::AddressStateOverride;
+use massa_models::execution::ExecutionTrace;
 use massa_models::{Address, Amount, BlockId, Slot};
 
 /// structure describing the output of a single execution
@@ -17,6 +20,10 @@ pub struct ExecutionOutput {
     pub state_changes: StateChanges,
     /// events emitted by the execution step
     pub events: EventStore,
+    /// receipts of the operations and asynchronous messages executed during the step
+    pub receipts: ReceiptStore,
+    /// traces of the executions of the step, if tracing was enabled
+    pub traces: Vec<ExecutionTrace>,
 }
 
 /// structure describing different types of read-only execution request
@@ -30,6 +37,14 @@ pub struct ReadOnlyExecutionRequest {
     pub call_stack: Vec<ExecutionStackElement>,
     /// Target of the request
     pub target: ReadOnlyExecutionTarget,
+    /// Whether to record the trace of the execution
+    pub trace: bool,
+    /// Ledger state overrides applied on top of the speculative ledger during the execution only
+    pub state_overrides: Vec<AddressStateOverride>,
+    /// Slot at which to simulate the execution, defaults to the slot after the latest executed one
+    pub simulated_slot: Option<Slot>,
+    /// Recent final slot at the output of which the state is viewed, defaults to the latest executed slot
+    pub final_slot: Option<Slot>,
 }
 
 /// structure describing different possible targets of a read-only execution request
@@ -46,6 +61,8 @@ pub enum ReadOnlyExecutionTarget {
         target_func: String,
         /// Parameter to pass to the target function
         parameter: String,
+        /// Coins transferred from the caller to the target address before the call
+        coins: Amount,
     },
 }
 
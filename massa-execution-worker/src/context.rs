@@ -10,11 +10,15 @@
 use crate::speculative_async_pool::SpeculativeAsyncPool;
 use crate::speculative_ledger::SpeculativeLedger;
 use massa_async_pool::{AsyncMessage, AsyncMessageId};
-use massa_execution_exports::{EventStore, ExecutionError, ExecutionOutput, ExecutionStackElement};
+use massa_execution_exports::{
+    EventStore, ExecutionError, ExecutionOutput, ExecutionStackElement, ReceiptStore,
+};
 use massa_final_state::{FinalState, StateChanges};
 use massa_hash::Hash;
 use massa_ledger::LedgerChanges;
 use massa_models::{
+    api::AddressStateOverride,
+    execution::{AbiCallTrace, ExecutionTrace, LedgerAccess},
     output_event::{EventExecutionContext, SCOutputEvent},
     Address, Amount, BlockId, OperationId, Slot,
 };
@@ -22,6 +26,7 @@ use parking_lot::RwLock;
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256PlusPlus;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::debug;
 
 /// A snapshot taken from an `ExecutionContext` and that represents its current state.
@@ -96,6 +101,23 @@ pub(crate) struct ExecutionContext {
 
     /// operation id that originally caused this execution (if any)
     pub origin_operation_id: Option<OperationId>,
+
+    /// trace of the ongoing execution, if it is traced.
+    /// Not part of snapshots so that the trace of a failed execution is kept.
+    pub trace: Option<ExecutionTrace>,
+
+    /// traces of the finished executions of this context
+    pub traces: Vec<ExecutionTrace>,
+
+    /// receipts of the operations and asynchronous messages executed in this context
+    pub receipts: ReceiptStore,
+
+    /// smart contract addresses created by the ongoing execution, reported in its receipt
+    pub created_addresses: Vec<Address>,
+
+    /// instant after which the interface calls of a read-only execution fail, so that it stops early.
+    /// Always `None` outside of read-only executions, which must stay deterministic.
+    pub deadline: Option<Instant>,
 }
 
 impl ExecutionContext {
@@ -107,20 +129,23 @@ impl ExecutionContext {
     /// # arguments
     /// * `final_state`: thread-safe access to the final state. Note that this will be used only for reading, never for writing
     /// * `previous_changes`: list of ledger changes that happened since the final ledger state and before the current execution
+    /// * `storage_byte_cost`: price locked from the balance of an address for each byte stored in its datastore
     ///
     /// # returns
     /// A new (empty) `ExecutionContext` instance
     pub(crate) fn new(
         final_state: Arc<RwLock<FinalState>>,
         previous_changes: StateChanges,
+        storage_byte_cost: Amount,
     ) -> Self {
         ExecutionContext {
             speculative_ledger: SpeculativeLedger::new(
                 final_state.clone(),
                 previous_changes.ledger_changes,
+                storage_byte_cost,
             ),
             speculative_async_pool: SpeculativeAsyncPool::new(
-                final_state.read().async_pool.clone(),
+                final_state.clone(),
                 previous_changes.async_pool_changes,
             ),
             max_gas: Default::default(),
@@ -135,6 +160,11 @@ impl ExecutionContext {
             events: Default::default(),
             unsafe_rng: Xoshiro256PlusPlus::from_seed([0u8; 32]),
             origin_operation_id: Default::default(),
+            trace: Default::default(),
+            traces: Default::default(),
+            receipts: Default::default(),
+            created_addresses: Default::default(),
+            deadline: None,
         }
     }
 
@@ -174,6 +204,7 @@ impl ExecutionContext {
     /// * `req`: parameters of the read only execution
     /// * `previous_changes`: list of state changes that happened since the `final_state` state and before this execution
     /// * `final_state`: thread-safe access to the final state. Note that this will be used only for reading, never for writing
+    /// * `storage_byte_cost`: price locked from the balance of an address for each byte stored in its datastore
     ///
     /// # returns
     /// A `ExecutionContext` instance ready for a read-only execution
@@ -184,6 +215,7 @@ impl ExecutionContext {
         call_stack: Vec<ExecutionStackElement>,
         previous_changes: StateChanges,
         final_state: Arc<RwLock<FinalState>>,
+        storage_byte_cost: Amount,
     ) -> Self {
         // Deterministically seed the unsafe RNG to allow the bytecode to use it.
         // Note that consecutive read-only calls for the same slot will get the same random seed.
@@ -208,11 +240,19 @@ impl ExecutionContext {
             stack: call_stack,
             read_only: true,
             unsafe_rng,
-            ..ExecutionContext::new(final_state, previous_changes)
+            ..ExecutionContext::new(final_state, previous_changes, storage_byte_cost)
         }
     }
 
+    /// Pretends the given ledger state for some addresses for the rest of the execution,
+    /// without it being part of the execution output.
+    /// Used to simulate read-only executions over a modified state.
+    pub(crate) fn apply_ledger_overrides(&mut self, overrides: Vec<AddressStateOverride>) {
+        self.speculative_ledger.apply_overrides(overrides);
+    }
+
     /// This function takes a batch of asynchronous operations to execute, removing them from the speculative pool.
+    /// Recurring messages are rescheduled, and the remaining gas budget of the ones whose recurrence is over is reimbursed.
     ///
     /// # Arguments
     /// * `max_gas`: maximal amount of asynchronous gas available
@@ -228,7 +268,14 @@ impl ExecutionContext {
         self.speculative_async_pool
             .take_batch_to_execute(self.slot, max_gas)
             .into_iter()
-            .map(|(_id, msg)| (self.get_bytecode(&msg.destination), msg))
+            .map(|(_id, mut msg)| {
+                // the recurrence of a message that could not be rescheduled is over:
+                // reimburse its remaining gas budget
+                if let Some(recurrence) = msg.recurrence.take() {
+                    self.reimburse_gas_budget(&msg, recurrence.gas_budget);
+                }
+                (self.get_bytecode(&msg.destination), msg)
+            })
             .collect()
     }
 
@@ -240,6 +287,7 @@ impl ExecutionContext {
     /// * `opt_block_id`: optional ID of the block at that slot
     /// * `previous_changes`: list of state changes that happened since the final state state and before this execution
     /// * `final_state`: thread-safe access to the final state. Note that this will be used only for reading, never for writing
+    /// * `storage_byte_cost`: price locked from the balance of an address for each byte stored in its datastore
     ///
     /// # returns
     /// A `ExecutionContext` instance
@@ -248,6 +296,7 @@ impl ExecutionContext {
         opt_block_id: Option<BlockId>,
         previous_changes: StateChanges,
         final_state: Arc<RwLock<FinalState>>,
+        storage_byte_cost: Amount,
     ) -> Self {
         // Deterministically seed the unsafe RNG to allow the bytecode to use it.
 
@@ -269,7 +318,7 @@ impl ExecutionContext {
             slot,
             opt_block_id,
             unsafe_rng,
-            ..ExecutionContext::new(final_state, previous_changes)
+            ..ExecutionContext::new(final_state, previous_changes, storage_byte_cost)
         }
     }
 
@@ -364,6 +413,9 @@ impl ExecutionContext {
         // increment the address creation counter at this slot
         self.created_addr_index += 1;
 
+        // report the address in the receipt of the execution
+        self.created_addresses.push(address);
+
         Ok(address)
     }
 
@@ -382,6 +434,12 @@ impl ExecutionContext {
         self.speculative_ledger.has_data_entry(address, key)
     }
 
+    /// gets the number of speculative ledger reads since the creation of the context,
+    /// and how many of them fell through to the final ledger
+    pub fn get_ledger_read_counts(&self) -> (u64, u64) {
+        self.speculative_ledger.get_read_counts()
+    }
+
     /// gets the effective parallel balance of an address
     pub fn get_parallel_balance(&self, address: &Address) -> Option<Amount> {
         self.speculative_ledger.get_parallel_balance(address)
@@ -505,15 +563,64 @@ impl ExecutionContext {
             .transfer_parallel_coins(from_addr, to_addr, amount)
     }
 
-    /// Add a new asynchronous message to speculative pool
+    /// Add a new asynchronous message to speculative pool.
+    /// The coins of the message and the gas budget of a recurring message are locked:
+    /// `coins + gas_budget * gas_price` is debited from its sender,
+    /// and the message is rejected if the sender cannot afford it.
     ///
     /// # Arguments
     /// * `msg`: asynchronous message to add
-    pub fn push_new_message(&mut self, msg: AsyncMessage) {
+    pub fn push_new_message(&mut self, msg: AsyncMessage) -> Result<(), ExecutionError> {
+        let gas_budget = msg
+            .recurrence
+            .as_ref()
+            .map_or(0, |recurrence| recurrence.gas_budget);
+        let amount = msg
+            .gas_price
+            .checked_mul_u64(gas_budget)
+            .and_then(|budget_cost| budget_cost.checked_add(msg.coins))
+            .ok_or_else(|| {
+                ExecutionError::RuntimeError(
+                    "overflow when computing the coins locked by an asynchronous message"
+                        .to_string(),
+                )
+            })?;
+        self.transfer_parallel_coins(Some(msg.sender), None, amount)?;
         self.speculative_async_pool.push_new_message(msg);
+        Ok(())
     }
 
-    /// Cancels an asynchronous message, reimbursing `msg.coins` to the sender
+    /// Cancels a pending asynchronous message on behalf of the current address,
+    /// removing it from the speculative pool and reimbursing its sender.
+    /// Fails if the message does not exist or was not sent by the current address.
+    ///
+    /// # Arguments
+    /// * `msg_id`: ID of the message to cancel
+    pub fn cancel_message(&mut self, msg_id: &AsyncMessageId) -> Result<(), ExecutionError> {
+        let caller = self.get_current_address()?;
+        match self.speculative_async_pool.get_message(msg_id) {
+            Some(msg) if msg.sender == caller => {}
+            Some(_) => {
+                return Err(ExecutionError::RuntimeError(format!(
+                    "message {:?} was not sent by {}",
+                    msg_id, caller
+                )))
+            }
+            None => {
+                return Err(ExecutionError::RuntimeError(format!(
+                    "message {:?} was not found",
+                    msg_id
+                )))
+            }
+        }
+        if let Some(msg) = self.speculative_async_pool.remove_message(msg_id) {
+            self.cancel_async_message(&msg);
+        }
+        Ok(())
+    }
+
+    /// Cancels an asynchronous message, reimbursing `msg.coins` to the sender,
+    /// along with the remaining gas budget of recurring messages
     ///
     /// # Arguments
     /// * `msg`: the asynchronous message to cancel
@@ -524,6 +631,24 @@ impl ExecutionContext {
                 msg.sender, e
             );
         }
+        if let Some(recurrence) = &msg.recurrence {
+            self.reimburse_gas_budget(msg, recurrence.gas_budget);
+        }
+    }
+
+    /// Reimburses `gas_budget * msg.gas_price` to the sender of a recurring asynchronous message
+    ///
+    /// # Arguments
+    /// * `msg`: the recurring asynchronous message
+    /// * `gas_budget`: the unused prepaid gas of the message
+    fn reimburse_gas_budget(&mut self, msg: &AsyncMessage, gas_budget: u64) {
+        let amount = msg.gas_price.saturating_mul_u64(gas_budget);
+        if let Err(e) = self.transfer_parallel_coins(None, Some(msg.sender), amount) {
+            debug!(
+                "async message gas budget: reimbursement of {} failed: {}",
+                msg.sender, e
+            );
+        }
     }
 
     /// Finishes a slot and generates the execution output.
@@ -550,6 +675,8 @@ impl ExecutionContext {
             block_id: std::mem::take(&mut self.opt_block_id),
             state_changes,
             events: std::mem::take(&mut self.events),
+            traces: std::mem::take(&mut self.traces),
+            receipts: std::mem::take(&mut self.receipts),
         }
     }
 
@@ -602,4 +729,54 @@ impl ExecutionContext {
 
         Ok(())
     }
+
+    /// Starts tracing the ABI calls of a new execution.
+    /// The trace is attributed to the current origin operation, if any.
+    pub fn start_trace(&mut self) {
+        self.trace = Some(ExecutionTrace {
+            origin_operation_id: self.origin_operation_id,
+            ..Default::default()
+        });
+    }
+
+    /// Finishes the ongoing trace, if any, and adds it to the traces of the context.
+    ///
+    /// # Arguments
+    /// * `gas_used`: gas consumed by the execution, if known
+    /// * `error`: error that stopped the execution, if any
+    pub fn finish_trace(&mut self, gas_used: Option<u64>, error: Option<String>) {
+        if let Some(mut trace) = self.trace.take() {
+            trace.gas_used = gas_used;
+            trace.error = error;
+            self.traces.push(trace);
+        }
+    }
+
+    /// Records an ABI call in the ongoing trace. Does nothing if the execution is not traced.
+    ///
+    /// # Arguments
+    /// * `name`: name of the ABI function
+    /// * `details`: lazily computes the arguments of the call and the ledger entries it accessed
+    /// * `result`: result of the call
+    pub fn trace_abi_call<T, E: std::fmt::Display>(
+        &mut self,
+        name: &str,
+        details: impl FnOnce() -> (String, Vec<LedgerAccess>),
+        result: &Result<T, E>,
+    ) {
+        if self.trace.is_none() {
+            return;
+        }
+        let (arguments, ledger_accesses) = details();
+        let call = AbiCallTrace {
+            name: name.to_string(),
+            arguments,
+            call_stack: self.get_call_stack(),
+            ledger_accesses,
+            error: result.as_ref().err().map(|err| err.to_string()),
+        };
+        if let Some(trace) = self.trace.as_mut() {
+            trace.abi_calls.push(call);
+        }
+    }
 }
@@ -4,20 +4,26 @@
 //! See `massa-execution-exports/controller_traits.rs` for functional details.
 
 use crate::execution::ExecutionState;
-use crate::request_queue::{RequestQueue, RequestWithResponseSender};
+use crate::readonly_executor::{ReadOnlyExecutorPool, ReadOnlyInputData};
+use crate::request_queue::RequestWithResponseSender;
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager, ExecutionOutput,
+    ExecutionController, ExecutionError, ExecutionManager, ExecutionOutput,
     ReadOnlyExecutionRequest,
 };
 use massa_hash::Hash;
 use massa_ledger::LedgerEntry;
-use massa_models::api::EventFilter;
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::{ExecutionReceipt, ExecutionTrace};
 use massa_models::output_event::SCOutputEvent;
+use massa_models::stats::ExecutionStats;
 use massa_models::Address;
-use massa_models::{BlockId, Slot};
+use massa_models::{BlockId, OperationId, Slot};
+use massa_time::MassaTime;
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::collections::HashMap;
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::info;
 
 /// structure used to communicate with execution thread
@@ -28,18 +34,15 @@ pub(crate) struct ExecutionInputData {
     pub finalized_blocks: HashMap<Slot, BlockId>,
     /// new blockclique (if there is a new one), blocks indexed by slot
     pub new_blockclique: Option<HashMap<Slot, BlockId>>,
-    /// queue for read-only execution requests and response MPSCs to send back their outputs
-    pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
 }
 
 impl ExecutionInputData {
     /// Creates a new empty `ExecutionInputData`
-    pub fn new(config: ExecutionConfig) -> Self {
+    pub fn new() -> Self {
         ExecutionInputData {
             stop: Default::default(),
             finalized_blocks: Default::default(),
             new_blockclique: Default::default(),
-            readonly_requests: RequestQueue::new(config.max_final_events),
         }
     }
 
@@ -50,7 +53,6 @@ impl ExecutionInputData {
             stop: std::mem::take(&mut self.stop),
             finalized_blocks: std::mem::take(&mut self.finalized_blocks),
             new_blockclique: std::mem::take(&mut self.new_blockclique),
-            readonly_requests: self.readonly_requests.take(),
         }
     }
 }
@@ -61,6 +63,10 @@ pub struct ExecutionControllerImpl {
     /// input data to process in the VM loop
     /// with a wake-up condition variable that needs to be triggered when the data changes
     pub(crate) input_data: Arc<(Condvar, Mutex<ExecutionInputData>)>,
+    /// input of the read-only executors (see readonly_executor.rs for details)
+    pub(crate) readonly_input_data: Arc<(Condvar, Mutex<ReadOnlyInputData>)>,
+    /// maximal time to wait for the output of a read-only request
+    pub(crate) readonly_timeout: MassaTime,
     /// current execution state (see execution.rs for details)
     pub(crate) execution_state: Arc<RwLock<ExecutionState>>,
 }
@@ -89,10 +95,32 @@ impl ExecutionController for ExecutionControllerImpl {
     /// * emitter address
     /// * original caller address
     /// * operation id
+    ///
+    /// At most `max_item_return_count` events are returned, and final events stored on disk
+    /// are read after releasing the execution state lock.
     fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent> {
+        let query = self
+            .execution_state
+            .read()
+            .get_filtered_sc_output_event(filter);
+        query.run()
+    }
+
+    /// Get the receipts of executed operations, final ones first
+    fn get_operation_receipts(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionReceipt> {
         self.execution_state
             .read()
-            .get_filtered_sc_output_event(filter)
+            .get_operation_receipts(&operation_ids)
+    }
+
+    /// Get the pending asynchronous messages matching a filter, by decreasing priority
+    fn get_async_messages(&self, filter: AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        self.execution_state.read().get_async_messages(&filter)
+    }
+
+    /// Get the execution performance stats aggregated separately over the latest final and active slot executions
+    fn get_stats(&self) -> ExecutionStats {
+        self.execution_state.read().get_stats()
     }
 
     /// Get a copy of a single datastore entry with its final and active values
@@ -124,15 +152,20 @@ impl ExecutionController for ExecutionControllerImpl {
 
     /// Executes a read-only request
     /// Read-only requests do not modify consensus state
+    ///
+    /// An `ExecutionError::ReadOnlyTimeout` is returned if the output is not available within the read-only timeout.
+    /// The request is cancelled if it has not started by then, and a running execution fails
+    /// at its next interface call, which frees its read-only executor.
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
     ) -> Result<ExecutionOutput, ExecutionError> {
+        let timeout = self.readonly_timeout.to_duration();
         let resp_rx = {
-            let mut input_data = self.input_data.1.lock();
+            let mut input_data = self.readonly_input_data.1.lock();
 
             // if the read-only queue is already full, return an error
-            if input_data.readonly_requests.is_full() {
+            if input_data.requests.is_full() {
                 return Err(ExecutionError::ChannelError(
                     "too many queued readonly requests".into(),
                 ));
@@ -142,20 +175,25 @@ impl ExecutionController for ExecutionControllerImpl {
             let (resp_tx, resp_rx) =
                 std::sync::mpsc::channel::<Result<ExecutionOutput, ExecutionError>>();
 
-            // append the request to the queue of input read-only requests
-            input_data
-                .readonly_requests
-                .push(RequestWithResponseSender::new(req, resp_tx));
+            // append the request to the queue of input read-only requests,
+            // along with the instant after which its output is not awaited anymore
+            input_data.requests.push(RequestWithResponseSender::new(
+                (Instant::now() + timeout, req),
+                resp_tx,
+            ));
 
-            // wake up the execution main loop
-            self.input_data.0.notify_one();
+            // wake up a read-only executor
+            self.readonly_input_data.0.notify_one();
 
             resp_rx
         };
 
-        // Wait for the result of the execution
-        match resp_rx.recv() {
+        // Wait for the result of the execution, at most until the timeout
+        match resp_rx.recv_timeout(timeout) {
             Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(ExecutionError::ReadOnlyTimeout(
+                "no response before the deadline".into(),
+            )),
             Err(err) => Err(ExecutionError::ChannelError(format!(
                 "readonly execution response channel readout failed: {}",
                 err
@@ -163,6 +201,13 @@ impl ExecutionController for ExecutionControllerImpl {
         }
     }
 
+    /// Get the execution traces of recently finalized operations
+    fn get_final_operation_traces(&self, operation_ids: Vec<OperationId>) -> Vec<ExecutionTrace> {
+        self.execution_state
+            .read()
+            .get_final_operation_traces(&operation_ids)
+    }
+
     /// Returns a boxed clone of self.
     /// Allows cloning `Box<dyn ExecutionController>`,
     /// see `massa-execution-exports/controller_traits.rs`
@@ -179,12 +224,16 @@ pub struct ExecutionManagerImpl {
     pub(crate) input_data: Arc<(Condvar, Mutex<ExecutionInputData>)>,
     /// handle used to join the worker thread
     pub(crate) thread_handle: Option<std::thread::JoinHandle<()>>,
+    /// pool of read-only executors to stop along with the worker
+    pub(crate) readonly_executors: ReadOnlyExecutorPool,
 }
 
 impl ExecutionManager for ExecutionManagerImpl {
     /// stops the worker
     fn stop(&mut self) {
         info!("stopping Execution controller...");
+        // stop the read-only executors, cancelling pending read-only requests
+        self.readonly_executors.stop();
         // notify the worker thread to stop
         {
             let mut input_wlock = self.input_data.1.lock();
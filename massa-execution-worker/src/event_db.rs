@@ -0,0 +1,475 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Module to store final smart contract events on disk
+//!
+//! Events are stored in the `events` column family, keyed by slot and index in slot,
+//! so that slot range queries are plain ordered iterations.
+//! Secondary indexes map emitter addresses, original caller addresses and origin operation IDs to event keys.
+//! Index keys are suffixed with the event key so that they are sorted by slot as well.
+
+use massa_execution_exports::EventStore;
+use massa_models::api::EventFilter;
+use massa_models::output_event::SCOutputEvent;
+use massa_models::Slot;
+use parking_lot::Mutex;
+use rocksdb::{
+    ColumnFamilyDescriptor, Direction, IteratorMode, Options, ReadOptions, WriteBatch, DB,
+};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+const EVENTS_CF: &str = "events";
+const EMITTER_INDEX_CF: &str = "emitter_index";
+const CALLER_INDEX_CF: &str = "caller_index";
+const OPERATION_INDEX_CF: &str = "operation_index";
+const METADATA_CF: &str = "metadata";
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const SERIALIZATION_ERROR: &str = "critical: event serialization failed";
+const SIZE_KEY: &[u8; 1] = b"z";
+
+/// Event key formatting macro: slot followed by the index of the event in that slot
+macro_rules! event_key {
+    ($event:expr) => {
+        [
+            &$event.context.slot.to_bytes_key()[..],
+            &$event.context.index_in_slot.to_be_bytes()[..],
+        ]
+        .concat()
+    };
+}
+
+/// Disk store of the final smart contract events
+///
+/// Contains a RocksDB DB instance and the retention limits of the store.
+/// The store can be shared between threads: queries do not block insertions.
+pub(crate) struct EventDB {
+    /// RocksDB instance
+    db: DB,
+    /// number of threads
+    thread_count: u8,
+    /// number of latest slots whose events are kept, `None` for no limit
+    retention_slots: Option<u64>,
+    /// maximal size in bytes of the stored events and their index entries, `None` for no limit
+    max_disk_size: Option<u64>,
+    /// current size in bytes of the stored events and their index entries,
+    /// the lock also serializes insertions and pruning
+    size: Mutex<u64>,
+}
+
+/// For a given start prefix (inclusive), returns the correct end prefix (non-inclusive).
+/// Returns `None` if there is no bounded limit.
+fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end_range = prefix.to_vec();
+    while let Some(0xff) = end_range.last() {
+        end_range.pop();
+    }
+    if let Some(byte) = end_range.last_mut() {
+        *byte += 1;
+        Some(end_range)
+    } else {
+        None
+    }
+}
+
+/// Get the index column families and key prefixes under which an event is referenced
+fn index_prefixes(event: &SCOutputEvent) -> Vec<(&'static str, Vec<u8>)> {
+    let mut prefixes = Vec::with_capacity(3);
+    if let Some(emitter) = event.context.call_stack.front() {
+        prefixes.push((EMITTER_INDEX_CF, emitter.to_bytes().to_vec()));
+    }
+    if let Some(caller) = event.context.call_stack.back() {
+        prefixes.push((CALLER_INDEX_CF, caller.to_bytes().to_vec()));
+    }
+    if let Some(operation_id) = event.context.origin_operation_id {
+        prefixes.push((OPERATION_INDEX_CF, operation_id.to_bytes().to_vec()));
+    }
+    prefixes
+}
+
+impl EventDB {
+    /// Create and initialize a new `EventDB`, reopening the existing one if any.
+    ///
+    /// # Arguments
+    /// * `path`: path to the desired disk event store directory
+    /// * `thread_count`: number of threads
+    /// * `retention_slots`: number of latest slots whose events are kept, `None` for no limit
+    /// * `max_disk_size`: maximal size in bytes of the store, `None` for no limit
+    pub fn new(
+        path: PathBuf,
+        thread_count: u8,
+        retention_slots: Option<u64>,
+        max_disk_size: Option<u64>,
+    ) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            path,
+            vec![
+                ColumnFamilyDescriptor::new(EVENTS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(EMITTER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(CALLER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(OPERATION_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+            ],
+        )
+        .expect(OPEN_ERROR);
+
+        let size = db
+            .get_cf(db.cf_handle(METADATA_CF).expect(CF_ERROR), SIZE_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| u64::from_be_bytes(bytes.try_into().expect(SERIALIZATION_ERROR)))
+            .unwrap_or_default();
+
+        EventDB {
+            db,
+            thread_count,
+            retention_slots,
+            max_disk_size,
+            size: Mutex::new(size),
+        }
+    }
+
+    /// Add final events to the store and prune it according to the retention limits.
+    /// Events are expected to be sorted by slot.
+    /// Events overwriting already stored ones replace them along with their index entries.
+    ///
+    /// The size limit never prunes the slots of the inserted events:
+    /// if they alone exceed `max_disk_size`, the store stays above it until newer events are inserted.
+    pub fn insert(&self, mut events: EventStore) {
+        let mut size = self.size.lock();
+        let events_handle = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let mut batch = WriteBatch::default();
+        let mut inserted_slots = None;
+
+        for event in events.take() {
+            let key = event_key!(event);
+            if let Some(old_value) = self.db.get_cf(events_handle, &key).expect(CRUD_ERROR) {
+                let old_event: SCOutputEvent =
+                    serde_json::from_slice(&old_value).expect(SERIALIZATION_ERROR);
+                for (cf, prefix) in index_prefixes(&old_event) {
+                    let index_key = [&prefix[..], &key[..]].concat();
+                    *size = size.saturating_sub(index_key.len() as u64);
+                    batch.delete_cf(self.db.cf_handle(cf).expect(CF_ERROR), index_key);
+                }
+                *size = size.saturating_sub((key.len() + old_value.len()) as u64);
+            }
+            for (cf, prefix) in index_prefixes(&event) {
+                let index_key = [&prefix[..], &key[..]].concat();
+                *size += index_key.len() as u64;
+                batch.put_cf(self.db.cf_handle(cf).expect(CF_ERROR), index_key, b"");
+            }
+            let value = serde_json::to_vec(&event).expect(SERIALIZATION_ERROR);
+            *size += (key.len() + value.len()) as u64;
+            batch.put_cf(events_handle, key, value);
+            let slot = event.context.slot;
+            inserted_slots = Some(inserted_slots.map_or((slot, slot), |(first, _)| (first, slot)));
+        }
+
+        let metadata_handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+        batch.put_cf(metadata_handle, SIZE_KEY, size.to_be_bytes());
+        self.db.write(batch).expect(CRUD_ERROR);
+
+        if let Some((first_slot, last_slot)) = inserted_slots {
+            self.prune(first_slot, last_slot, &mut size);
+        }
+    }
+
+    /// Get the first slot whose events must be kept given the latest stored slot,
+    /// or `None` if all slots can be kept
+    fn first_kept_slot(&self, last_slot: Slot) -> Option<Slot> {
+        let retention_slots = self.retention_slots?;
+        let thread_count = self.thread_count as u64;
+        let last_index = last_slot.period * thread_count + last_slot.thread as u64;
+        let first_index = (last_index + 1).checked_sub(retention_slots)?;
+        Some(Slot::new(
+            first_index / thread_count,
+            (first_index % thread_count) as u8,
+        ))
+    }
+
+    /// Remove the oldest events until the store fits the retention limits
+    ///
+    /// # Arguments
+    /// * `first_inserted_slot`: first slot of the events just inserted, which the size limit does not prune
+    /// * `last_slot`: latest stored slot
+    /// * `current_size`: locked current size of the store, updated by the pruning
+    fn prune(&self, first_inserted_slot: Slot, last_slot: Slot, current_size: &mut u64) {
+        let first_kept_key = self
+            .first_kept_slot(last_slot)
+            .map(|slot| slot.to_bytes_key());
+        let first_inserted_key = first_inserted_slot.to_bytes_key();
+        let events_handle = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let mut batch = WriteBatch::default();
+        let mut size = *current_size;
+
+        for (key, value) in self.db.iterator_cf(events_handle, IteratorMode::Start) {
+            let within_slots = first_kept_key.map_or(true, |first| key[..] >= first[..]);
+            let within_size = self.max_disk_size.map_or(true, |max| size <= max)
+                || key[..] >= first_inserted_key[..];
+            if within_slots && within_size {
+                break;
+            }
+            let event: SCOutputEvent = serde_json::from_slice(&value).expect(SERIALIZATION_ERROR);
+            for (cf, prefix) in index_prefixes(&event) {
+                let index_key = [&prefix[..], &key[..]].concat();
+                size = size.saturating_sub(index_key.len() as u64);
+                batch.delete_cf(self.db.cf_handle(cf).expect(CF_ERROR), index_key);
+            }
+            size = size.saturating_sub((key.len() + value.len()) as u64);
+            batch.delete_cf(events_handle, key);
+        }
+
+        if size != *current_size {
+            *current_size = size;
+            let metadata_handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
+            batch.put_cf(metadata_handle, SIZE_KEY, size.to_be_bytes());
+            self.db.write(batch).expect(CRUD_ERROR);
+        }
+    }
+
+    /// Get events optionally filtered by:
+    /// * start slot
+    /// * end slot
+    /// * emitter address
+    /// * original caller address
+    /// * operation id
+    ///
+    /// The most selective available index is used to restrict the events that are read,
+    /// and at most `max_count` events are returned, oldest first.
+    pub fn get_filtered_sc_output_event(
+        &self,
+        filter: &EventFilter,
+        max_count: usize,
+    ) -> VecDeque<SCOutputEvent> {
+        let events_handle = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let index = if let Some(operation_id) = filter.original_operation_id {
+            Some((OPERATION_INDEX_CF, operation_id.to_bytes().to_vec()))
+        } else if let Some(emitter) = filter.emitter_address {
+            Some((EMITTER_INDEX_CF, emitter.to_bytes().to_vec()))
+        } else {
+            filter
+                .original_caller_address
+                .map(|caller| (CALLER_INDEX_CF, caller.to_bytes().to_vec()))
+        };
+        let (cf, prefix) = index.unwrap_or((EVENTS_CF, Vec::new()));
+
+        // bound the iteration to the prefix and the slot range of the filter
+        let mut opt = ReadOptions::default();
+        let upper_bound = match filter.end {
+            Some(end) => Some([&prefix[..], &end.to_bytes_key()[..]].concat()),
+            None => end_prefix(&prefix),
+        };
+        if let Some(upper_bound) = upper_bound {
+            opt.set_iterate_upper_bound(upper_bound);
+        }
+        let lower_bound = match filter.start {
+            Some(start) => [&prefix[..], &start.to_bytes_key()[..]].concat(),
+            None => prefix.clone(),
+        };
+        let iterator = self.db.iterator_cf_opt(
+            self.db.cf_handle(cf).expect(CF_ERROR),
+            opt,
+            IteratorMode::From(&lower_bound, Direction::Forward),
+        );
+
+        let values: Box<dyn Iterator<Item = Vec<u8>>> = if cf == EVENTS_CF {
+            Box::new(iterator.map(|(_, value)| value.to_vec()))
+        } else {
+            Box::new(iterator.filter_map(|(key, _)| {
+                self.db
+                    .get_cf(events_handle, &key[prefix.len()..])
+                    .expect(CRUD_ERROR)
+            }))
+        };
+        values
+            .map(|value| serde_json::from_slice(&value).expect(SERIALIZATION_ERROR))
+            .filter(|event| filter.matches(event))
+            .take(max_count)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::output_event::EventExecutionContext;
+    use massa_models::{Address, OperationId};
+    use tempfile::TempDir;
+
+    const EMITTER: [u8; 32] = [1; 32];
+    const OTHER: [u8; 32] = [2; 32];
+    const OPERATION_ID: [u8; 32] = [3; 32];
+
+    /// event of the given slot and index, emitted by `emitter`,
+    /// and originating from `OPERATION_ID` if in period 1
+    fn event(slot: Slot, index_in_slot: u64, emitter: [u8; 32]) -> SCOutputEvent {
+        SCOutputEvent {
+            context: EventExecutionContext {
+                slot,
+                block: None,
+                read_only: false,
+                index_in_slot,
+                call_stack: vec![Address::from_bytes(&emitter)].into(),
+                origin_operation_id: Some(OperationId::from_bytes(&OPERATION_ID))
+                    .filter(|_| slot.period == 1),
+            },
+            data: format!("{}:{}", slot, index_in_slot),
+        }
+    }
+
+    fn store(events: Vec<SCOutputEvent>) -> EventStore {
+        let mut store = EventStore::default();
+        events.into_iter().for_each(|event| store.push(event));
+        store
+    }
+
+    /// store with events in slots (1, 0) to (2, 1)
+    fn sample_db(
+        temp_dir: &TempDir,
+        retention_slots: Option<u64>,
+        max_disk_size: Option<u64>,
+    ) -> EventDB {
+        let db = EventDB::new(
+            temp_dir.path().to_path_buf(),
+            2,
+            retention_slots,
+            max_disk_size,
+        );
+        db.insert(store(vec![
+            event(Slot::new(1, 0), 0, EMITTER),
+            event(Slot::new(1, 0), 1, OTHER),
+            event(Slot::new(1, 1), 0, EMITTER),
+        ]));
+        db.insert(store(vec![
+            event(Slot::new(2, 0), 0, EMITTER),
+            event(Slot::new(2, 1), 0, OTHER),
+        ]));
+        db
+    }
+
+    #[test]
+    fn test_filtered_queries() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, None, None);
+
+        let by_operation = db.get_filtered_sc_output_event(
+            &EventFilter {
+                original_operation_id: Some(OperationId::from_bytes(&OPERATION_ID)),
+                ..Default::default()
+            },
+            usize::MAX,
+        );
+        assert_eq!(by_operation.len(), 3);
+
+        let by_emitter = db.get_filtered_sc_output_event(
+            &EventFilter {
+                start: Some(Slot::new(1, 1)),
+                end: Some(Slot::new(2, 1)),
+                emitter_address: Some(Address::from_bytes(&EMITTER)),
+                ..Default::default()
+            },
+            usize::MAX,
+        );
+        let data: Vec<_> = by_emitter.iter().map(|event| event.data.as_str()).collect();
+        assert_eq!(
+            data,
+            vec!["(period: 1, thread: 1):0", "(period: 2, thread: 0):0"]
+        );
+    }
+
+    #[test]
+    fn test_max_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, None, None);
+
+        let capped = db.get_filtered_sc_output_event(&Default::default(), 2);
+        assert_eq!(capped.len(), 2);
+        assert_eq!(capped[1].context.index_in_slot, 1);
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, None, None);
+
+        // overwriting an event with itself keeps the size accounting stable
+        let size = *db.size.lock();
+        db.insert(store(vec![event(Slot::new(2, 1), 0, OTHER)]));
+        assert_eq!(*db.size.lock(), size);
+
+        // overwriting an event replaces its index entries
+        db.insert(store(vec![event(Slot::new(2, 1), 0, EMITTER)]));
+        let by_other = db.get_filtered_sc_output_event(
+            &EventFilter {
+                emitter_address: Some(Address::from_bytes(&OTHER)),
+                ..Default::default()
+            },
+            usize::MAX,
+        );
+        assert_eq!(by_other.len(), 1);
+        assert_eq!(by_other[0].context.slot, Slot::new(1, 0));
+    }
+
+    #[test]
+    fn test_retention_slots() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, Some(4), None);
+
+        // only the 4 latest slots are retained
+        db.insert(store(vec![event(Slot::new(3, 0), 0, EMITTER)]));
+        let all = db.get_filtered_sc_output_event(&Default::default(), usize::MAX);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].context.slot, Slot::new(1, 1));
+    }
+
+    #[test]
+    fn test_max_disk_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, None, None);
+        let size = *db.size.lock();
+        drop(db);
+
+        // the size is persisted across restarts
+        let db = EventDB::new(temp_dir.path().to_path_buf(), 2, None, Some(size));
+        assert_eq!(*db.size.lock(), size);
+
+        // the oldest events are pruned to make room for the new ones
+        db.insert(store(vec![event(Slot::new(3, 0), 0, EMITTER)]));
+        assert!(*db.size.lock() <= size);
+        let all = db.get_filtered_sc_output_event(&Default::default(), usize::MAX);
+        assert_eq!(all[0].context.slot, Slot::new(1, 0));
+        assert_eq!(all[0].context.index_in_slot, 1);
+        assert_eq!(all.back().unwrap().context.slot, Slot::new(3, 0));
+    }
+
+    #[test]
+    fn test_max_disk_size_keeps_inserted_slot() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sample_db(&temp_dir, None, None);
+        drop(db);
+        let db = EventDB::new(temp_dir.path().to_path_buf(), 2, None, Some(1));
+
+        // a slot whose events alone exceed the limit is kept, the older ones are pruned
+        db.insert(store(vec![
+            event(Slot::new(3, 1), 0, OTHER),
+            event(Slot::new(3, 1), 1, OTHER),
+        ]));
+        let all = db.get_filtered_sc_output_event(&Default::default(), usize::MAX);
+        assert_eq!(all.len(), 2);
+        assert!(all
+            .iter()
+            .all(|event| event.context.slot == Slot::new(3, 1)));
+        assert!(*db.size.lock() > 1);
+
+        // and pruned once newer events are inserted
+        db.insert(store(vec![event(Slot::new(4, 0), 0, OTHER)]));
+        let all = db.get_filtered_sc_output_event(&Default::default(), usize::MAX);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].context.slot, Slot::new(4, 0));
+    }
+}
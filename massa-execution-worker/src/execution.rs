@@ -1,6 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-//! This module deals with executing final and active slots, as well as read-only requests.
+//! This module deals with executing final and active slots, and with preparing the context of read-only requests.
 //! It also keeps a history of executed slots, thus holding the speculative state of the ledger.
 //!
 //! Execution usually happens in the following way:
@@ -9,20 +9,24 @@
 //! * the output of the execution is extracted from the context
 
 use crate::context::ExecutionContext;
+use crate::event_db::EventDB;
 use crate::interface_impl::InterfaceImpl;
-use massa_async_pool::AsyncMessage;
+use crate::stats::{ExecutionMetricsHistory, SlotExecutionMetrics};
+use massa_async_pool::{AsyncMessage, Change};
 use massa_execution_exports::{
     EventStore, ExecutionConfig, ExecutionError, ExecutionOutput, ExecutionStackElement,
-    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget, ReceiptStore,
 };
 use massa_final_state::{FinalState, StateChanges};
 use massa_hash::Hash;
 use massa_ledger::{
     Applicable, LedgerEntry, LedgerEntryUpdate, SetOrDelete, SetOrKeep, SetUpdateOrDelete,
 };
-use massa_models::api::EventFilter;
+use massa_models::api::{AsyncMessageFilter, AsyncMessageInfo, EventFilter};
+use massa_models::execution::{ExecutionReceipt, ExecutionTrace, ReceiptOrigin};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::signed::Signable;
+use massa_models::stats::ExecutionStats;
 use massa_models::{Address, BlockId, OperationId, OperationType, SignedOperation};
 use massa_models::{Amount, Slot};
 use massa_sc_runtime::Interface;
@@ -30,8 +34,9 @@ use massa_storage::Storage;
 use parking_lot::{Mutex, RwLock};
 use std::usize;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     sync::Arc,
+    time::Instant,
 };
 use tracing::debug;
 
@@ -58,6 +63,12 @@ pub(crate) struct ExecutionState {
     pub final_cursor: Slot,
     // store containing execution events that became final
     final_events: EventStore,
+    // optional disk store of the final execution events, replacing final_events when enabled
+    final_events_db: Option<Arc<EventDB>>,
+    // execution traces of the operations that became final, oldest at the front
+    final_traces: VecDeque<ExecutionTrace>,
+    // store containing execution receipts that became final
+    final_receipts: ReceiptStore,
     // final state with atomic R/W access
     final_state: Arc<RwLock<FinalState>>,
     // execution context (see documentation in context.rs)
@@ -66,6 +77,8 @@ pub(crate) struct ExecutionState {
     execution_interface: Box<dyn Interface>,
     /// Shared storage across all modules
     storage: Storage,
+    // performance metrics of the latest executed slots
+    metrics_history: ExecutionMetricsHistory,
 }
 
 pub(crate) enum HistorySearchResult<T> {
@@ -97,6 +110,7 @@ impl ExecutionState {
         let execution_context = Arc::new(Mutex::new(ExecutionContext::new(
             final_state.clone(),
             Default::default(),
+            config.storage_byte_cost,
         )));
 
         // Instantiate the interface providing ABI access to the VM, share the execution context with it
@@ -105,6 +119,19 @@ impl ExecutionState {
             execution_context.clone(),
         ));
 
+        // open the disk store of final events if enabled
+        let final_events_db = config.final_events_db_path.clone().map(|path| {
+            Arc::new(EventDB::new(
+                path,
+                config.thread_count,
+                config.final_events_retention_slots,
+                config.final_events_max_disk_size,
+            ))
+        });
+
+        // empty metrics history: execution performance is only measured locally
+        let metrics_history = ExecutionMetricsHistory::new(config.stats_history_length);
+
         // build the execution state
         ExecutionState {
             config,
@@ -115,10 +142,16 @@ impl ExecutionState {
             active_history: Default::default(),
             // empty final event store: it is not recovered through bootstrap
             final_events: Default::default(),
+            final_events_db,
+            // empty final traces: they are not recovered through bootstrap
+            final_traces: Default::default(),
+            // empty final receipt store: it is not recovered through bootstrap
+            final_receipts: Default::default(),
             // no active slots executed yet: set active_cursor to the last final block
             active_cursor: last_final_slot,
             final_cursor: last_final_slot,
             storage,
+            metrics_history,
         }
     }
 
@@ -154,8 +187,22 @@ impl ExecutionState {
         }
 
         // append generated events to the final event store
-        self.final_events.extend(exec_out.events);
-        self.final_events.prune(self.config.max_final_events);
+        if let Some(final_events_db) = self.final_events_db.as_ref() {
+            final_events_db.insert(exec_out.events);
+        } else {
+            self.final_events.extend(exec_out.events);
+            self.final_events.prune(self.config.max_final_events);
+        }
+
+        // append generated receipts to the final receipt store
+        self.final_receipts.extend(exec_out.receipts, true);
+        self.final_receipts.prune(self.config.max_final_receipts);
+
+        // append generated operation traces to the final traces, dropping the oldest ones
+        self.final_traces.extend(exec_out.traces);
+        while self.final_traces.len() > self.config.max_final_traces {
+            self.final_traces.pop_front();
+        }
     }
 
     /// Applies an execution output to the active (non-final) state
@@ -357,20 +404,24 @@ impl ExecutionState {
 
     /// Execute an operation in the context of a block.
     /// Assumes the execution context was initialized at the beginning of the slot.
+    /// A receipt is added to the context for `ExecuteSC` and `CallSC` operations.
     ///
     /// # Arguments
     /// * `operation`: operation to execute
     /// * `block_creator_addr`: address of the block creator
+    ///
+    /// # Returns
+    /// The gas used by the execution (zero for operations that do not run smart contracts), or an error
     pub fn execute_operation(
         &self,
         operation: &SignedOperation,
         block_creator_addr: Address,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // prefilter only SC operations
         match &operation.content.op {
             OperationType::ExecuteSC { .. } => {}
             OperationType::CallSC { .. } => {}
-            _ => return Ok(()),
+            _ => return Ok(0),
         };
 
         // get the operation's sender address
@@ -385,22 +436,81 @@ impl ExecutionState {
             .compute_id()
             .expect("could not compute operation ID");
 
-        // call the execution process specific to the operation type
-        match &operation.content.op {
-            OperationType::ExecuteSC { .. } => self.execute_executesc_op(
-                &operation.content.op,
-                block_creator_addr,
-                operation_id,
-                sender_addr,
+        // call the execution process specific to the operation type,
+        // and compute the gas fees and the coins sent to the target
+        let (result, fee, coins) = match &operation.content.op {
+            OperationType::ExecuteSC {
+                max_gas, gas_price, ..
+            } => (
+                self.execute_executesc_op(
+                    &operation.content.op,
+                    block_creator_addr,
+                    operation_id,
+                    sender_addr,
+                ),
+                gas_price.saturating_mul_u64(*max_gas),
+                Amount::default(),
             ),
-            OperationType::CallSC { .. } => self.execute_callsc_op(
-                &operation.content.op,
-                block_creator_addr,
-                operation_id,
-                sender_addr,
+            OperationType::CallSC {
+                max_gas,
+                gas_price,
+                parallel_coins,
+                sequential_coins,
+                ..
+            } => (
+                self.execute_callsc_op(
+                    &operation.content.op,
+                    block_creator_addr,
+                    operation_id,
+                    sender_addr,
+                ),
+                gas_price.saturating_mul_u64(*max_gas),
+                sequential_coins.saturating_add(*parallel_coins),
             ),
             _ => panic!("unexpected operation type"), // checked at the beginning of the function
-        }
+        };
+        self.push_receipt(ReceiptOrigin::Operation(operation_id), &result, fee, coins);
+        result
+    }
+
+    /// Adds the receipt of an execution to the context.
+    /// The addresses created during the execution are taken from the context.
+    ///
+    /// # Arguments
+    /// * `origin`: what was executed
+    /// * `result`: gas used by the execution, or the error that made it fail
+    /// * `fee`: gas fees paid whatever the outcome
+    /// * `coins`: coins sent to the target, only spent if the execution succeeded
+    fn push_receipt(
+        &self,
+        origin: ReceiptOrigin,
+        result: &Result<u64, ExecutionError>,
+        fee: Amount,
+        coins: Amount,
+    ) {
+        let mut context = context_guard!(self);
+        let created_addresses = std::mem::take(&mut context.created_addresses);
+        let receipt = match result {
+            Ok(gas_used) => ExecutionReceipt {
+                origin,
+                slot: context.slot,
+                is_final: false,
+                error: None,
+                gas_used: Some(*gas_used),
+                coins_spent: fee.saturating_add(coins),
+                created_addresses,
+            },
+            Err(err) => ExecutionReceipt {
+                origin,
+                slot: context.slot,
+                is_final: false,
+                error: Some(err.to_string()),
+                gas_used: None,
+                coins_spent: fee,
+                created_addresses: Vec::new(),
+            },
+        };
+        context.receipts.push(receipt);
     }
 
     /// Execute an operation of type `ExecuteSC`
@@ -411,13 +521,16 @@ impl ExecutionState {
     /// * `block_creator_addr`: address of the block creator
     /// * `operation_id`: ID of the operation
     /// * `sender_addr`: address of the sender
+    ///
+    /// # Returns
+    /// The gas used by the execution, or an error
     pub fn execute_executesc_op(
         &self,
         operation: &OperationType,
         block_creator_addr: Address,
         operation_id: OperationId,
         sender_addr: Address,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // process ExecuteSC operations only
         let (bytecode, max_gas, coins, gas_price) = match &operation {
             OperationType::ExecuteSC {
@@ -479,23 +592,32 @@ impl ExecutionState {
 
             // set the context origin operation ID
             context.origin_operation_id = Some(operation_id);
+
+            // record the ABI calls of the operation if enabled
+            if self.config.trace_operations {
+                context.start_trace();
+            }
         };
 
         // run the VM on the bytecode contained in the operation
         let run_result = massa_sc_runtime::run_main(bytecode, *max_gas, &*self.execution_interface);
-        if let Err(err) = run_result {
-            // there was an error during bytecode execution:
-            // cancel the effects of the execution by resetting the context to the previously saved snapshot
-            let mut context = context_guard!(self);
-            context.origin_operation_id = None;
-            context.reset_to_snapshot(context_snapshot);
-            return Err(ExecutionError::RuntimeError(format!(
-                "bytecode execution error: {}",
-                err
-            )));
+        let mut context = context_guard!(self);
+        match run_result {
+            Ok(remaining_gas) => {
+                let gas_used = max_gas.saturating_sub(remaining_gas);
+                context.finish_trace(Some(gas_used), None);
+                Ok(gas_used)
+            }
+            Err(err) => {
+                // there was an error during bytecode execution:
+                // cancel the effects of the execution by resetting the context to the previously saved snapshot
+                let err = format!("bytecode execution error: {}", err);
+                context.finish_trace(None, Some(err.clone()));
+                context.origin_operation_id = None;
+                context.reset_to_snapshot(context_snapshot);
+                Err(ExecutionError::RuntimeError(err))
+            }
         }
-
-        Ok(())
     }
 
     /// Execute an operation of type `CallSC`
@@ -506,13 +628,16 @@ impl ExecutionState {
     /// * `block_creator_addr`: address of the block creator
     /// * `operation_id`: ID of the operation
     /// * `sender_addr`: address of the sender
+    ///
+    /// # Returns
+    /// The gas used by the execution, or an error
     pub fn execute_callsc_op(
         &self,
         operation: &OperationType,
         block_creator_addr: Address,
         operation_id: OperationId,
         sender_addr: Address,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // process CallSC operations only
         let (gas_price, max_gas, target_addr, target_func, param, parallel_coins, sequential_coins) =
             match &operation {
@@ -589,6 +714,11 @@ impl ExecutionState {
             // set the context origin operation ID
             context.origin_operation_id = Some(operation_id);
 
+            // record the ABI calls of the operation if enabled
+            if self.config.trace_operations {
+                context.start_trace();
+            }
+
             // Set the call stack o the sender addr only to allow it to send parallel coins (access rights)
             context.stack = vec![ExecutionStackElement {
                 address: sender_addr,
@@ -601,12 +731,14 @@ impl ExecutionState {
                 context.transfer_parallel_coins(Some(sender_addr), Some(target_addr), coins)
             {
                 // cancel the effects of the execution by resetting the context to the previously saved snapshot
-                context.origin_operation_id = None;
-                context.reset_to_snapshot(context_snapshot);
-                return Err(ExecutionError::RuntimeError(format!(
+                let err = format!(
                     "failed to transfer {} call coins from {} to {}: {}",
                     coins, sender_addr, target_addr, err
-                )));
+                );
+                context.finish_trace(None, Some(err.clone()));
+                context.origin_operation_id = None;
+                context.reset_to_snapshot(context_snapshot);
+                return Err(ExecutionError::RuntimeError(err));
             }
 
             // Add the second part of the stack (the target)
@@ -619,7 +751,8 @@ impl ExecutionState {
 
         // quit if there is no function to be called
         if target_func.is_empty() {
-            return Ok(());
+            context_guard!(self).finish_trace(Some(0), None);
+            return Ok(0);
         }
 
         // run the VM on the called fucntion of the bytecode
@@ -630,19 +763,23 @@ impl ExecutionState {
             param,
             &*self.execution_interface,
         );
-        if let Err(err) = run_result {
-            // there was an error during bytecode execution:
-            // cancel the effects of the execution by resetting the context to the previously saved snapshot
-            let mut context = context_guard!(self);
-            context.origin_operation_id = None;
-            context.reset_to_snapshot(context_snapshot);
-            return Err(ExecutionError::RuntimeError(format!(
-                "bytecode execution error: {}",
-                err
-            )));
+        let mut context = context_guard!(self);
+        match run_result {
+            Ok(remaining_gas) => {
+                let gas_used = max_gas.saturating_sub(remaining_gas);
+                context.finish_trace(Some(gas_used), None);
+                Ok(gas_used)
+            }
+            Err(err) => {
+                // there was an error during bytecode execution:
+                // cancel the effects of the execution by resetting the context to the previously saved snapshot
+                let err = format!("bytecode execution error: {}", err);
+                context.finish_trace(None, Some(err.clone()));
+                context.origin_operation_id = None;
+                context.reset_to_snapshot(context_snapshot);
+                Err(ExecutionError::RuntimeError(err))
+            }
         }
-
-        Ok(())
     }
 
     /// Tries to execute an asynchronous message
@@ -651,11 +788,14 @@ impl ExecutionState {
     /// # Arguments
     /// * message: message information
     /// * bytecode: executable target bytecode, or None if unavailable
+    ///
+    /// # Returns
+    /// The gas used by the execution, or an error
     pub fn execute_async_message(
         &self,
         message: AsyncMessage,
         bytecode: Option<Vec<u8>>,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<u64, ExecutionError> {
         // If there is no target bytecode or if message data is invalid,
         // directly reimburse sender with coins and quit
         let (bytecode, data) = match (bytecode, std::str::from_utf8(&message.data)) {
@@ -708,23 +848,24 @@ impl ExecutionState {
         }
 
         // run the target function
-        if let Err(err) = massa_sc_runtime::run_function(
+        match massa_sc_runtime::run_function(
             &bytecode,
             message.max_gas,
             &message.handler,
             data,
             &*self.execution_interface,
         ) {
-            // execution failed: reset context to snapshot and reimburse sender
-            let mut context = context_guard!(self);
-            context.reset_to_snapshot(context_snapshot);
-            context.cancel_async_message(&message);
-            Err(ExecutionError::RuntimeError(format!(
-                "async message runtime execution error: {}",
-                err
-            )))
-        } else {
-            Ok(())
+            Ok(remaining_gas) => Ok(message.max_gas.saturating_sub(remaining_gas)),
+            Err(err) => {
+                // execution failed: reset context to snapshot and reimburse sender
+                let mut context = context_guard!(self);
+                context.reset_to_snapshot(context_snapshot);
+                context.cancel_async_message(&message);
+                Err(ExecutionError::RuntimeError(format!(
+                    "async message runtime execution error: {}",
+                    err
+                )))
+            }
         }
     }
 
@@ -734,10 +875,19 @@ impl ExecutionState {
     /// # Arguments
     /// * `slot`: slot to execute
     /// * `opt_block`: block ID if there is a block a that slot, otherwise None
+    /// * `is_final`: whether the slot is executed as final, used to tag its performance metrics
     ///
     /// # Returns
     /// An `ExecutionOutput` structure summarizing the output of the executed slot
-    pub fn execute_slot(&self, slot: Slot, opt_block_id: Option<BlockId>) -> ExecutionOutput {
+    pub fn execute_slot(
+        &mut self,
+        slot: Slot,
+        opt_block_id: Option<BlockId>,
+        is_final: bool,
+    ) -> ExecutionOutput {
+        let slot_start = Instant::now();
+        let mut metrics = SlotExecutionMetrics::new(slot, is_final);
+
         // accumulate previous active changes from output history
         let previous_changes = self.get_accumulated_active_changes_at_slot(slot);
 
@@ -747,6 +897,7 @@ impl ExecutionState {
             opt_block_id,
             previous_changes,
             self.final_state.clone(),
+            self.config.storage_byte_cost,
         );
 
         // note that here, some pre-operations (like crediting block producers) can be performed before the lock
@@ -760,9 +911,20 @@ impl ExecutionState {
         // Try executing asynchronous messages.
         // Effects are cancelled on failure and the sender is reimbursed.
         for (opt_bytecode, message) in messages {
-            if let Err(err) = self.execute_async_message(message, opt_bytecode) {
-                debug!("failed executing async message: {}", err);
+            let origin = ReceiptOrigin::AsyncMessage {
+                emission_slot: message.emission_slot,
+                emission_index: message.emission_index,
+            };
+            let coins = message.coins;
+            let message_start = Instant::now();
+            let result = self.execute_async_message(message, opt_bytecode);
+            metrics.async_message_time += message_start.elapsed();
+            metrics.async_message_count += 1;
+            match &result {
+                Ok(gas_used) => metrics.async_message_gas_used += gas_used,
+                Err(err) => debug!("failed executing async message: {}", err),
             }
+            self.push_receipt(origin, &result, Amount::default(), coins);
         }
 
         // check if there is a block at this slot
@@ -775,92 +937,127 @@ impl ExecutionState {
             // Try executing the operations of this block in the order in which they appear in the block.
             // Errors are logged but do not interrupt the execution of the slot.
             for (op_idx, operation) in stored_block.block.operations.iter().enumerate() {
-                if let Err(err) = self.execute_operation(
+                let op_start = Instant::now();
+                let result = self.execute_operation(
                     operation,
                     Address::from_public_key(&stored_block.block.header.content.creator),
-                ) {
-                    debug!(
+                );
+                metrics.operation_time += op_start.elapsed();
+                metrics.operation_count += 1;
+                match result {
+                    Ok(gas_used) => metrics.operation_gas_used += gas_used,
+                    Err(err) => debug!(
                         "failed executing operation index {} in block {}: {}",
                         op_idx, block_id, err
-                    );
+                    ),
                 }
             }
         }
 
-        // finish slot and return the execution output
-        context_guard!(self).settle_slot()
+        // finish slot
+        let exec_out = {
+            let mut context = context_guard!(self);
+            let (read_count, final_read_count) = context.get_ledger_read_counts();
+            metrics.ledger_read_count = read_count;
+            metrics.final_ledger_read_count = final_read_count;
+            context.settle_slot()
+        };
+
+        // record the performance of the slot execution
+        metrics.total_time = slot_start.elapsed();
+        metrics.log_if_slow(self.config.slow_slot_threshold.to_duration());
+        self.metrics_history.push(metrics);
+
+        exec_out
+    }
+
+    /// Gets the execution performance statistics aggregated over the latest final and active slot executions
+    pub fn get_stats(&self) -> ExecutionStats {
+        self.metrics_history.get_stats()
     }
 
-    /// Runs a read-only execution request.
-    /// The executed bytecode appears to be able to read and write the consensus state,
-    /// but all accumulated changes are simply returned as an `ExecutionOutput` object,
-    /// and not actually applied to the consensus state.
+    /// Creates the execution context of a read-only execution request,
+    /// from a snapshot of the current execution state.
+    /// The context holds its own copy of the active changes,
+    /// so that the request can be executed without holding access to the execution state.
     ///
     /// # Arguments
     /// * `req`: a read-only execution request
     ///
     /// # Returns
-    ///  `ExecutionOutput` describing the output of the execution, or an error
-    pub(crate) fn execute_readonly_request(
+    /// The `ExecutionContext` in which the request must be executed, or an error
+    pub(crate) fn get_readonly_context(
         &self,
-        req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
-        // set the execution slot to be the one after the latest executed active slot
-        let slot = self
-            .active_cursor
-            .get_next_slot(self.config.thread_count)
-            .expect("slot overflow in readonly execution");
+        req: &ReadOnlyExecutionRequest,
+    ) -> Result<ExecutionContext, ExecutionError> {
+        let (next_slot, previous_changes) = match req.final_slot {
+            // view the final state as it was at the output of the requested final slot
+            Some(final_slot) => {
+                let previous_changes = self
+                    .final_state
+                    .read()
+                    .get_changes_reverting_to_slot(final_slot)
+                    .map_err(|err| ExecutionError::FinalStateError(err.to_string()))?;
+                let next_slot = final_slot.get_next_slot(self.config.thread_count)?;
+                (next_slot, previous_changes)
+            }
+            // the execution state is the one after the latest executed active slot
+            None => {
+                let next_slot = self
+                    .active_cursor
+                    .get_next_slot(self.config.thread_count)
+                    .expect("slot overflow in readonly execution");
+
+                // accumulate state changes that happened in the output history before this slot
+                (
+                    next_slot,
+                    self.get_accumulated_active_changes_at_slot(next_slot),
+                )
+            }
+        };
 
-        // accumulate state changes that happened in the output history before this slot
-        let previous_changes = self.get_accumulated_active_changes_at_slot(slot);
+        // set the execution slot to the simulated one if any
+        let slot = req.simulated_slot.unwrap_or(next_slot);
 
         // create a readonly execution context
-        let execution_context = ExecutionContext::readonly(
+        let mut execution_context = ExecutionContext::readonly(
             slot,
             req.max_gas,
             req.simulated_gas_price,
-            req.call_stack,
+            req.call_stack.clone(),
             previous_changes,
             self.final_state.clone(),
+            self.config.storage_byte_cost,
         );
 
-        // run the intepreter according to the target type
-        match req.target {
-            ReadOnlyExecutionTarget::BytecodeExecution(bytecode) => {
-                // set the execution context for execution
-                *context_guard!(self) = execution_context;
+        // pretend the requested ledger state for the duration of the execution
+        execution_context.apply_ledger_overrides(req.state_overrides.clone());
 
-                // run the bytecode's main function
-                massa_sc_runtime::run_main(&bytecode, req.max_gas, &*self.execution_interface)
-                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
-            }
-            ReadOnlyExecutionTarget::FunctionCall {
-                target_addr,
-                target_func,
-                parameter,
-            } => {
-                // get the bytecode, default to an empty vector
-                let bytecode = execution_context
-                    .get_bytecode(&target_addr)
-                    .unwrap_or_default();
-
-                // set the execution context for execution
-                *context_guard!(self) = execution_context;
-
-                // run the target function in the bytecode
-                massa_sc_runtime::run_function(
-                    &bytecode,
-                    req.max_gas,
-                    &target_func,
-                    &parameter,
-                    &*self.execution_interface,
-                )
-                .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
+        // record the ABI calls of the execution if requested
+        if req.trace {
+            execution_context.start_trace();
+        }
+
+        // transfer the call coins from the caller to the target,
+        // with a call stack reduced to the caller to give it spending rights
+        if let ReadOnlyExecutionTarget::FunctionCall {
+            target_addr, coins, ..
+        } = &req.target
+        {
+            if *coins > Amount::default() {
+                let call_stack = std::mem::take(&mut execution_context.stack);
+                execution_context.stack = call_stack.iter().take(1).cloned().collect();
+                let transfer_result = execution_context.transfer_parallel_coins(
+                    call_stack.first().map(|caller| caller.address),
+                    Some(*target_addr),
+                    *coins,
+                );
+                execution_context.stack = call_stack;
+                transfer_result?;
             }
         }
 
-        // return the execution output
-        Ok(context_guard!(self).settle_slot())
+        Ok(execution_context)
     }
 
     /// Gets a parallel balance both at the latest final and active executed slots
@@ -959,21 +1156,158 @@ impl ExecutionState {
         (final_entry, active_entry)
     }
 
-    /// Gets execution events optionally filtered by:
-    /// * start slot
-    /// * end slot
-    /// * emitter address
-    /// * original caller address
-    /// * operation id
-    pub fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent> {
-        self.final_events
-            .get_filtered_sc_output_event(&filter)
+    /// Gets the receipts of executed operations, final ones first and in execution order.
+    ///
+    /// # Arguments
+    /// * `operation_ids`: IDs of the operations
+    pub fn get_operation_receipts(&self, operation_ids: &[OperationId]) -> Vec<ExecutionReceipt> {
+        self.final_receipts
+            .get_operation_receipts(operation_ids)
             .into_iter()
             .chain(
                 self.active_history
                     .iter()
-                    .flat_map(|item| item.events.get_filtered_sc_output_event(&filter)),
+                    .flat_map(|item| item.receipts.get_operation_receipts(operation_ids)),
             )
             .collect()
     }
+
+    /// Gets the execution traces of final operations, in execution order.
+    /// Operations that were not traced, or whose traces were pruned, are ignored.
+    ///
+    /// # Arguments
+    /// * `operation_ids`: IDs of the operations
+    pub fn get_final_operation_traces(&self, operation_ids: &[OperationId]) -> Vec<ExecutionTrace> {
+        self.final_traces
+            .iter()
+            .filter(|trace| match &trace.origin_operation_id {
+                Some(id) => operation_ids.contains(id),
+                None => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Gets the pending asynchronous messages matching a filter,
+    /// taking into account the messages emitted and consumed by active executions.
+    /// Messages are sorted by decreasing priority.
+    pub fn get_async_messages(&self, filter: &AsyncMessageFilter) -> Vec<AsyncMessageInfo> {
+        // start from the final pool
+        let mut messages: BTreeMap<_, _> = self
+            .final_state
+            .read()
+            .async_pool
+            .iter()
+            .map(|(id, msg)| (id, async_message_info(&msg, true)))
+            .filter(|(_id, info)| filter.matches(info))
+            .collect();
+
+        // apply the asynchronous pool changes of active executions in order
+        for output in &self.active_history {
+            for change in &output.state_changes.async_pool_changes.0 {
+                match change {
+                    Change::Add(id, msg) => {
+                        let info = async_message_info(msg, false);
+                        if filter.matches(&info) {
+                            messages.insert(*id, info);
+                        }
+                    }
+                    Change::Delete(id) => {
+                        messages.remove(id);
+                    }
+                }
+            }
+        }
+
+        messages.into_values().collect()
+    }
+
+    /// Prepares a query of the execution events optionally filtered by:
+    /// * start slot
+    /// * end slot
+    /// * emitter address
+    /// * original caller address
+    /// * operation id
+    ///
+    /// Events held in memory are collected right away.
+    /// Final events stored on disk are read by `EventQuery::run`, which does not need the execution state.
+    pub fn get_filtered_sc_output_event(&self, filter: EventFilter) -> EventQuery {
+        let max_count = self.config.max_item_return_count;
+        let mut events: Vec<SCOutputEvent> = Vec::new();
+        let final_events_db = match &self.final_events_db {
+            Some(final_events_db) => {
+                // only read the slots that are final at this point,
+                // later ones are part of the collected active events
+                let mut db_filter = filter.clone();
+                if let Ok(next_slot) = self.final_cursor.get_next_slot(self.config.thread_count) {
+                    db_filter.end = Some(filter.end.map_or(next_slot, |end| end.min(next_slot)));
+                }
+                Some((final_events_db.clone(), db_filter))
+            }
+            None => {
+                events.extend(self.final_events.get_filtered_sc_output_event(&filter));
+                None
+            }
+        };
+        events.extend(
+            self.active_history
+                .iter()
+                .flat_map(|item| item.events.get_filtered_sc_output_event(&filter)),
+        );
+        events.truncate(max_count);
+        EventQuery {
+            final_events_db,
+            events,
+            max_count,
+        }
+    }
+}
+
+/// Query of execution events prepared by `ExecutionState::get_filtered_sc_output_event`
+pub(crate) struct EventQuery {
+    /// disk store of the final events with the filter to apply to it, if final events are stored on disk
+    final_events_db: Option<(Arc<EventDB>, EventFilter)>,
+    /// events collected from memory, oldest first
+    events: Vec<SCOutputEvent>,
+    /// maximal number of returned events
+    max_count: usize,
+}
+
+impl EventQuery {
+    /// Reads the final events from disk if needed and returns all the matching events, oldest first
+    pub fn run(self) -> Vec<SCOutputEvent> {
+        match self.final_events_db {
+            Some((final_events_db, filter)) => {
+                let mut events: Vec<SCOutputEvent> = final_events_db
+                    .get_filtered_sc_output_event(&filter, self.max_count)
+                    .into();
+                events.extend(self.events);
+                events.truncate(self.max_count);
+                events
+            }
+            None => self.events,
+        }
+    }
+}
+
+/// Describes an asynchronous message for the API
+///
+/// # Arguments
+/// * `msg`: the asynchronous message
+/// * `is_final`: whether the message is in the final pool
+fn async_message_info(msg: &AsyncMessage, is_final: bool) -> AsyncMessageInfo {
+    AsyncMessageInfo {
+        emission_slot: msg.emission_slot,
+        emission_index: msg.emission_index,
+        sender: msg.sender,
+        destination: msg.destination,
+        handler: msg.handler.clone(),
+        max_gas: msg.max_gas,
+        gas_price: msg.gas_price,
+        coins: msg.coins,
+        validity_start: msg.validity_start,
+        validity_end: msg.validity_end,
+        data: msg.data.clone(),
+        is_final,
+    }
 }
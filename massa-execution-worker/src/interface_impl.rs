@@ -6,23 +6,34 @@
 //! See the definition of Interface in the massa-sc-runtime crate for functional details.
 
 use crate::context::ExecutionContext;
-use anyhow::{bail, Result};
-use massa_async_pool::AsyncMessage;
+use anyhow::{anyhow, bail, Result};
+use massa_async_pool::{AsyncMessage, AsyncMessageRecurrence};
 use massa_execution_exports::ExecutionConfig;
 use massa_execution_exports::ExecutionStackElement;
+use massa_models::execution::LedgerAccess;
 use massa_models::{timeslots::get_block_slot_timestamp, Address, Amount, Slot};
 use massa_sc_runtime::{Interface, InterfaceClone};
 use parking_lot::Mutex;
 use rand::Rng;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::debug;
 
-/// helper for locking the context mutex
+/// helper for locking the context mutex.
+/// Fails the interface call if the deadline of a read-only execution is reached,
+/// so that the execution stops without waiting for its gas to run out.
 macro_rules! context_guard {
-    ($self:ident) => {
-        $self.context.lock()
-    };
+    ($self:ident) => {{
+        let context = $self.context.lock();
+        if context
+            .deadline
+            .map_or(false, |deadline| Instant::now() >= deadline)
+        {
+            bail!("read-only execution deadline reached");
+        }
+        context
+    }};
 }
 
 /// an implementation of the Interface trait (see massa-sc-runtime crate)
@@ -43,6 +54,177 @@ impl InterfaceImpl {
     pub fn new(config: ExecutionConfig, context: Arc<Mutex<ExecutionContext>>) -> InterfaceImpl {
         InterfaceImpl { config, context }
     }
+
+    /// Sends an asynchronous message that is executed up to `max_repetitions` times,
+    /// every `period` periods after its previous execution.
+    ///
+    /// The coins and the fees of all the repetitions are locked from the current address when sending:
+    /// `raw_coins`, only given to the target at the first execution,
+    /// and `max_gas * gas_price` for each repetition after the first one.
+    /// The fees of the repetitions that do not happen are reimbursed when the message is discarded.
+    ///
+    /// NOTE: not part of the `Interface` trait of the current massa-sc-runtime,
+    /// smart contracts can call it once the runtime exposes it
+    ///
+    /// # Arguments
+    /// * `target_address`: Destination address hash in format string
+    /// * `target_handler`: Name of the message handling function
+    /// * `validity_start`: Tuple containing the period and thread of the validity start slot of the first execution
+    /// * `validity_end`: Tuple containing the period and thread of the validity end slot of the first execution
+    /// * `max_gas`: Maximum gas for each execution of the message
+    /// * `gas_price`: Price of one gas unit
+    /// * `raw_coins`: Coins given by the sender
+    /// * `data`: Message data
+    /// * `period`: Number of periods between two executions of the message
+    /// * `max_repetitions`: Maximum number of executions of the message
+    #[allow(dead_code)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_recurring_message(
+        &self,
+        target_address: &str,
+        target_handler: &str,
+        validity_start: (u64, u8),
+        validity_end: (u64, u8),
+        max_gas: u64,
+        gas_price: u64,
+        raw_coins: u64,
+        data: &[u8],
+        period: u64,
+        max_repetitions: u64,
+    ) -> Result<()> {
+        if validity_start.1 >= self.config.thread_count {
+            bail!("validity start thread exceeds the configuration thread count")
+        }
+        if validity_end.1 >= self.config.thread_count {
+            bail!("validity end thread exceeds the configuration thread count")
+        }
+        if target_handler.len() > u8::MAX as usize {
+            bail!(
+                "handler name length exceeds the maximum of {} bytes",
+                u8::MAX
+            )
+        }
+        if period == 0 {
+            bail!("recurrence period must be at least 1")
+        }
+        if max_repetitions == 0 {
+            bail!("maximum repetition count must be at least 1")
+        }
+        let remaining_count = max_repetitions - 1;
+        let gas_budget = max_gas
+            .checked_mul(remaining_count)
+            .ok_or_else(|| anyhow!("overflow when computing the gas budget of the repetitions"))?;
+        let mut execution_context = context_guard!(self);
+        let emission_slot = execution_context.slot;
+        let emission_index = execution_context.created_message_index;
+        let sender = execution_context.get_current_address()?;
+        let message = AsyncMessage {
+            emission_slot,
+            emission_index,
+            sender,
+            destination: Address::from_str(target_address)?,
+            handler: target_handler.to_string(),
+            validity_start: Slot::new(validity_start.0, validity_start.1),
+            validity_end: Slot::new(validity_end.0, validity_end.1),
+            max_gas,
+            gas_price: Amount::from_raw(gas_price),
+            coins: Amount::from_raw(raw_coins),
+            data: data.to_vec(),
+            recurrence: Some(AsyncMessageRecurrence {
+                period,
+                remaining_count,
+                gas_budget,
+            }),
+        };
+        let result = execution_context.push_new_message(message.clone());
+        execution_context.trace_abi_call(
+            "send_recurring_message",
+            || {
+                (
+                    format!(
+                        "{}, {}, {} to {}, {}, {}, {}, {} bytes, every {} periods, {} times",
+                        message.destination,
+                        message.handler,
+                        message.validity_start,
+                        message.validity_end,
+                        message.max_gas,
+                        message.gas_price,
+                        message.coins,
+                        message.data.len(),
+                        period,
+                        max_repetitions
+                    ),
+                    vec![write_access(sender, "balance")],
+                )
+            },
+            &result,
+        );
+        result?;
+        execution_context.created_message_index += 1;
+        Ok(())
+    }
+
+    /// Cancels an asynchronous message sent by the current address,
+    /// reimbursing its coins and the remaining gas budget of recurring messages to it.
+    ///
+    /// NOTE: not part of the `Interface` trait of the current massa-sc-runtime,
+    /// smart contracts can call it once the runtime exposes it
+    ///
+    /// # Arguments
+    /// * `fee`: raw fee of the message (`max_gas * gas_price`)
+    /// * `emission_slot`: tuple containing the period and thread of the emission slot of the message
+    /// * `emission_index`: index of the message among the ones emitted at its emission slot
+    #[allow(dead_code)]
+    pub fn cancel_message(
+        &self,
+        fee: u64,
+        emission_slot: (u64, u8),
+        emission_index: u64,
+    ) -> Result<()> {
+        if emission_slot.1 >= self.config.thread_count {
+            bail!("emission slot thread exceeds the configuration thread count")
+        }
+        let message_id = (
+            std::cmp::Reverse(Amount::from_raw(fee)),
+            Slot::new(emission_slot.0, emission_slot.1),
+            emission_index,
+        );
+        let mut context = context_guard!(self);
+        let address = context.get_current_address()?;
+        let result = context.cancel_message(&message_id);
+        context.trace_abi_call(
+            "cancel_message",
+            || {
+                (
+                    format!("{:?}", message_id),
+                    vec![write_access(address, "balance")],
+                )
+            },
+            &result,
+        );
+        Ok(result?)
+    }
+}
+
+/// helper for describing a read of a ledger entry field in execution traces
+fn read_access(address: Address, field: impl Into<String>) -> LedgerAccess {
+    LedgerAccess::Read {
+        address,
+        field: field.into(),
+    }
+}
+
+/// helper for describing a write of a ledger entry field in execution traces
+fn write_access(address: Address, field: impl Into<String>) -> LedgerAccess {
+    LedgerAccess::Write {
+        address,
+        field: field.into(),
+    }
+}
+
+/// name of a datastore entry field in execution traces
+fn datastore(key: &str) -> String {
+    format!("datastore[{}]", key)
 }
 
 impl InterfaceClone for InterfaceImpl {
@@ -81,31 +263,42 @@ impl Interface for InterfaceImpl {
         // write-lock context
         let mut context = context_guard!(self);
 
-        // get target bytecode
-        let bytecode = match context.get_bytecode(&to_address) {
-            Some(bytecode) => bytecode,
-            None => bail!("bytecode not found for address {}", to_address),
-        };
-
         // get caller address
-        let from_address = match context.stack.last() {
-            Some(addr) => addr.address,
-            _ => bail!("failed to read call stack current address"),
-        };
+        let from_address = context.stack.last().map(|elem| elem.address);
 
-        // transfer coins from caller to target address
+        // get target bytecode and transfer coins from caller to target address
         let coins = massa_models::Amount::from_raw(raw_coins);
-        if let Err(err) =
-            context.transfer_parallel_coins(Some(from_address), Some(to_address), coins)
-        {
-            bail!(
-                "error transferring {} parallel coins from {} to {}: {}",
-                coins,
-                from_address,
-                to_address,
-                err
-            );
-        }
+        let result = match (context.get_bytecode(&to_address), from_address) {
+            (None, _) => Err(anyhow!("bytecode not found for address {}", to_address)),
+            (_, None) => Err(anyhow!("failed to read call stack current address")),
+            (Some(bytecode), Some(from_address)) => context
+                .transfer_parallel_coins(Some(from_address), Some(to_address), coins)
+                .map(|_| bytecode)
+                .map_err(|err| {
+                    anyhow!(
+                        "error transferring {} parallel coins from {} to {}: {}",
+                        coins,
+                        from_address,
+                        to_address,
+                        err
+                    )
+                }),
+        };
+
+        // the call is traced with the stack of the caller
+        context.trace_abi_call(
+            "init_call",
+            || {
+                let mut accesses = vec![read_access(to_address, "bytecode")];
+                if let Some(from_address) = from_address {
+                    accesses.push(write_access(from_address, "balance"));
+                    accesses.push(write_access(to_address, "balance"));
+                }
+                (format!("{}, {}", to_address, coins), accesses)
+            },
+            &result,
+        );
+        let bytecode = result?;
 
         // push a new call stack element on top of the current call stack
         context.stack.push(ExecutionStackElement {
@@ -123,10 +316,16 @@ impl Interface for InterfaceImpl {
     fn finish_call(&self) -> Result<()> {
         let mut context = context_guard!(self);
 
-        if context.stack.pop().is_none() {
-            bail!("call stack out of bounds")
-        }
+        // the call is traced with the stack of the callee
+        let result = if context.stack.is_empty() {
+            Err(anyhow!("call stack out of bounds"))
+        } else {
+            Ok(())
+        };
+        context.trace_abi_call("finish_call", || (String::new(), Vec::new()), &result);
+        result?;
 
+        context.stack.pop();
         Ok(())
     }
 
@@ -136,12 +335,18 @@ impl Interface for InterfaceImpl {
     /// The raw representation (no decimal factor) of the parallel balance of the address,
     /// or zero if the address is not found in the ledger.
     fn get_balance(&self) -> Result<u64> {
-        let context = context_guard!(self);
+        let mut context = context_guard!(self);
         let address = context.get_current_address()?;
-        Ok(context
+        let result: Result<u64> = Ok(context
             .get_parallel_balance(&address)
             .unwrap_or_default()
-            .to_raw())
+            .to_raw());
+        context.trace_abi_call(
+            "get_balance",
+            || (String::new(), vec![read_access(address, "balance")]),
+            &result,
+        );
+        result
     }
 
     /// Gets the parallel balance of arbitrary address passed as argument.
@@ -154,10 +359,17 @@ impl Interface for InterfaceImpl {
     /// or zero if the address is not found in the ledger.
     fn get_balance_for(&self, address: &str) -> Result<u64> {
         let address = massa_models::Address::from_str(address)?;
-        Ok(context_guard!(self)
+        let mut context = context_guard!(self);
+        let result: Result<u64> = Ok(context
             .get_parallel_balance(&address)
             .unwrap_or_default()
-            .to_raw())
+            .to_raw());
+        context.trace_abi_call(
+            "get_balance_for",
+            || (address.to_string(), vec![read_access(address, "balance")]),
+            &result,
+        );
+        result
     }
 
     /// Creates a new ledger entry with the initial bytecode given as argument.
@@ -169,7 +381,20 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The string representation of the newly created address
     fn create_module(&self, bytecode: &[u8]) -> Result<String> {
-        match context_guard!(self).create_new_sc_address(bytecode.to_vec()) {
+        let mut context = context_guard!(self);
+        let result = context.create_new_sc_address(bytecode.to_vec());
+        context.trace_abi_call(
+            "create_module",
+            || {
+                let accesses = match &result {
+                    Ok(addr) => vec![write_access(*addr, "bytecode")],
+                    Err(_) => Vec::new(),
+                };
+                (format!("{} bytes", bytecode.len()), accesses)
+            },
+            &result,
+        );
+        match result {
             Ok(addr) => Ok(addr.to_string()),
             Err(err) => bail!("couldn't create new SC address: {}", err),
         }
@@ -184,13 +409,23 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data_for(&self, address: &str, key: &str) -> Result<Vec<u8>> {
-        let addr = &massa_models::Address::from_str(address)?;
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        let context = context_guard!(self);
-        match context.get_data_entry(addr, &key) {
-            Some(value) => Ok(value),
-            _ => bail!("data entry not found"),
-        }
+        let addr = massa_models::Address::from_str(address)?;
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
+        let result = context
+            .get_data_entry(&addr, &hashed_key)
+            .ok_or_else(|| anyhow!("data entry not found"));
+        context.trace_abi_call(
+            "raw_get_data_for",
+            || {
+                (
+                    format!("{}, {}", addr, key),
+                    vec![read_access(addr, datastore(key))],
+                )
+            },
+            &result,
+        );
+        result
     }
 
     /// Sets a datastore entry for a given address.
@@ -203,9 +438,20 @@ impl Interface for InterfaceImpl {
     /// * value: new value to set
     fn raw_set_data_for(&self, address: &str, key: &str, value: &[u8]) -> Result<()> {
         let addr = massa_models::Address::from_str(address)?;
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
         let mut context = context_guard!(self);
-        context.set_data_entry(&addr, key, value.to_vec())?;
+        let result = context.set_data_entry(&addr, hashed_key, value.to_vec());
+        context.trace_abi_call(
+            "raw_set_data_for",
+            || {
+                (
+                    format!("{}, {}, {} bytes", addr, key, value.len()),
+                    vec![write_access(addr, datastore(key))],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -218,8 +464,23 @@ impl Interface for InterfaceImpl {
     /// * value: value to append
     fn raw_append_data_for(&self, address: &str, key: &str, value: &[u8]) -> Result<()> {
         let addr = massa_models::Address::from_str(address)?;
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        context_guard!(self).append_data_entry(&addr, key, value.to_vec())?;
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
+        let result = context.append_data_entry(&addr, hashed_key, value.to_vec());
+        context.trace_abi_call(
+            "raw_append_data_for",
+            || {
+                (
+                    format!("{}, {}, {} bytes", addr, key, value.len()),
+                    vec![
+                        read_access(addr, datastore(key)),
+                        write_access(addr, datastore(key)),
+                    ],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -230,9 +491,21 @@ impl Interface for InterfaceImpl {
     /// * address: string representation of the address
     /// * key: string key of the datastore entry to delete
     fn raw_delete_data_for(&self, address: &str, key: &str) -> Result<()> {
-        let addr = &massa_models::Address::from_str(address)?;
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        context_guard!(self).delete_data_entry(addr, &key)?;
+        let addr = massa_models::Address::from_str(address)?;
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
+        let result = context.delete_data_entry(&addr, &hashed_key);
+        context.trace_abi_call(
+            "raw_delete_data_for",
+            || {
+                (
+                    format!("{}, {}", addr, key),
+                    vec![write_access(addr, datastore(key))],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -246,9 +519,20 @@ impl Interface for InterfaceImpl {
     /// true if the address exists and has the entry matching the provided key in its datastore, otherwise false
     fn has_data_for(&self, address: &str, key: &str) -> Result<bool> {
         let addr = massa_models::Address::from_str(address)?;
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        let context = context_guard!(self);
-        Ok(context.has_data_entry(&addr, &key))
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
+        let result: Result<bool> = Ok(context.has_data_entry(&addr, &hashed_key));
+        context.trace_abi_call(
+            "has_data_for",
+            || {
+                (
+                    format!("{}, {}", addr, key),
+                    vec![read_access(addr, datastore(key))],
+                )
+            },
+            &result,
+        );
+        result
     }
 
     /// Gets a datastore value by key for the current address (top of the call stack).
@@ -259,13 +543,18 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data(&self, key: &str) -> Result<Vec<u8>> {
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        let context = context_guard!(self);
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        match context.get_data_entry(&addr, &key) {
-            Some(data) => Ok(data),
-            _ => bail!("data entry not found"),
-        }
+        let result = context
+            .get_data_entry(&addr, &hashed_key)
+            .ok_or_else(|| anyhow!("data entry not found"));
+        context.trace_abi_call(
+            "raw_get_data",
+            || (key.to_string(), vec![read_access(addr, datastore(key))]),
+            &result,
+        );
+        result
     }
 
     /// Sets a datastore entry for the current address (top of the call stack).
@@ -277,10 +566,21 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry to set
     /// * value: new value to set
     fn raw_set_data(&self, key: &str, value: &[u8]) -> Result<()> {
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        context.set_data_entry(&addr, key, value.to_vec())?;
+        let result = context.set_data_entry(&addr, hashed_key, value.to_vec());
+        context.trace_abi_call(
+            "raw_set_data",
+            || {
+                (
+                    format!("{}, {} bytes", key, value.len()),
+                    vec![write_access(addr, datastore(key))],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -292,10 +592,24 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry
     /// * value: value to append
     fn raw_append_data(&self, key: &str, value: &[u8]) -> Result<()> {
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        context.append_data_entry(&addr, key, value.to_vec())?;
+        let result = context.append_data_entry(&addr, hashed_key, value.to_vec());
+        context.trace_abi_call(
+            "raw_append_data",
+            || {
+                (
+                    format!("{}, {} bytes", key, value.len()),
+                    vec![
+                        read_access(addr, datastore(key)),
+                        write_access(addr, datastore(key)),
+                    ],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -305,10 +619,16 @@ impl Interface for InterfaceImpl {
     /// # Arguments
     /// * key: string key of the datastore entry to delete
     fn raw_delete_data(&self, key: &str) -> Result<()> {
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        context.delete_data_entry(&addr, &key)?;
+        let result = context.delete_data_entry(&addr, &hashed_key);
+        context.trace_abi_call(
+            "raw_delete_data",
+            || (key.to_string(), vec![write_access(addr, datastore(key))]),
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -320,10 +640,16 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// true if the address exists and has the entry matching the provided key in its datastore, otherwise false
     fn has_data(&self, key: &str) -> Result<bool> {
-        let key = massa_hash::Hash::compute_from(key.as_bytes());
-        let context = context_guard!(self);
+        let hashed_key = massa_hash::Hash::compute_from(key.as_bytes());
+        let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        Ok(context.has_data_entry(&addr, &key))
+        let result: Result<bool> = Ok(context.has_data_entry(&addr, &hashed_key));
+        context.trace_abi_call(
+            "has_data",
+            || (key.to_string(), vec![read_access(addr, datastore(key))]),
+            &result,
+        );
+        result
     }
 
     /// Hashes arbitrary data
@@ -382,7 +708,21 @@ impl Interface for InterfaceImpl {
         let amount = massa_models::Amount::from_raw(raw_amount);
         let mut context = context_guard!(self);
         let from_address = context.get_current_address()?;
-        context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
+        let result = context.transfer_parallel_coins(Some(from_address), Some(to_address), amount);
+        context.trace_abi_call(
+            "transfer_coins",
+            || {
+                (
+                    format!("{}, {}", to_address, amount),
+                    vec![
+                        write_access(from_address, "balance"),
+                        write_access(to_address, "balance"),
+                    ],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -402,7 +742,21 @@ impl Interface for InterfaceImpl {
         let to_address = massa_models::Address::from_str(to_address)?;
         let amount = massa_models::Amount::from_raw(raw_amount);
         let mut context = context_guard!(self);
-        context.transfer_parallel_coins(Some(from_address), Some(to_address), amount)?;
+        let result = context.transfer_parallel_coins(Some(from_address), Some(to_address), amount);
+        context.trace_abi_call(
+            "transfer_coins_for",
+            || {
+                (
+                    format!("{}, {}, {}", from_address, to_address, amount),
+                    vec![
+                        write_access(from_address, "balance"),
+                        write_access(to_address, "balance"),
+                    ],
+                )
+            },
+            &result,
+        );
+        result?;
         Ok(())
     }
 
@@ -448,7 +802,13 @@ impl Interface for InterfaceImpl {
     /// # Arguments:
     /// data: the string data that is the payload of the event
     fn generate_event(&self, data: String) -> Result<()> {
-        context_guard!(self).generate_event(data)?;
+        let mut context = context_guard!(self);
+        context.trace_abi_call(
+            "generate_event",
+            || (data.clone(), Vec::new()),
+            &Ok::<(), String>(()),
+        );
+        context.generate_event(data)?;
         Ok(())
     }
 
@@ -503,11 +863,17 @@ impl Interface for InterfaceImpl {
         if validity_end.1 >= self.config.thread_count {
             bail!("validity end thread exceeds the configuration thread count")
         }
+        if target_handler.len() > u8::MAX as usize {
+            bail!(
+                "handler name length exceeds the maximum of {} bytes",
+                u8::MAX
+            )
+        }
         let mut execution_context = context_guard!(self);
         let emission_slot = execution_context.slot;
         let emission_index = execution_context.created_message_index;
         let sender = execution_context.get_current_address()?;
-        execution_context.push_new_message(AsyncMessage {
+        let message = AsyncMessage {
             emission_slot,
             emission_index,
             sender,
@@ -519,7 +885,29 @@ impl Interface for InterfaceImpl {
             gas_price: Amount::from_raw(gas_price),
             coins: Amount::from_raw(raw_coins),
             data: data.to_vec(),
-        });
+            recurrence: None,
+        };
+        execution_context.trace_abi_call(
+            "send_message",
+            || {
+                (
+                    format!(
+                        "{}, {}, {} to {}, {}, {}, {}, {} bytes",
+                        message.destination,
+                        message.handler,
+                        message.validity_start,
+                        message.validity_end,
+                        message.max_gas,
+                        message.gas_price,
+                        message.coins,
+                        message.data.len()
+                    ),
+                    Vec::new(),
+                )
+            },
+            &Ok::<(), String>(()),
+        );
+        execution_context.push_new_message(message)?;
         execution_context.created_message_index += 1;
         Ok(())
     }
@@ -540,7 +928,18 @@ impl Interface for InterfaceImpl {
     fn raw_set_bytecode(&self, bytecode: &[u8]) -> Result<()> {
         let mut execution_context = context_guard!(self);
         let address = execution_context.get_current_address()?;
-        match execution_context.set_bytecode(&address, bytecode.to_vec()) {
+        let result = execution_context.set_bytecode(&address, bytecode.to_vec());
+        execution_context.trace_abi_call(
+            "raw_set_bytecode",
+            || {
+                (
+                    format!("{} bytes", bytecode.len()),
+                    vec![write_access(address, "bytecode")],
+                )
+            },
+            &result,
+        );
+        match result {
             Ok(()) => Ok(()),
             Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
         }
@@ -551,7 +950,18 @@ impl Interface for InterfaceImpl {
     fn raw_set_bytecode_for(&self, address: &str, bytecode: &[u8]) -> Result<()> {
         let address = massa_models::Address::from_str(address)?;
         let mut execution_context = context_guard!(self);
-        match execution_context.set_bytecode(&address, bytecode.to_vec()) {
+        let result = execution_context.set_bytecode(&address, bytecode.to_vec());
+        execution_context.trace_abi_call(
+            "raw_set_bytecode_for",
+            || {
+                (
+                    format!("{}, {} bytes", address, bytecode.len()),
+                    vec![write_access(address, "bytecode")],
+                )
+            },
+            &result,
+        );
+        match result {
             Ok(()) => Ok(()),
             Err(err) => bail!("couldn't set address {} bytecode: {}", address, err),
         }
@@ -40,7 +40,7 @@
 //!
 //! ## worker.rs
 //! This module runs the main loop of the worker thread.
-//! It contains the logic to process incoming blockclique change notifications and read-only execution requests.
+//! It contains the logic to process incoming blockclique change notifications.
 //! It sequences the blocks according to their slot number into queues,
 //! and requests the execution of active and final slots to execution.rs.
 //!
@@ -61,6 +61,21 @@
 //! A speculative (non-final) ledger that supports canceling already-executed operations
 //! in the case of some blockclique changes.
 //!
+//! ## `event_db.rs`
+//! An optional RocksDB-backed store of final smart contract events,
+//! indexed by emitter address, original caller address, origin operation ID and slot,
+//! and pruned by slot count or disk size.
+//!
+//! ## `readonly_executor.rs`
+//! A bounded pool of threads executing read-only requests in parallel,
+//! each against its own snapshot of the active execution state,
+//! so that read-only executions do not block the execution of slots.
+//! Requests time out after a configurable wall-clock delay.
+//!
+//! ## `stats.rs`
+//! Performance metrics of slot executions (wall time, gas, ledger reads),
+//! aggregated over a bounded history of recent slots and exposed in the node status.
+//!
 //! ## `request_queue.rs`
 //! This module contains the implementation of a generic finite-size execution request queue.
 //! It handles requests that come with an MPSC to send back the result of their execution once it's done.
@@ -72,11 +87,14 @@
 
 mod context;
 mod controller;
+mod event_db;
 mod execution;
 mod interface_impl;
+mod readonly_executor;
 mod request_queue;
 mod speculative_async_pool;
 mod speculative_ledger;
+mod stats;
 mod worker;
 
 pub use worker::start_execution_worker;
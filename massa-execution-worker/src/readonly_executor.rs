@@ -0,0 +1,314 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module implements a bounded pool of read-only executor threads.
+//! Each read-only request is executed against a snapshot of the active execution state,
+//! taken under a short-lived read lock, so that read-only executions
+//! never block the execution of final or candidate slots by the worker thread.
+//! Requests that are not picked up before their wall-clock deadline are cancelled.
+//! A running request fails at its first interface call after its deadline,
+//! so that slow requests do not hold an executor until their gas is exhausted.
+
+use crate::context::ExecutionContext;
+use crate::execution::ExecutionState;
+use crate::interface_impl::InterfaceImpl;
+use crate::request_queue::{RequestQueue, RequestWithResponseSender};
+use massa_execution_exports::{
+    ExecutionConfig, ExecutionError, ExecutionOutput, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
+};
+use massa_final_state::FinalState;
+use massa_sc_runtime::Interface;
+use parking_lot::{Condvar, Mutex, RwLock};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+/// Read-only execution request along with the instant after which its output is not awaited anymore
+pub(crate) type TimedReadOnlyRequest = (Instant, ReadOnlyExecutionRequest);
+
+/// Max number of times a read-only request is executed again because the final state changed during its execution
+const MAX_FINAL_STATE_CHANGE_RETRIES: usize = 3;
+
+/// Structure used to communicate with the read-only executors
+pub(crate) struct ReadOnlyInputData {
+    /// set stop to true to stop the read-only executors
+    pub stop: bool,
+    /// queue for read-only execution requests and response MPSCs to send back their outputs
+    pub requests: RequestQueue<TimedReadOnlyRequest, ExecutionOutput>,
+}
+
+/// Structure gathering all elements needed by a read-only executor thread
+pub(crate) struct ReadOnlyExecutor {
+    /// shared access to the incoming read-only requests
+    input_data: Arc<(Condvar, Mutex<ReadOnlyInputData>)>,
+    /// execution state from which read-only contexts are snapshotted
+    execution_state: Arc<RwLock<ExecutionState>>,
+    /// final state on top of which the snapshots are built, used to check that it did not change during an execution
+    final_state: Arc<RwLock<FinalState>>,
+    /// context of the ongoing read-only execution, shared with the interface
+    execution_context: Arc<Mutex<ExecutionContext>>,
+    /// interface providing ABI access to the VM for this executor
+    execution_interface: Box<dyn Interface>,
+}
+
+impl ReadOnlyExecutor {
+    /// Creates a read-only executor with its own context and interface, so that executors can run in parallel
+    ///
+    /// # Arguments
+    /// * `config`: execution configuration
+    /// * `input_data`: shared access to the incoming read-only requests
+    /// * `execution_state`: execution state from which read-only contexts are snapshotted
+    /// * `final_state`: thread-safe shared access to the final state
+    pub fn new(
+        config: &ExecutionConfig,
+        input_data: Arc<(Condvar, Mutex<ReadOnlyInputData>)>,
+        execution_state: Arc<RwLock<ExecutionState>>,
+        final_state: Arc<RwLock<FinalState>>,
+    ) -> Self {
+        let execution_context = Arc::new(Mutex::new(ExecutionContext::new(
+            final_state.clone(),
+            Default::default(),
+            config.storage_byte_cost,
+        )));
+        ReadOnlyExecutor {
+            input_data,
+            execution_state,
+            final_state,
+            execution_interface: Box::new(InterfaceImpl::new(
+                config.clone(),
+                execution_context.clone(),
+            )),
+            execution_context,
+        }
+    }
+
+    /// Main loop of a read-only executor thread:
+    /// pops requests one at a time and executes them until stop is requested.
+    fn main_loop(&self) {
+        while let Some(req) = self.wait_request() {
+            let ((deadline, req), resp_tx) = req.into_request_sender_pair();
+
+            // do not bother executing requests whose output is not awaited anymore
+            if Instant::now() >= deadline {
+                // ignore errors: they mean that the requester has already given up
+                let _ = resp_tx.send(Err(ExecutionError::ReadOnlyTimeout(
+                    "not started before the deadline".into(),
+                )));
+                continue;
+            }
+
+            // execute the request and send back the outcome,
+            // ignoring errors because they just mean that the requester has given up
+            let outcome = self.execute(deadline, req);
+            let _ = resp_tx.send(outcome);
+        }
+    }
+
+    /// Waits for the next read-only request to execute.
+    ///
+    /// # Returns
+    /// The next request, or None if the executor needs to stop
+    fn wait_request(
+        &self,
+    ) -> Option<RequestWithResponseSender<TimedReadOnlyRequest, ExecutionOutput>> {
+        let mut input_data = self.input_data.1.lock();
+        loop {
+            if input_data.stop {
+                return None;
+            }
+            if let Some(req) = input_data.requests.pop() {
+                return Some(req);
+            }
+            self.input_data.0.wait(&mut input_data);
+        }
+    }
+
+    /// Runs a read-only execution request.
+    /// The executed bytecode appears to be able to read and write the consensus state,
+    /// but all accumulated changes are simply returned as an `ExecutionOutput` object,
+    /// and not actually applied to the consensus state.
+    ///
+    /// The execution fails at its first interface call after `deadline`.
+    /// If the final state moved forward during the execution, the state it read is not consistent anymore:
+    /// the request is then executed again on a new snapshot, at most `MAX_FINAL_STATE_CHANGE_RETRIES` times.
+    ///
+    /// # Arguments
+    /// * `deadline`: instant after which the output of the request is not awaited anymore
+    /// * `req`: a read-only execution request
+    ///
+    /// # Returns
+    ///  `ExecutionOutput` describing the output of the execution, or an error
+    pub fn execute(
+        &self,
+        deadline: Instant,
+        req: ReadOnlyExecutionRequest,
+    ) -> Result<ExecutionOutput, ExecutionError> {
+        for _ in 0..=MAX_FINAL_STATE_CHANGE_RETRIES {
+            // snapshot the execution state along with the final slot it is built upon, releasing the locks right away.
+            // Note that the final state is only written while the execution state is write-locked.
+            let (mut execution_context, final_slot) = {
+                let execution_state = self.execution_state.read();
+                (
+                    execution_state.get_readonly_context(&req)?,
+                    self.final_state.read().slot,
+                )
+            };
+            execution_context.deadline = Some(deadline);
+
+            let outcome = self.run(execution_context, &req);
+
+            if Instant::now() >= deadline {
+                return match outcome {
+                    Err(_) => Err(ExecutionError::ReadOnlyTimeout(
+                        "interrupted at the deadline".into(),
+                    )),
+                    // the output is not awaited anymore anyway
+                    Ok(_) => Err(ExecutionError::ReadOnlyTimeout(
+                        "finished after the deadline".into(),
+                    )),
+                };
+            }
+            // the final state did not change during the execution: the output is consistent
+            if self.final_state.read().slot == final_slot {
+                return outcome;
+            }
+        }
+        Err(ExecutionError::FinalStateError(format!(
+            "the final state changed during each of the {} read-only execution attempts",
+            MAX_FINAL_STATE_CHANGE_RETRIES + 1
+        )))
+    }
+
+    /// Runs a read-only execution request in a given execution context
+    fn run(
+        &self,
+        execution_context: ExecutionContext,
+        req: &ReadOnlyExecutionRequest,
+    ) -> Result<ExecutionOutput, ExecutionError> {
+        // run the intepreter according to the target type
+        let run_result = match &req.target {
+            ReadOnlyExecutionTarget::BytecodeExecution(bytecode) => {
+                // set the execution context for execution
+                *self.execution_context.lock() = execution_context;
+
+                // run the bytecode's main function
+                massa_sc_runtime::run_main(bytecode, req.max_gas, &*self.execution_interface)
+            }
+            ReadOnlyExecutionTarget::FunctionCall {
+                target_addr,
+                target_func,
+                parameter,
+                ..
+            } => {
+                // get the bytecode, default to an empty vector
+                let bytecode = execution_context
+                    .get_bytecode(target_addr)
+                    .unwrap_or_default();
+
+                // set the execution context for execution
+                *self.execution_context.lock() = execution_context;
+
+                // run the target function in the bytecode
+                massa_sc_runtime::run_function(
+                    &bytecode,
+                    req.max_gas,
+                    target_func,
+                    parameter,
+                    &*self.execution_interface,
+                )
+            }
+        };
+
+        // return the execution output, or the error along with the trace if there is one
+        let mut context = self.execution_context.lock();
+        match run_result {
+            Ok(remaining_gas) => {
+                context.finish_trace(Some(req.max_gas.saturating_sub(remaining_gas)), None);
+                Ok(context.settle_slot())
+            }
+            Err(err) => {
+                context.finish_trace(None, Some(err.to_string()));
+                match context.traces.pop() {
+                    Some(trace) => Err(ExecutionError::TracedRuntimeError(
+                        err.to_string(),
+                        Box::new(trace),
+                    )),
+                    None => Err(ExecutionError::RuntimeError(err.to_string())),
+                }
+            }
+        }
+    }
+}
+
+/// Bounded pool of read-only executor threads sharing a queue of requests
+pub(crate) struct ReadOnlyExecutorPool {
+    /// shared access to the incoming read-only requests
+    input_data: Arc<(Condvar, Mutex<ReadOnlyInputData>)>,
+    /// handles used to join the executor threads
+    thread_handles: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl ReadOnlyExecutorPool {
+    /// Launches `config.readonly_executor_count` read-only executor threads
+    ///
+    /// # Arguments
+    /// * `config`: execution configuration
+    /// * `execution_state`: execution state from which read-only contexts are snapshotted
+    /// * `final_state`: thread-safe shared access to the final state
+    pub fn new(
+        config: ExecutionConfig,
+        execution_state: Arc<RwLock<ExecutionState>>,
+        final_state: Arc<RwLock<FinalState>>,
+    ) -> Self {
+        let input_data = Arc::new((
+            Condvar::new(),
+            Mutex::new(ReadOnlyInputData {
+                stop: false,
+                requests: RequestQueue::new(config.readonly_queue_length),
+            }),
+        ));
+
+        let thread_handles = (0..config.readonly_executor_count)
+            .map(|_| {
+                let executor = ReadOnlyExecutor::new(
+                    &config,
+                    input_data.clone(),
+                    execution_state.clone(),
+                    final_state.clone(),
+                );
+                std::thread::spawn(move || executor.main_loop())
+            })
+            .collect();
+
+        ReadOnlyExecutorPool {
+            input_data,
+            thread_handles,
+        }
+    }
+
+    /// Gets a shared access to the input of the read-only executors
+    pub fn get_input_data(&self) -> Arc<(Condvar, Mutex<ReadOnlyInputData>)> {
+        self.input_data.clone()
+    }
+
+    /// Stops the read-only executors and cancels the pending requests
+    pub fn stop(&mut self) {
+        {
+            let mut input_data = self.input_data.1.lock();
+            input_data.stop = true;
+            self.input_data.0.notify_all();
+        }
+        for handle in self.thread_handles.drain(..) {
+            if handle.join().is_err() {
+                info!("a read-only executor thread panicked");
+            }
+        }
+        self.input_data
+            .1
+            .lock()
+            .requests
+            .cancel(ExecutionError::ChannelError(
+                "readonly execution cancelled because the execution worker is closing".into(),
+            ));
+    }
+}
@@ -68,32 +68,6 @@ impl<T, R> RequestQueue<T, R> {
         }
     }
 
-    /// Extends Self with the contents of another `RequestQueue`.
-    /// The contents of the incoming queue are appended last.
-    /// Excess items with respect to `self.max_items` are canceled and dropped.
-    pub fn extend(&mut self, mut other: RequestQueue<T, R>) {
-        // compute the number of available item slots
-        let free_slots = self.max_items.saturating_sub(self.queue.len());
-
-        // if there are no available slots remaining, do nothing
-        if free_slots == 0 {
-            return;
-        }
-
-        // if there are not enough available slots to fit the entire incoming queue
-        if free_slots < other.queue.len() {
-            // truncate the incoming queue to the size that fits, cancelling excess items
-            other.queue.drain(free_slots..).for_each(|req| {
-                req.cancel(ExecutionError::ChannelError(
-                    "maximal request queue capacity reached".into(),
-                ))
-            });
-        }
-
-        // append the kept part of the incoming queue
-        self.queue.extend(other.queue);
-    }
-
     /// Cancel all queued items.
     ///
     /// # Arguments
@@ -131,14 +105,6 @@ impl<T, R> RequestQueue<T, R> {
         self.queue.push_back(req);
     }
 
-    /// Take all the elements into a new queue and reset the current queue
-    pub fn take(&mut self) -> Self {
-        RequestQueue {
-            max_items: self.max_items,
-            queue: std::mem::take(&mut self.queue),
-        }
-    }
-
     /// Checks whether the queue is full
     ///
     /// # Returns
@@ -146,12 +112,4 @@ impl<T, R> RequestQueue<T, R> {
     pub fn is_full(&self) -> bool {
         self.queue.len() >= self.max_items
     }
-
-    /// Checks whether the queue is empty
-    ///
-    /// # Returns
-    /// true if the queue is empty, false otherwise
-    pub fn is_empty(&self) -> bool {
-        self.queue.is_empty()
-    }
 }
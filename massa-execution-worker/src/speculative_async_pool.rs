@@ -3,16 +3,27 @@
 //! The speculative asynchronous pool represents the state of
 //! the pool at an arbitrary execution slot.
 
-use massa_async_pool::{AsyncMessage, AsyncMessageId, AsyncPool, AsyncPoolChanges};
+use massa_async_pool::{AsyncMessage, AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
+use massa_final_state::FinalState;
 use massa_models::Slot;
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::sync::Arc;
 
-/// The `SpeculativeAsyncPool` holds a copy of the final state asynchronous pool
-/// to which it applies the previous changes.
-/// The `SpeculativeAsyncPool` manipulates this copy to compute the full pool
+/// The `SpeculativeAsyncPool` reads the final state asynchronous pool through
+/// an overlay of the messages added or removed by the previous changes.
+/// The `SpeculativeAsyncPool` manipulates this overlay to compute the full pool
 /// while keeping track of all the newly added changes.
 pub struct SpeculativeAsyncPool {
-    /// Copy of the final asynchronous pool with the previous changes applied
-    async_pool: AsyncPool,
+    /// Thread-safe shared access to the final state. For reading only.
+    final_state: Arc<RwLock<FinalState>>,
+
+    /// Messages added (`Some`) to or removed (`None`) from the final pool since finality
+    overlay: BTreeMap<AsyncMessageId, Option<AsyncMessage>>,
+
+    /// Number of messages in the speculative pool
+    length: u64,
 
     /// List of newly emitted asynchronous messages
     emitted: Vec<(AsyncMessageId, AsyncMessage)>,
@@ -25,15 +36,24 @@ impl SpeculativeAsyncPool {
     /// Creates a new `SpeculativeAsyncPool`
     ///
     /// # Arguments
-    /// * `async_pool`: a copy of the final state `AsyncPool`
+    /// * `final_state`: thread-safe shared access to the final state
     /// * `previous_changes`: accumulation of changes that previously happened to the asynchronous pool since finality
-    pub fn new(mut async_pool: AsyncPool, previous_changes: AsyncPoolChanges) -> Self {
-        async_pool.apply_changes_unchecked(previous_changes);
-        SpeculativeAsyncPool {
-            async_pool,
+    pub fn new(final_state: Arc<RwLock<FinalState>>, previous_changes: AsyncPoolChanges) -> Self {
+        let length = final_state.read().async_pool.len();
+        let mut pool = SpeculativeAsyncPool {
+            final_state,
+            overlay: Default::default(),
+            length,
             emitted: Default::default(),
             settled_changes: Default::default(),
+        };
+        for change in previous_changes.0 {
+            match change {
+                Change::Add(msg_id, msg) => pool.set_message(msg_id, Some(msg)),
+                Change::Delete(msg_id) => pool.set_message(msg_id, None),
+            }
         }
+        pool
     }
 
     /// Returns the changes caused to the `SpeculativeAsyncPool` since its creation,
@@ -58,47 +78,231 @@ impl SpeculativeAsyncPool {
         self.emitted.push((msg.compute_id(), msg));
     }
 
+    /// Gets a copy of a message of the speculative pool, including the ones emitted during the current slot
+    pub fn get_message(&self, msg_id: &AsyncMessageId) -> Option<AsyncMessage> {
+        if let Some((_, msg)) = self.emitted.iter().find(|(id, _)| id == msg_id) {
+            return Some(msg.clone());
+        }
+        match self.overlay.get(msg_id) {
+            Some(overlay_msg) => overlay_msg.clone(),
+            None => self.final_state.read().async_pool.get_message(msg_id),
+        }
+    }
+
+    /// Removes a message from the speculative pool, settling its deletion in the changes accumulator
+    /// unless it was emitted during the current slot
+    ///
+    /// # Returns
+    /// The removed message, or `None` if it was not in the pool
+    pub fn remove_message(&mut self, msg_id: &AsyncMessageId) -> Option<AsyncMessage> {
+        if let Some(index) = self.emitted.iter().position(|(id, _)| id == msg_id) {
+            return Some(self.emitted.remove(index).1);
+        }
+        let msg = self.get_message(msg_id)?;
+        self.set_message(*msg_id, None);
+        self.settled_changes.push_delete(*msg_id);
+        Some(msg)
+    }
+
+    /// Adds (`Some`) or removes (`None`) a message in the overlay, keeping track of the pool length
+    fn set_message(&mut self, msg_id: AsyncMessageId, msg: Option<AsyncMessage>) {
+        let was_present = match self.overlay.get(&msg_id) {
+            Some(overlay_msg) => overlay_msg.is_some(),
+            None => self
+                .final_state
+                .read()
+                .async_pool
+                .get_message(&msg_id)
+                .is_some(),
+        };
+        match (was_present, msg.is_some()) {
+            (false, true) => self.length += 1,
+            (true, false) => self.length -= 1,
+            _ => {}
+        }
+        self.overlay.insert(msg_id, msg);
+    }
+
+    /// Collects the messages of the speculative pool matching a predicate, by decreasing priority.
+    /// Only the candidates listed by `get_final_ids` are read from the final pool,
+    /// while all the messages added by the overlay are checked.
+    fn collect_candidates<G, F>(
+        &self,
+        get_final_ids: G,
+        predicate: F,
+    ) -> Vec<(AsyncMessageId, AsyncMessage)>
+    where
+        G: FnOnce(&AsyncPool) -> Vec<AsyncMessageId>,
+        F: Fn(&AsyncMessage) -> bool,
+    {
+        let final_state = self.final_state.read();
+        let mut result = BTreeMap::new();
+        for msg_id in get_final_ids(&final_state.async_pool) {
+            // the overlay shadows the final messages
+            if self.overlay.contains_key(&msg_id) {
+                continue;
+            }
+            if let Some(msg) = final_state.async_pool.get_message(&msg_id) {
+                if predicate(&msg) {
+                    result.insert(msg_id, msg);
+                }
+            }
+        }
+        for (msg_id, msg) in self.overlay.iter() {
+            if let Some(msg) = msg {
+                if predicate(msg) {
+                    result.insert(*msg_id, msg.clone());
+                }
+            }
+        }
+        result.into_iter().collect()
+    }
+
+    /// Collects at most `count` messages of the speculative pool with the lowest priority, by increasing priority
+    fn collect_lowest_priority_messages(&self, count: u64) -> Vec<(AsyncMessageId, AsyncMessage)> {
+        let final_state = self.final_state.read();
+        let mut final_messages = final_state.async_pool.iter_rev().peekable();
+        let mut overlay = self.overlay.iter().rev().peekable();
+        let mut result = Vec::new();
+        while (result.len() as u64) < count {
+            match next_message(&mut final_messages, &mut overlay, |a, b| a > b) {
+                Some(message) => result.push(message),
+                None => break,
+            }
+        }
+        result
+    }
+
     /// Takes a batch of asynchronous messages to execute,
     /// removing them from the speculative asynchronous pool and settling their deletion from it in the changes accumulator.
+    /// Recurring messages are replaced by their next occurrence instead of being removed.
     ///
     /// # Arguments
     /// * `slot`: slot at which the batch is taken (allows filtering by validity interval)
     /// * `max_gas`: maximum amount of gas available
     ///
     /// # Returns
-    /// A vector of `AsyncMessage` to execute
+    /// A vector of `AsyncMessage` to execute, sorted from the most priority to the least priority.
+    /// Messages that were rescheduled have their recurrence moved to their next occurrence.
     pub fn take_batch_to_execute(
         &mut self,
         slot: Slot,
-        max_gas: u64,
+        mut max_gas: u64,
     ) -> Vec<(AsyncMessageId, AsyncMessage)> {
-        // take a batch of messages, removing it from the async pool
-        let msgs = self.async_pool.take_batch_to_execute(slot, max_gas);
+        // select messages within their validity period in decreasing priority order
+        let mut msgs = self.collect_candidates(
+            |pool| pool.get_executable_message_ids(slot),
+            |msg| slot >= msg.validity_start && slot < msg.validity_end,
+        );
 
-        // settle deletions
-        for (msg_id, _msg) in &msgs {
-            self.settled_changes.push_delete(*msg_id);
-        }
+        // check available gas
+        msgs.retain(|(_msg_id, msg)| {
+            if max_gas >= msg.max_gas {
+                max_gas -= msg.max_gas;
+                true
+            } else {
+                false
+            }
+        });
 
-        msgs
+        // remove them from the pool or reschedule them, and settle the changes
+        msgs.into_iter()
+            .map(|(msg_id, mut msg)| {
+                match msg.take_next_occurrence(slot) {
+                    Some(next_msg) => {
+                        self.set_message(msg_id, Some(next_msg.clone()));
+                        self.settled_changes.push_add(msg_id, next_msg);
+                    }
+                    None => {
+                        self.set_message(msg_id, None);
+                        self.settled_changes.push_delete(msg_id);
+                    }
+                }
+                (msg_id, msg)
+            })
+            .collect()
     }
 
     /// Settle a slot.
-    /// Consume newly emitted messages into `self.async_pool`, recording changes into `self.settled_changes`.
+    /// Consume newly emitted messages into the pool, recording changes into `self.settled_changes`.
     ///
     /// # Arguments
     /// * slot: slot that is being settled
     ///
     /// # Returns
-    /// the list of deleted `(message_id, message)`, used for reimbursement
+    /// The list of `(message_id, message)` that were eliminated from the pool after the changes were applied, sorted in the following order:
+    /// * expired messages from the pool, in priority order (from highest to lowest priority)
+    /// * expired messages from the newly emitted ones (in emission order)
+    /// * excess messages after inserting all remaining emitted ones, in reverse priority order (from lowest to highest priority)
     pub fn settle_slot(&mut self, slot: Slot) -> Vec<(AsyncMessageId, AsyncMessage)> {
-        let deleted_messages = self.async_pool.settle_slot(slot, &mut self.emitted);
-        for (msg_id, msg) in std::mem::take(&mut self.emitted) {
+        // Filter out all messages for which the validity end is expired.
+        // Note that the validity_end bound is NOT included in the validity interval of the message.
+        let mut eliminated = self.collect_candidates(
+            |pool| pool.get_expired_message_ids(slot),
+            |msg| slot >= msg.validity_end,
+        );
+        for (msg_id, _msg) in &eliminated {
+            self.set_message(*msg_id, None);
+        }
+        let (expired, emitted): (Vec<_>, Vec<_>) = std::mem::take(&mut self.emitted)
+            .into_iter()
+            .partition(|(_msg_id, msg)| slot >= msg.validity_end);
+        eliminated.extend(expired);
+
+        // Insert new messages into the pool
+        for (msg_id, msg) in emitted {
+            self.set_message(msg_id, Some(msg.clone()));
             self.settled_changes.push_add(msg_id, msg);
         }
-        for (msg_id, _msg) in deleted_messages.iter() {
+
+        // Truncate message pool to its max size, removing non-prioritary items
+        let max_length = self.final_state.read().async_pool.max_length();
+        let excess = self.collect_lowest_priority_messages(self.length.saturating_sub(max_length));
+        for (msg_id, _msg) in &excess {
+            self.set_message(*msg_id, None);
+        }
+        eliminated.extend(excess);
+
+        for (msg_id, _msg) in eliminated.iter() {
             self.settled_changes.push_delete(*msg_id);
         }
-        deleted_messages
+        eliminated
+    }
+}
+
+/// Gets the next message of the merge of the final pool messages with the overlay,
+/// where `precedes` tells whether an ID comes before another one in iteration order.
+/// Overlay entries take precedence over final messages with the same ID,
+/// and removed messages are skipped.
+fn next_message<'a, F, O>(
+    final_messages: &mut Peekable<F>,
+    overlay: &mut Peekable<O>,
+    precedes: impl Fn(&AsyncMessageId, &AsyncMessageId) -> bool,
+) -> Option<(AsyncMessageId, AsyncMessage)>
+where
+    F: Iterator<Item = (AsyncMessageId, AsyncMessage)>,
+    O: Iterator<Item = (&'a AsyncMessageId, &'a Option<AsyncMessage>)>,
+{
+    loop {
+        let take_overlay = match (final_messages.peek(), overlay.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => false,
+            (None, Some(_)) => true,
+            (Some((final_id, _)), Some((overlay_id, _))) => {
+                if *overlay_id == final_id {
+                    // the overlay shadows the final message
+                    final_messages.next();
+                    true
+                } else {
+                    precedes(overlay_id, final_id)
+                }
+            }
+        };
+        if !take_overlay {
+            return final_messages.next();
+        }
+        if let Some((msg_id, Some(msg))) = overlay.next() {
+            return Some((*msg_id, msg.clone()));
+        }
     }
 }
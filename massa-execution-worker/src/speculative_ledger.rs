@@ -7,10 +7,12 @@
 
 use massa_execution_exports::ExecutionError;
 use massa_final_state::FinalState;
-use massa_hash::Hash;
-use massa_ledger::{Applicable, LedgerChanges};
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_ledger::{Applicable, FinalLedger, LedgerChanges};
+use massa_models::api::AddressStateOverride;
 use massa_models::{Address, Amount};
 use parking_lot::RwLock;
+use std::cell::Cell;
 use std::sync::Arc;
 
 /// The `SpeculativeLedger` contains an thread-safe shared reference to the final ledger (read-only),
@@ -33,6 +35,16 @@ pub struct SpeculativeLedger {
 
     /// list of ledger changes that were applied to this `SpeculativeLedger` since its creation
     added_changes: LedgerChanges,
+
+    /// price locked from the balance of an address for each byte stored in its datastore,
+    /// and refunded when the bytes are removed
+    storage_byte_cost: Amount,
+
+    /// number of reads of the speculative ledger since its creation
+    read_count: Cell<u64>,
+
+    /// number of reads that were not served by the speculative changes and fell through to the final ledger
+    final_read_count: Cell<u64>,
 }
 
 impl SpeculativeLedger {
@@ -41,14 +53,40 @@ impl SpeculativeLedger {
     /// # Arguments
     /// * `final_state`: thread-safe shared access to the final state (for reading only)
     /// * `previous_changes`: accumulation of changes that previously happened to the ledger since finality
-    pub fn new(final_state: Arc<RwLock<FinalState>>, previous_changes: LedgerChanges) -> Self {
+    /// * `storage_byte_cost`: price locked from the balance of an address for each byte stored in its datastore
+    pub fn new(
+        final_state: Arc<RwLock<FinalState>>,
+        previous_changes: LedgerChanges,
+        storage_byte_cost: Amount,
+    ) -> Self {
         SpeculativeLedger {
             final_state,
             previous_changes,
             added_changes: Default::default(),
+            storage_byte_cost,
+            read_count: Default::default(),
+            final_read_count: Default::default(),
         }
     }
 
+    /// Gets the number of reads of the speculative ledger since its creation,
+    /// and the number of them that fell through to the final ledger
+    pub fn get_read_counts(&self) -> (u64, u64) {
+        (self.read_count.get(), self.final_read_count.get())
+    }
+
+    /// Counts a read of the speculative ledger
+    fn count_read(&self) {
+        self.read_count.set(self.read_count.get().saturating_add(1));
+    }
+
+    /// Reads the final ledger, counting the read as one that fell through the speculative changes
+    fn read_final_ledger<T>(&self, read: impl FnOnce(&FinalLedger) -> T) -> T {
+        self.final_read_count
+            .set(self.final_read_count.get().saturating_add(1));
+        read(&self.final_state.read().ledger)
+    }
+
     /// Returns the changes caused to the `SpeculativeLedger` since its creation,
     /// and resets their local value to nothing.
     pub fn take(&mut self) -> LedgerChanges {
@@ -65,6 +103,34 @@ impl SpeculativeLedger {
         self.added_changes = snapshot;
     }
 
+    /// Pretends the given ledger state for some addresses.
+    /// The overrides are applied on top of the previous changes,
+    /// so they are visible to the execution but never returned by `take` or `get_snapshot`.
+    ///
+    /// # Arguments
+    /// * `overrides`: ledger state to pretend for each overridden address
+    pub fn apply_overrides(&mut self, overrides: Vec<AddressStateOverride>) {
+        let mut changes = LedgerChanges::default();
+        for AddressStateOverride {
+            address,
+            balance,
+            bytecode,
+            datastore,
+        } in overrides
+        {
+            if let Some(balance) = balance {
+                changes.set_parallel_balance(address, balance);
+            }
+            if let Some(bytecode) = bytecode {
+                changes.set_bytecode(address, bytecode);
+            }
+            for (key, value) in datastore {
+                changes.set_data_entry(address, key, value);
+            }
+        }
+        self.previous_changes.apply(changes);
+    }
+
     /// Gets the effective parallel balance of an address
     ///
     /// # Arguments:
@@ -74,14 +140,34 @@ impl SpeculativeLedger {
     /// Some(Amount) if the address was found, otherwise None
     pub fn get_parallel_balance(&self, addr: &Address) -> Option<Amount> {
         // try to read from added_changes, then previous_changes, then final_state
+        self.count_read();
         self.added_changes.get_parallel_balance_or_else(addr, || {
             self.previous_changes
                 .get_parallel_balance_or_else(addr, || {
-                    self.final_state.read().ledger.get_parallel_balance(addr)
+                    self.read_final_ledger(|ledger| ledger.get_parallel_balance(addr))
                 })
         })
     }
 
+    /// Gets the coins locked for the datastore storage of an address
+    ///
+    /// # Arguments:
+    /// `addr`: the address to query
+    ///
+    /// # Returns
+    /// `Some(Amount)` if the address was found, otherwise None
+    pub fn get_locked_storage_cost(&self, addr: &Address) -> Option<Amount> {
+        // try to read from added_changes, then previous_changes, then final_state
+        self.count_read();
+        self.added_changes
+            .get_locked_storage_cost_or_else(addr, || {
+                self.previous_changes
+                    .get_locked_storage_cost_or_else(addr, || {
+                        self.read_final_ledger(|ledger| ledger.get_locked_storage_cost(addr))
+                    })
+            })
+    }
+
     /// Gets the effective bytecode of an address
     ///
     /// # Arguments:
@@ -91,9 +177,11 @@ impl SpeculativeLedger {
     /// `Some(Vec<u8>)` if the address was found, otherwise None
     pub fn get_bytecode(&self, addr: &Address) -> Option<Vec<u8>> {
         // try to read from added_changes, then previous_changes, then ledger in final_state
+        self.count_read();
         self.added_changes.get_bytecode_or_else(addr, || {
-            self.previous_changes
-                .get_bytecode_or_else(addr, || self.final_state.read().ledger.get_bytecode(addr))
+            self.previous_changes.get_bytecode_or_else(addr, || {
+                self.read_final_ledger(|ledger| ledger.get_bytecode(addr))
+            })
         })
     }
 
@@ -154,9 +242,11 @@ impl SpeculativeLedger {
     /// true if the address was found, otherwise false
     pub fn entry_exists(&self, addr: &Address) -> bool {
         // try to read from added_changes, then previous_changes, then ledger in final_state
+        self.count_read();
         self.added_changes.entry_exists_or_else(addr, || {
-            self.previous_changes
-                .entry_exists_or_else(addr, || self.final_state.read().ledger.entry_exists(addr))
+            self.previous_changes.entry_exists_or_else(addr, || {
+                self.read_final_ledger(|ledger| ledger.entry_exists(addr))
+            })
         })
     }
 
@@ -210,9 +300,10 @@ impl SpeculativeLedger {
     /// `Some(Vec<u8>)` if the value was found, `None` if the address does not exist or if the key is not in its datastore.
     pub fn get_data_entry(&self, addr: &Address, key: &Hash) -> Option<Vec<u8>> {
         // try to read from added_changes, then previous_changes, then ledger in final_state
+        self.count_read();
         self.added_changes.get_data_entry_or_else(addr, key, || {
             self.previous_changes.get_data_entry_or_else(addr, key, || {
-                self.final_state.read().ledger.get_data_entry(addr, key)
+                self.read_final_ledger(|ledger| ledger.get_data_entry(addr, key))
             })
         })
     }
@@ -227,16 +318,72 @@ impl SpeculativeLedger {
     /// true if the key exists in the address datastore, false otherwise
     pub fn has_data_entry(&self, addr: &Address, key: &Hash) -> bool {
         // try to read from added_changes, then previous_changes, then ledger in final_state
+        self.count_read();
         self.added_changes.has_data_entry_or_else(addr, key, || {
             self.previous_changes.has_data_entry_or_else(addr, key, || {
-                self.final_state.read().ledger.has_data_entry(addr, key)
+                self.read_final_ledger(|ledger| ledger.has_data_entry(addr, key))
             })
         })
     }
 
+    /// Locks the storage cost of the bytes added to the datastore of an address from its balance,
+    /// or refunds the storage cost of the bytes removed from it.
+    /// Refunds never exceed the coins that were actually locked by the address,
+    /// so that removing bytes that were stored without being charged does not create coins.
+    /// No changes are retained in case of failure.
+    ///
+    /// # Arguments
+    /// * `addr`: address owning the datastore
+    /// * `old_size`: number of bytes stored in the datastore entry before the change
+    /// * `new_size`: number of bytes stored in the datastore entry after the change
+    fn update_storage_cost(
+        &mut self,
+        addr: &Address,
+        old_size: usize,
+        new_size: usize,
+    ) -> Result<(), ExecutionError> {
+        let locked = self.get_locked_storage_cost(addr).unwrap_or_default();
+        if new_size > old_size {
+            // lock the cost of the added bytes, failing if the address cannot cover it
+            let added_bytes = (new_size - old_size) as u64;
+            let cost = self
+                .storage_byte_cost
+                .checked_mul_u64(added_bytes)
+                .ok_or_else(|| ExecutionError::RuntimeError("overflow in storage cost".into()))?;
+            let new_locked = locked
+                .checked_add(cost)
+                .ok_or_else(|| ExecutionError::RuntimeError("overflow in storage cost".into()))?;
+            self.transfer_parallel_coins(Some(*addr), None, cost)
+                .map_err(|err| {
+                    ExecutionError::RuntimeError(format!(
+                        "could not lock the storage cost of {} bytes for address {}: {}",
+                        added_bytes, addr, err
+                    ))
+                })?;
+            self.added_changes
+                .set_locked_storage_cost(*addr, new_locked);
+        } else if new_size < old_size {
+            // refund the cost of the removed bytes, within the limit of the locked coins
+            let refund = self
+                .storage_byte_cost
+                .saturating_mul_u64((old_size - new_size) as u64)
+                .min(locked);
+            if refund == Amount::default() {
+                return Ok(());
+            }
+            self.transfer_parallel_coins(None, Some(*addr), refund)?;
+            self.added_changes
+                .set_locked_storage_cost(*addr, locked.saturating_sub(refund));
+        }
+        Ok(())
+    }
+
     /// Sets a data set entry for a given address in the ledger.
     /// Fails if the address doesn't exist.
     /// If the datastore entry does not exist, it is created.
+    /// The storage cost of the added bytes is locked from the balance of the address,
+    /// and the storage cost of the removed bytes is refunded to it.
+    /// Fails if the address balance cannot cover the storage cost.
     ///
     /// # Arguments
     /// * `addr`: target address
@@ -256,6 +403,12 @@ impl SpeculativeLedger {
             )));
         }
 
+        // lock or refund the storage cost of the size difference of the datastore entry
+        let old_size = self
+            .get_data_entry(addr, &key)
+            .map_or(0, |value| HASH_SIZE_BYTES + value.len());
+        self.update_storage_cost(addr, old_size, HASH_SIZE_BYTES + data.len())?;
+
         // set data
         self.added_changes.set_data_entry(*addr, key, data);
 
@@ -264,18 +417,22 @@ impl SpeculativeLedger {
 
     /// Deletes a datastore entry for a given address.
     /// Fails if the entry or address does not exist.
+    /// The storage cost of the entry is refunded to the address.
     ///
     /// # Arguments
     /// * `addr`: address
     /// * `key`: key of the entry to delete in the address' datastore
     pub fn delete_data_entry(&mut self, addr: &Address, key: &Hash) -> Result<(), ExecutionError> {
         // check if the entry exists
-        if !self.has_data_entry(addr, key) {
-            return Err(ExecutionError::RuntimeError(format!(
+        let value = self.get_data_entry(addr, key).ok_or_else(|| {
+            ExecutionError::RuntimeError(format!(
                 "could not delete data entry {} for address {}: entry does not exist",
                 key, addr
-            )));
-        }
+            ))
+        })?;
+
+        // refund the storage cost of the entry
+        self.update_storage_cost(addr, HASH_SIZE_BYTES + value.len(), 0)?;
 
         // delete entry
         self.added_changes.delete_data_entry(*addr, *key);
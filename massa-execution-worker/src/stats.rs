@@ -0,0 +1,159 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module keeps track of the performance of slot executions.
+//! Metrics are measured for each executed slot and aggregated over a bounded history of recent slots.
+//! Final and active executions are aggregated separately: a final slot is only executed
+//! when the speculative execution of the same slot cannot be reused.
+
+use massa_models::stats::{ExecutionStats, SlotExecutionStats};
+use massa_models::Slot;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tracing::warn;
+
+/// Performance metrics of the execution of a single slot
+#[derive(Debug, Clone)]
+pub(crate) struct SlotExecutionMetrics {
+    /// executed slot
+    pub slot: Slot,
+    /// whether the slot was executed as final or as active
+    pub is_final: bool,
+    /// wall time spent executing the whole slot
+    pub total_time: Duration,
+    /// number of executed operations
+    pub operation_count: u64,
+    /// wall time spent executing operations
+    pub operation_time: Duration,
+    /// gas used by the executed operations
+    pub operation_gas_used: u64,
+    /// number of executed asynchronous messages
+    pub async_message_count: u64,
+    /// wall time spent executing asynchronous messages
+    pub async_message_time: Duration,
+    /// gas used by the executed asynchronous messages
+    pub async_message_gas_used: u64,
+    /// number of reads of the speculative ledger
+    pub ledger_read_count: u64,
+    /// number of speculative ledger reads that hit the final ledger
+    pub final_ledger_read_count: u64,
+}
+
+impl SlotExecutionMetrics {
+    /// Creates empty metrics for the final or active execution of a slot
+    pub fn new(slot: Slot, is_final: bool) -> Self {
+        SlotExecutionMetrics {
+            slot,
+            is_final,
+            total_time: Duration::ZERO,
+            operation_count: 0,
+            operation_time: Duration::ZERO,
+            operation_gas_used: 0,
+            async_message_count: 0,
+            async_message_time: Duration::ZERO,
+            async_message_gas_used: 0,
+            ledger_read_count: 0,
+            final_ledger_read_count: 0,
+        }
+    }
+
+    /// Logs a warning with the breakdown of the execution time if the slot took longer than `threshold` to execute
+    pub fn log_if_slow(&self, threshold: Duration) {
+        if self.total_time <= threshold {
+            return;
+        }
+        warn!(
+            "slow {} execution of slot {}: {:?} total, {} operations in {:?} ({} gas), {} async messages in {:?} ({} gas), {} ledger reads ({} from the final ledger)",
+            if self.is_final { "final" } else { "active" },
+            self.slot,
+            self.total_time,
+            self.operation_count,
+            self.operation_time,
+            self.operation_gas_used,
+            self.async_message_count,
+            self.async_message_time,
+            self.async_message_gas_used,
+            self.ledger_read_count,
+            self.final_ledger_read_count
+        );
+    }
+}
+
+/// Bounded histories of the metrics of the latest final and active slot executions
+pub(crate) struct ExecutionMetricsHistory {
+    /// maximal number of slot executions of each kind kept in the history
+    max_length: usize,
+    /// metrics of the latest final slot executions, newest at the back
+    final_history: VecDeque<SlotExecutionMetrics>,
+    /// metrics of the latest active slot executions, newest at the back
+    active_history: VecDeque<SlotExecutionMetrics>,
+}
+
+impl ExecutionMetricsHistory {
+    /// Creates an empty history keeping the metrics of at most `max_length` final
+    /// and `max_length` active slot executions
+    pub fn new(max_length: usize) -> Self {
+        ExecutionMetricsHistory {
+            max_length,
+            final_history: VecDeque::with_capacity(max_length),
+            active_history: VecDeque::with_capacity(max_length),
+        }
+    }
+
+    /// Appends the metrics of an executed slot to the history of its kind,
+    /// dropping the oldest ones if that history is full
+    pub fn push(&mut self, metrics: SlotExecutionMetrics) {
+        if self.max_length == 0 {
+            return;
+        }
+        let history = if metrics.is_final {
+            &mut self.final_history
+        } else {
+            &mut self.active_history
+        };
+        while history.len() >= self.max_length {
+            history.pop_front();
+        }
+        history.push_back(metrics);
+    }
+
+    /// Aggregates the metrics of the final and active slot executions separately
+    pub fn get_stats(&self) -> ExecutionStats {
+        ExecutionStats {
+            final_slots: aggregate(&self.final_history),
+            active_slots: aggregate(&self.active_history),
+        }
+    }
+}
+
+/// Aggregates the metrics of a list of slot executions
+fn aggregate(history: &VecDeque<SlotExecutionMetrics>) -> SlotExecutionStats {
+    let mut stats = SlotExecutionStats::default();
+    for metrics in history {
+        let slot_time_us = duration_to_us(metrics.total_time);
+        stats.slot_count += 1;
+        stats.total_time_us = stats.total_time_us.saturating_add(slot_time_us);
+        stats.max_slot_time_us = stats.max_slot_time_us.max(slot_time_us);
+        stats.operation_count += metrics.operation_count;
+        stats.operation_time_us = stats
+            .operation_time_us
+            .saturating_add(duration_to_us(metrics.operation_time));
+        stats.operation_gas_used = stats
+            .operation_gas_used
+            .saturating_add(metrics.operation_gas_used);
+        stats.async_message_count += metrics.async_message_count;
+        stats.async_message_time_us = stats
+            .async_message_time_us
+            .saturating_add(duration_to_us(metrics.async_message_time));
+        stats.async_message_gas_used = stats
+            .async_message_gas_used
+            .saturating_add(metrics.async_message_gas_used);
+        stats.ledger_read_count += metrics.ledger_read_count;
+        stats.final_ledger_read_count += metrics.final_ledger_read_count;
+    }
+    stats
+}
+
+/// Converts a duration to a saturated number of microseconds
+fn duration_to_us(duration: Duration) -> u64 {
+    duration.as_micros().try_into().unwrap_or(u64::MAX)
+}
@@ -1,29 +1,40 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
+use crate::context::ExecutionContext;
+use crate::execution::ExecutionState;
+use crate::interface_impl::InterfaceImpl;
+use crate::readonly_executor::{ReadOnlyExecutor, ReadOnlyInputData};
+use crate::request_queue::RequestQueue;
+use crate::speculative_async_pool::SpeculativeAsyncPool;
 use crate::start_execution_worker;
-use massa_async_pool::AsyncPoolConfig;
+use massa_async_pool::{
+    AsyncMessage, AsyncMessageRecurrence, AsyncPoolChanges, AsyncPoolConfig, Change,
+};
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionError, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ExecutionConfig, ExecutionError, ExecutionStackElement, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
 };
 use massa_final_state::{FinalState, FinalStateConfig};
 use massa_hash::Hash;
-use massa_ledger::{LedgerConfig, LedgerError};
+use massa_ledger::{LedgerChanges, LedgerConfig, LedgerError};
 use massa_models::{
-    api::EventFilter,
+    api::{AddressStateOverride, AsyncMessageFilter, EventFilter},
     constants::{AMOUNT_DECIMAL_FACTOR, FINAL_HISTORY_LENGTH, THREAD_COUNT},
+    execution::ReceiptOrigin,
+    signed::Signable,
     Block, BlockHeader, BlockId, Operation, OperationType, SerializeCompact, SignedHeader,
     SignedOperation,
 };
 use massa_models::{Address, Amount, Slot};
 use massa_signature::{derive_public_key, generate_random_private_key, PrivateKey, PublicKey};
 use massa_storage::Storage;
-use parking_lot::RwLock;
+use parking_lot::{Condvar, Mutex, RwLock};
 use serial_test::serial;
 use std::{
     cmp::Reverse,
     collections::{BTreeMap, HashMap},
     str::FromStr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tempfile::{NamedTempFile, TempDir};
 
@@ -92,8 +103,246 @@ fn test_sending_read_only_execution_command() {
             target: ReadOnlyExecutionTarget::BytecodeExecution(
                 include_bytes!("./wasm/event_test.wasm").to_vec(),
             ),
+            trace: false,
+            state_overrides: Default::default(),
+            simulated_slot: None,
+            final_slot: None,
+        })
+        .unwrap();
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_parallel_read_only_executions() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) =
+        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let request = || ReadOnlyExecutionRequest {
+        max_gas: 1_000_000,
+        simulated_gas_price: Amount::from_raw(1_000_000 * AMOUNT_DECIMAL_FACTOR),
+        call_stack: vec![],
+        target: ReadOnlyExecutionTarget::BytecodeExecution(
+            include_bytes!("./wasm/event_test.wasm").to_vec(),
+        ),
+        trace: false,
+        state_overrides: Default::default(),
+        simulated_slot: None,
+        final_slot: None,
+    };
+    // send several requests at once from different threads
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let controller = controller.clone_box();
+            std::thread::spawn(move || controller.execute_readonly_request(request()))
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap().unwrap();
+    }
+    manager.stop();
+
+    // a request that cannot be served within the timeout returns an error
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let exec_cfg = ExecutionConfig {
+        readonly_timeout: 0.into(),
+        ..ExecutionConfig::default()
+    };
+    let (mut manager, controller) =
+        start_execution_worker(exec_cfg, sample_state, Default::default());
+    assert!(matches!(
+        controller.execute_readonly_request(request()),
+        Err(ExecutionError::ReadOnlyTimeout(_))
+    ));
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_read_only_execution_interrupted_at_deadline() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let config = ExecutionConfig::default();
+    let execution_state = Arc::new(RwLock::new(ExecutionState::new(
+        config.clone(),
+        sample_state.clone(),
+        Default::default(),
+    )));
+    let input_data = Arc::new((
+        Condvar::new(),
+        Mutex::new(ReadOnlyInputData {
+            stop: false,
+            requests: RequestQueue::new(config.readonly_queue_length),
+        }),
+    ));
+    let executor = ReadOnlyExecutor::new(&config, input_data, execution_state, sample_state);
+
+    let request = || ReadOnlyExecutionRequest {
+        max_gas: 1_000_000,
+        simulated_gas_price: Amount::from_raw(1_000_000 * AMOUNT_DECIMAL_FACTOR),
+        call_stack: vec![],
+        target: ReadOnlyExecutionTarget::BytecodeExecution(
+            include_bytes!("./wasm/event_test.wasm").to_vec(),
+        ),
+        trace: false,
+        state_overrides: Default::default(),
+        simulated_slot: None,
+        final_slot: None,
+    };
+
+    // the deadline is already reached when the execution starts, as if it was reached during the execution:
+    // the execution fails at its first interface call instead of running until its gas is exhausted
+    assert!(matches!(
+        executor.execute(Instant::now(), request()),
+        Err(ExecutionError::ReadOnlyTimeout(_))
+    ));
+
+    // with time left, it runs to completion
+    let mut output = executor
+        .execute(Instant::now() + Duration::from_secs(60), request())
+        .unwrap();
+    assert!(!output.events.take().is_empty());
+}
+
+#[test]
+#[serial]
+fn test_read_only_execution_trace() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) =
+        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let (address, _, _) = get_random_address_full();
+    let output = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            simulated_gas_price: Amount::from_raw(1_000_000 * AMOUNT_DECIMAL_FACTOR),
+            call_stack: vec![ExecutionStackElement {
+                address,
+                coins: Default::default(),
+                owned_addresses: vec![address],
+            }],
+            target: ReadOnlyExecutionTarget::BytecodeExecution(
+                include_bytes!("./wasm/event_test.wasm").to_vec(),
+            ),
+            trace: true,
+            state_overrides: Default::default(),
+            simulated_slot: None,
+            final_slot: None,
+        })
+        .unwrap();
+    assert_eq!(output.traces.len(), 1);
+    let trace = &output.traces[0];
+    assert!(trace.error.is_none());
+    assert!(trace.gas_used.unwrap() > 0);
+    let event_call = trace
+        .abi_calls
+        .iter()
+        .find(|call| call.name == "generate_event")
+        .expect("generate_event call not traced");
+    assert_eq!(event_call.call_stack, vec![address]);
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_read_only_execution_state_overrides() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) =
+        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let (caller, _, _) = get_random_address_full();
+    let (target, _, _) = get_random_address_full();
+    let coins = Amount::from_str("10").unwrap();
+    let simulated_slot = Slot::new(42, 1);
+    // pretend the caller is funded and the target holds the message handler bytecode
+    let mut output = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            simulated_gas_price: Amount::from_raw(1_000_000 * AMOUNT_DECIMAL_FACTOR),
+            call_stack: vec![
+                ExecutionStackElement {
+                    address: caller,
+                    coins: Default::default(),
+                    owned_addresses: vec![caller],
+                },
+                ExecutionStackElement {
+                    address: target,
+                    coins,
+                    owned_addresses: vec![target],
+                },
+            ],
+            target: ReadOnlyExecutionTarget::FunctionCall {
+                target_addr: target,
+                target_func: "receive".into(),
+                parameter: "hello".into(),
+                coins,
+            },
+            trace: false,
+            state_overrides: vec![
+                AddressStateOverride {
+                    address: caller,
+                    balance: Some(Amount::from_str("100").unwrap()),
+                    bytecode: None,
+                    datastore: Default::default(),
+                },
+                AddressStateOverride {
+                    address: target,
+                    balance: None,
+                    bytecode: Some(include_bytes!("./wasm/receive_message.wasm").to_vec()),
+                    datastore: Default::default(),
+                },
+            ],
+            simulated_slot: Some(simulated_slot),
+            final_slot: None,
         })
         .unwrap();
+    assert_eq!(output.slot, simulated_slot);
+    let events = output.events.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].data, "message received: hello");
+    assert_eq!(events[0].context.slot, simulated_slot);
+    // the overrides are not part of the output, only the transfer of the call coins is
+    let ledger_changes = &output.state_changes.ledger_changes;
+    assert_eq!(
+        ledger_changes.get_parallel_balance_or_else(&caller, || None),
+        Some(Amount::from_str("90").unwrap())
+    );
+    assert_eq!(
+        ledger_changes.get_parallel_balance_or_else(&target, || None),
+        Some(coins)
+    );
+    assert!(ledger_changes
+        .get_bytecode_or_else(&target, || None)
+        .is_none());
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_read_only_execution_at_final_slot() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let final_slot = sample_state.read().slot;
+    let (mut manager, controller) =
+        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+    let request = |final_slot| ReadOnlyExecutionRequest {
+        max_gas: 1_000_000,
+        simulated_gas_price: Amount::from_raw(1_000_000 * AMOUNT_DECIMAL_FACTOR),
+        call_stack: vec![],
+        target: ReadOnlyExecutionTarget::BytecodeExecution(
+            include_bytes!("./wasm/event_test.wasm").to_vec(),
+        ),
+        trace: false,
+        state_overrides: Default::default(),
+        simulated_slot: None,
+        final_slot: Some(final_slot),
+    };
+    // the latest final slot is always available
+    let output = controller
+        .execute_readonly_request(request(final_slot))
+        .unwrap();
+    assert_eq!(output.slot, final_slot.get_next_slot(THREAD_COUNT).unwrap());
+    // slots outside of the retained final history are rejected
+    assert!(matches!(
+        controller.execute_readonly_request(request(Slot::new(0, 0))),
+        Err(ExecutionError::FinalStateError(_))
+    ));
     manager.stop();
 }
 
@@ -296,6 +545,100 @@ fn generate_events() {
     manager.stop();
 }
 
+#[test]
+#[serial]
+fn get_operation_receipts() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Execute `event_test.wasm` in a block and check the receipt of the operation.
+    let exec_cfg = ExecutionConfig {
+        t0: 10.into(),
+        ..ExecutionConfig::default()
+    };
+    let storage: Storage = Default::default();
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(exec_cfg, sample_state, storage.clone());
+
+    let (_, sender_private_key, sender_public_key) = get_random_address_full();
+    let operation = create_execute_sc_operation(
+        sender_private_key,
+        sender_public_key,
+        include_bytes!("./wasm/event_test.wasm"),
+    )
+    .unwrap();
+    let operation_id = operation.content.compute_id().unwrap();
+    let (block_id, block) = create_block(vec![operation], Slot::new(1, 0)).unwrap();
+    let slot = block.header.content.slot;
+
+    storage.store_block(block_id, block, Default::default());
+
+    let finalized_blocks: HashMap<Slot, BlockId> = Default::default();
+    let mut blockclique: HashMap<Slot, BlockId> = Default::default();
+
+    blockclique.insert(slot, block_id);
+
+    controller.update_blockclique_status(finalized_blocks, blockclique);
+
+    std::thread::sleep(Duration::from_millis(1000));
+    let receipts = controller.get_operation_receipts(vec![operation_id]);
+    assert_eq!(receipts.len(), 1);
+    let receipt = &receipts[0];
+    assert_eq!(receipt.origin, ReceiptOrigin::Operation(operation_id));
+    assert_eq!(receipt.slot, slot);
+    assert!(!receipt.is_final);
+    assert!(receipt.error.is_none(), "{:?}", receipt.error);
+    assert!(receipt.gas_used.is_some());
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_execution_stats() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    // Execute `event_test.wasm` in an active block and check that it shows in the execution stats,
+    // and that it is not counted again when the block becomes final.
+    let exec_cfg = ExecutionConfig {
+        t0: 10.into(),
+        ..ExecutionConfig::default()
+    };
+    let storage: Storage = Default::default();
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(exec_cfg, sample_state, storage.clone());
+    assert_eq!(controller.get_stats().active_slots.slot_count, 0);
+
+    let (_, sender_private_key, sender_public_key) = get_random_address_full();
+    let operation = create_execute_sc_operation(
+        sender_private_key,
+        sender_public_key,
+        include_bytes!("./wasm/event_test.wasm"),
+    )
+    .unwrap();
+    let (block_id, block) = create_block(vec![operation], Slot::new(1, 0)).unwrap();
+    let slot = block.header.content.slot;
+
+    storage.store_block(block_id, block, Default::default());
+
+    let mut blockclique: HashMap<Slot, BlockId> = Default::default();
+    blockclique.insert(slot, block_id);
+    controller.update_blockclique_status(Default::default(), blockclique.clone());
+
+    std::thread::sleep(Duration::from_millis(1000));
+    let stats = controller.get_stats();
+    assert_eq!(stats.final_slots.slot_count, 0);
+    assert!(stats.active_slots.slot_count > 0);
+    assert_eq!(stats.active_slots.operation_count, 1);
+    assert!(stats.active_slots.operation_gas_used > 0);
+    assert!(stats.active_slots.max_slot_time_us <= stats.active_slots.total_time_us);
+    assert!(stats.active_slots.final_ledger_read_count <= stats.active_slots.ledger_read_count);
+
+    // the block becoming final reuses its speculative execution, which is not counted twice
+    controller.update_blockclique_status(blockclique, Default::default());
+    std::thread::sleep(Duration::from_millis(1000));
+    let stats = controller.get_stats();
+    assert_eq!(stats.final_slots.operation_count, 0);
+    assert_eq!(stats.active_slots.operation_count, 1);
+    manager.stop();
+}
+
 /// Create an operation for the given sender with `data` as bytecode.
 /// Return a result that should be unwrapped in the root `#[test]` routine.
 fn create_execute_sc_operation(
@@ -382,3 +725,554 @@ fn create_block(
     )?;
     Ok((id, Block { header, operations }))
 }
+
+#[test]
+#[serial]
+fn test_datastore_storage_cost() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let storage_byte_cost = Amount::from_str("0.001").unwrap();
+    let mut context = ExecutionContext::new(sample_state, Default::default(), storage_byte_cost);
+    let address = get_random_address();
+    context.stack = vec![ExecutionStackElement {
+        address,
+        coins: Default::default(),
+        owned_addresses: vec![address],
+    }];
+    context
+        .transfer_parallel_coins(None, Some(address), Amount::from_str("1").unwrap())
+        .unwrap();
+    let key = Hash::compute_from(b"key");
+
+    // storing a 32 bytes key with a 8 bytes value locks 40 bytes worth of coins
+    context.set_data_entry(&address, key, vec![0; 8]).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("0.96").unwrap()
+    );
+
+    // growing the value locks the cost of the extra bytes only
+    context
+        .append_data_entry(&address, key, vec![0; 10])
+        .unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("0.95").unwrap()
+    );
+
+    // writes that the balance cannot cover are rejected without changes
+    assert!(context
+        .set_data_entry(&address, key, vec![0; 1000])
+        .is_err());
+    assert_eq!(context.get_data_entry(&address, &key).unwrap().len(), 18);
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("0.95").unwrap()
+    );
+
+    // shrinking the value refunds the cost of the removed bytes
+    context.set_data_entry(&address, key, vec![0; 2]).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("0.966").unwrap()
+    );
+
+    // deleting the entry refunds its whole storage cost
+    context.delete_data_entry(&address, &key).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("1").unwrap()
+    );
+}
+
+#[test]
+#[serial]
+fn test_uncharged_datastore_entry_refund() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let address = get_random_address();
+    let key = Hash::compute_from(b"key");
+    let other_key = Hash::compute_from(b"other key");
+
+    // datastore entries stored in the final ledger before storage costs were locked
+    let mut changes = LedgerChanges::default();
+    changes.set_parallel_balance(address, Amount::from_str("1").unwrap());
+    changes.set_data_entry(address, key, vec![0; 8]);
+    changes.set_data_entry(address, other_key, vec![0; 8]);
+    sample_state
+        .write()
+        .ledger
+        .apply_changes(changes, Slot::new(0, 0));
+
+    let storage_byte_cost = Amount::from_str("0.001").unwrap();
+    let mut context = ExecutionContext::new(sample_state, Default::default(), storage_byte_cost);
+    context.stack = vec![ExecutionStackElement {
+        address,
+        coins: Default::default(),
+        owned_addresses: vec![address],
+    }];
+
+    // shrinking or deleting entries that were never charged refunds nothing
+    context.set_data_entry(&address, key, vec![0; 2]).unwrap();
+    context.delete_data_entry(&address, &other_key).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("1").unwrap()
+    );
+
+    // growing an uncharged entry locks the cost of the extra bytes,
+    // and only those coins can be refunded afterwards
+    context.set_data_entry(&address, key, vec![0; 12]).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("0.99").unwrap()
+    );
+    context.delete_data_entry(&address, &key).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_str("1").unwrap()
+    );
+}
+
+#[test]
+#[serial]
+fn test_recurring_message_gas_budget_prepaid() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let mut context = ExecutionContext::new(sample_state, Default::default(), Default::default());
+    let address = get_random_address();
+    context.stack = vec![ExecutionStackElement {
+        address,
+        coins: Default::default(),
+        owned_addresses: vec![address],
+    }];
+    context
+        .transfer_parallel_coins(None, Some(address), Amount::from_raw(100))
+        .unwrap();
+    let message = |gas_budget: u64| AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index: gas_budget,
+        sender: address,
+        destination: address,
+        handler: "function".to_string(),
+        validity_start: Slot::new(1, 0),
+        validity_end: Slot::new(3, 0),
+        max_gas: 10,
+        gas_price: Amount::from_raw(2),
+        coins: Default::default(),
+        data: Vec::new(),
+        recurrence: Some(AsyncMessageRecurrence {
+            period: 10,
+            remaining_count: 1,
+            gas_budget,
+        }),
+    };
+
+    // the gas budget is debited from the sender when the message is sent
+    let prepaid = message(15);
+    context.push_new_message(prepaid.clone()).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_raw(70)
+    );
+
+    // a budget that the sender cannot afford is rejected without changes
+    assert!(context.push_new_message(message(100)).is_err());
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_raw(70)
+    );
+
+    // reimbursing the budget gives back exactly what was debited
+    context.cancel_async_message(&prepaid);
+    assert_eq!(
+        context.get_parallel_balance(&address).unwrap(),
+        Amount::from_raw(100)
+    );
+}
+
+#[test]
+#[serial]
+fn test_cancel_message() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let sender = get_random_address();
+    let other = get_random_address();
+    let message = |emission_index: u64| AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index,
+        sender,
+        destination: other,
+        handler: "function".to_string(),
+        validity_start: Slot::new(1, 0),
+        validity_end: Slot::new(3, 0),
+        max_gas: 10,
+        gas_price: Amount::from_raw(2),
+        coins: Default::default(),
+        data: Vec::new(),
+        recurrence: Some(AsyncMessageRecurrence {
+            period: 10,
+            remaining_count: 1,
+            gas_budget: 15,
+        }),
+    };
+    // a message already in the final pool, whose gas budget was prepaid
+    let final_msg = message(0);
+    let final_id = final_msg.compute_id();
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(AsyncPoolChanges(vec![Change::Add(final_id, final_msg)]));
+
+    let mut context = ExecutionContext::new(sample_state, Default::default(), Default::default());
+    let stack = |address: Address| {
+        vec![ExecutionStackElement {
+            address,
+            coins: Default::default(),
+            owned_addresses: vec![address],
+        }]
+    };
+    context.stack = stack(sender);
+    context
+        .transfer_parallel_coins(None, Some(sender), Amount::from_raw(100))
+        .unwrap();
+    let emitted_msg = message(1);
+    let emitted_id = emitted_msg.compute_id();
+    context.push_new_message(emitted_msg).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(70)
+    );
+
+    // only the sender can cancel its messages
+    context.stack = stack(other);
+    assert!(context.cancel_message(&emitted_id).is_err());
+    context.stack = stack(sender);
+    assert!(context.cancel_message(&message(2).compute_id()).is_err());
+
+    // cancelling reimburses the prepaid gas budget and removes the message
+    context.cancel_message(&emitted_id).unwrap();
+    context.cancel_message(&final_id).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(130)
+    );
+    assert!(context.cancel_message(&emitted_id).is_err());
+    let output = context.settle_slot();
+    assert!(matches!(
+        &output.state_changes.async_pool_changes.0[..],
+        [Change::Delete(id)] if *id == final_id
+    ));
+}
+
+#[test]
+#[serial]
+fn test_interface_cancel_message() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let sender = get_random_address();
+    let other = get_random_address();
+    let mut context = ExecutionContext::new(sample_state, Default::default(), Default::default());
+    let stack = |address: Address| {
+        vec![ExecutionStackElement {
+            address,
+            coins: Default::default(),
+            owned_addresses: vec![address],
+        }]
+    };
+    context.stack = stack(sender);
+    context
+        .transfer_parallel_coins(None, Some(sender), Amount::from_raw(100))
+        .unwrap();
+    let message = AsyncMessage {
+        emission_slot: Slot::new(0, 1),
+        emission_index: 3,
+        sender,
+        destination: other,
+        handler: "function".to_string(),
+        validity_start: Slot::new(1, 0),
+        validity_end: Slot::new(3, 0),
+        max_gas: 10,
+        gas_price: Amount::from_raw(2),
+        coins: Amount::from_raw(20),
+        data: Vec::new(),
+        recurrence: Some(AsyncMessageRecurrence {
+            period: 10,
+            remaining_count: 1,
+            gas_budget: 15,
+        }),
+    };
+    // both the coins and the gas budget of the message are locked when it is sent
+    context.push_new_message(message).unwrap();
+    assert_eq!(
+        context.get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(50)
+    );
+    let context = Arc::new(Mutex::new(context));
+    let interface = InterfaceImpl::new(ExecutionConfig::default(), context.clone());
+
+    // only the emitter of the message can cancel it
+    context.lock().stack = stack(other);
+    assert!(interface.cancel_message(20, (0, 1), 3).is_err());
+    context.lock().stack = stack(sender);
+    assert!(interface.cancel_message(20, (0, 1), 4).is_err());
+    assert!(interface.cancel_message(20, (0, THREAD_COUNT), 3).is_err());
+
+    // cancelling refunds the locked coins and gas budget
+    interface.cancel_message(20, (0, 1), 3).unwrap();
+    assert_eq!(
+        context.lock().get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(100)
+    );
+    assert!(interface.cancel_message(20, (0, 1), 3).is_err());
+}
+
+#[test]
+#[serial]
+fn test_send_recurring_message() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let sender = get_random_address();
+    let destination = get_random_address();
+    let mut context =
+        ExecutionContext::new(sample_state.clone(), Default::default(), Default::default());
+    context.stack = vec![ExecutionStackElement {
+        address: sender,
+        coins: Default::default(),
+        owned_addresses: vec![sender],
+    }];
+    context
+        .transfer_parallel_coins(None, Some(sender), Amount::from_raw(100))
+        .unwrap();
+    let context = Arc::new(Mutex::new(context));
+    let interface = InterfaceImpl::new(ExecutionConfig::default(), context.clone());
+    let send = |raw_coins: u64, max_repetitions: u64| {
+        interface.send_recurring_message(
+            &destination.to_string(),
+            "receive",
+            (1, 0),
+            (3, 0),
+            10,
+            2,
+            raw_coins,
+            b"data",
+            5,
+            max_repetitions,
+        )
+    };
+
+    // the coins and the fees of the two repetitions after the first execution are locked
+    send(20, 3).unwrap();
+    assert_eq!(
+        context.lock().get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(40)
+    );
+
+    // messages that cannot be afforded or never run are rejected without changes
+    assert!(send(50, 1).is_err());
+    assert!(send(0, 0).is_err());
+    assert_eq!(
+        context.lock().get_parallel_balance(&sender).unwrap(),
+        Amount::from_raw(40)
+    );
+    assert_eq!(context.lock().created_message_index, 1);
+
+    // the message is executed three times, every 5 periods
+    let changes = context
+        .lock()
+        .settle_slot()
+        .state_changes
+        .async_pool_changes;
+    let msg_id = match &changes.0[..] {
+        [Change::Add(id, msg)] => {
+            assert_eq!(msg.coins, Amount::from_raw(20));
+            assert_eq!(
+                msg.recurrence,
+                Some(AsyncMessageRecurrence {
+                    period: 5,
+                    remaining_count: 2,
+                    gas_budget: 20,
+                })
+            );
+            *id
+        }
+        _ => panic!("the message was not sent"),
+    };
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(changes);
+    for execution_period in [2, 7, 12] {
+        let mut pool = SpeculativeAsyncPool::new(sample_state.clone(), Default::default());
+        let batch = pool.take_batch_to_execute(Slot::new(execution_period, 0), 100);
+        assert_eq!(
+            batch.len(),
+            1,
+            "no execution at period {}",
+            execution_period
+        );
+        assert_eq!(batch[0].0, msg_id);
+        sample_state
+            .write()
+            .async_pool
+            .apply_changes_unchecked(pool.take());
+    }
+    let mut pool = SpeculativeAsyncPool::new(sample_state.clone(), Default::default());
+    assert!(pool.take_batch_to_execute(Slot::new(17, 0), 100).is_empty());
+}
+
+#[test]
+#[serial]
+fn test_get_async_messages() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    // add a message that is not valid before a long time to the final pool
+    let sender = get_random_address();
+    let destination = get_random_address();
+    let message = AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index: 0,
+        sender,
+        destination,
+        handler: "receive".into(),
+        max_gas: 100_000,
+        gas_price: Amount::from_str("0.001").unwrap(),
+        coins: Amount::from_str("1").unwrap(),
+        validity_start: Slot::new(1000, 0),
+        validity_end: Slot::new(1010, 0),
+        data: b"hello".to_vec(),
+        recurrence: None,
+    };
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(AsyncPoolChanges(vec![Change::Add(
+            message.compute_id(),
+            message,
+        )]));
+    let (mut manager, controller) =
+        start_execution_worker(ExecutionConfig::default(), sample_state, Default::default());
+
+    let messages = controller.get_async_messages(AsyncMessageFilter {
+        sender: Some(sender),
+        start: Some(Slot::new(1005, 0)),
+        ..Default::default()
+    });
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].destination, destination);
+    assert_eq!(messages[0].data, b"hello".to_vec());
+    assert!(messages[0].is_final);
+
+    // messages that do not match the filter are ignored
+    assert!(controller
+        .get_async_messages(AsyncMessageFilter {
+            destination: Some(sender),
+            ..Default::default()
+        })
+        .is_empty());
+    assert!(controller
+        .get_async_messages(AsyncMessageFilter {
+            end: Some(Slot::new(1000, 0)),
+            ..Default::default()
+        })
+        .is_empty());
+    manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_take_batch() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let address = Address(Hash::compute_from(b"abc"));
+    let mut changes = AsyncPoolChanges::default();
+    for i in 1..10 {
+        changes.push_add(
+            (Reverse(Amount::from_raw(i)), Slot::new(0, 0), 0),
+            AsyncMessage {
+                emission_slot: Slot::new(0, 0),
+                emission_index: 0,
+                sender: address,
+                destination: address,
+                handler: "function".to_string(),
+                validity_start: Slot::new(1, 0),
+                validity_end: Slot::new(3, 0),
+                max_gas: i,
+                gas_price: Amount::from_raw(1),
+                coins: Amount::from_raw(0),
+                data: Vec::new(),
+                recurrence: None,
+            },
+        );
+    }
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(changes);
+    assert_eq!(sample_state.read().async_pool.len(), 9);
+
+    // the batch is taken from the final pool through the speculative pool
+    let mut pool = SpeculativeAsyncPool::new(sample_state.clone(), Default::default());
+    let batch = pool.take_batch_to_execute(Slot::new(2, 0), 19);
+    assert_eq!(batch.len(), 3);
+    assert_eq!(pool.take().0.len(), 3);
+    let batch = pool.take_batch_to_execute(Slot::new(2, 0), 100);
+    assert_eq!(batch.len(), 6);
+}
+
+#[test]
+#[serial]
+fn test_recurring_message() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let address = Address(Hash::compute_from(b"abc"));
+    let message = AsyncMessage {
+        emission_slot: Slot::new(0, 0),
+        emission_index: 0,
+        sender: address,
+        destination: address,
+        handler: "function".to_string(),
+        validity_start: Slot::new(1, 0),
+        validity_end: Slot::new(3, 0),
+        max_gas: 10,
+        gas_price: Amount::from_raw(1),
+        coins: Amount::from_raw(5),
+        data: Vec::new(),
+        recurrence: Some(AsyncMessageRecurrence {
+            period: 10,
+            remaining_count: 1,
+            gas_budget: 15,
+        }),
+    };
+    let msg_id = message.compute_id();
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(AsyncPoolChanges(vec![Change::Add(msg_id, message)]));
+
+    // the message is rescheduled 10 periods after the execution slot
+    let mut pool = SpeculativeAsyncPool::new(sample_state.clone(), Default::default());
+    let batch = pool.take_batch_to_execute(Slot::new(2, 0), 100);
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch[0].1.coins, Amount::from_raw(5));
+    assert!(batch[0].1.recurrence.is_none());
+    assert!(pool.take_batch_to_execute(Slot::new(2, 1), 100).is_empty());
+    let changes = pool.take();
+    let next_msg = match &changes.0[..] {
+        [Change::Add(id, next_msg)] if *id == msg_id => next_msg.clone(),
+        _ => panic!("the message was not rescheduled"),
+    };
+    assert_eq!(next_msg.validity_start, Slot::new(12, 0));
+    assert_eq!(next_msg.validity_end, Slot::new(14, 0));
+    assert_eq!(next_msg.coins, Amount::default());
+    assert_eq!(
+        next_msg.recurrence,
+        Some(AsyncMessageRecurrence {
+            period: 10,
+            remaining_count: 0,
+            gas_budget: 5,
+        })
+    );
+
+    // the last occurrence is removed from the pool, keeping its remaining gas budget for reimbursement
+    sample_state
+        .write()
+        .async_pool
+        .apply_changes_unchecked(changes);
+    let mut pool = SpeculativeAsyncPool::new(sample_state.clone(), Default::default());
+    let batch = pool.take_batch_to_execute(Slot::new(12, 0), 100);
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch[0].1.recurrence.as_ref().unwrap().gas_budget, 5);
+    assert!(matches!(&pool.take().0[..], [Change::Delete(id)] if *id == msg_id));
+}
@@ -7,11 +7,8 @@
 
 use crate::controller::{ExecutionControllerImpl, ExecutionInputData, ExecutionManagerImpl};
 use crate::execution::ExecutionState;
-use crate::request_queue::RequestQueue;
-use massa_execution_exports::{
-    ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager, ExecutionOutput,
-    ReadOnlyExecutionRequest,
-};
+use crate::readonly_executor::ReadOnlyExecutorPool;
+use massa_execution_exports::{ExecutionConfig, ExecutionController, ExecutionManager};
 use massa_final_state::FinalState;
 use massa_models::BlockId;
 use massa_models::{
@@ -46,8 +43,6 @@ pub(crate) struct ExecutionThread {
     last_active_slot: Slot,
     // Execution state (see execution.rs) to which execution requests are sent
     execution_state: Arc<RwLock<ExecutionState>>,
-    /// queue for read-only requests and response MPSCs to send back their outputs
-    readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
 }
 
 impl ExecutionThread {
@@ -75,7 +70,6 @@ impl ExecutionThread {
             pending_final_blocks: Default::default(),
             blockclique: Default::default(),
             active_slots: Default::default(),
-            readonly_requests: RequestQueue::new(config.readonly_queue_length),
             config,
             execution_state,
         }
@@ -279,7 +273,7 @@ impl ExecutionThread {
         exec_state.clear_history();
 
         // execute slot
-        let exec_out = exec_state.execute_slot(slot, exec_target);
+        let exec_out = exec_state.execute_slot(slot, exec_target, true);
 
         // apply execution output to final state
         exec_state.apply_final_execution_output(exec_out);
@@ -321,7 +315,7 @@ impl ExecutionThread {
         };
 
         // execute the slot
-        let exec_out = exec_state.execute_slot(slot, exec_target);
+        let exec_out = exec_state.execute_slot(slot, exec_target, false);
 
         // apply execution output to active state
         exec_state.apply_active_execution_output(exec_out);
@@ -370,39 +364,6 @@ impl ExecutionThread {
         exec_state.truncate_history(&self.active_slots, &self.ready_final_slots);
     }
 
-    /// Append incoming read-only requests to the relevant queue,
-    /// Cancel those that are in excess if there are too many.
-    fn update_readonly_requests(
-        &mut self,
-        new_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
-    ) {
-        // Append incoming readonly requests to our readonly request queue
-        // Excess requests are cancelled
-        self.readonly_requests.extend(new_requests);
-    }
-
-    /// Executes a read-only request from the queue, if any.
-    /// The result of the execution is sent asynchronously through the response channel provided with the request.
-    ///
-    /// # Returns
-    /// true if a request was executed, false otherwise
-    fn execute_one_readonly_request(&mut self) -> bool {
-        if let Some(req_resp) = self.readonly_requests.pop() {
-            let (req, resp_tx) = req_resp.into_request_sender_pair();
-
-            // Acquire read access to the execution state and execute the read-only request
-            let outcome = self.execution_state.read().execute_readonly_request(req);
-
-            // Send the execution output through resp_tx.
-            // Ignore errors because they just mean that the request emitter dropped the received
-            // because it doesn't need the response anymore.
-            let _ = resp_tx.send(outcome);
-
-            return true;
-        }
-        false
-    }
-
     /// Internal function tool used in `self.wait_loop_event()`, check in the
     /// first place the content of `input_data` set by the controller.
     ///
@@ -414,14 +375,11 @@ impl ExecutionThread {
     ///
     /// # Test case
     /// With the test configuration, the behavior is slightly different and we prefer
-    /// to execute all `readonly_requests`, `new_blockclique`, and
-    /// `finalized_blocks` before he thread join
+    /// to execute all `new_blockclique` and `finalized_blocks` before he thread join
     fn check_input_data(&self, input_data: &ExecutionInputData) -> Option<bool> {
         #[cfg(test)]
         {
-            if (!input_data.readonly_requests.is_empty()
-                || input_data.new_blockclique.is_some()
-                || !input_data.finalized_blocks.is_empty())
+            if (input_data.new_blockclique.is_some() || !input_data.finalized_blocks.is_empty())
                 && !input_data.stop
             {
                 return Some(false);
@@ -435,12 +393,9 @@ impl ExecutionThread {
             if input_data.stop {
                 return Some(true);
             }
-            // Check for readonly requests, new blockclique or final slot changes
+            // Check for new blockclique or final slot changes
             // The most frequent triggers are checked first.
-            if !input_data.readonly_requests.is_empty()
-                || input_data.new_blockclique.is_some()
-                || !input_data.finalized_blocks.is_empty()
-            {
+            if input_data.new_blockclique.is_some() || !input_data.finalized_blocks.is_empty() {
                 return Some(false);
             }
         }
@@ -453,18 +408,18 @@ impl ExecutionThread {
     /// `Some(ExecutionInputData)` representing the input requests,
     /// or `None` if the main loop needs to stop.
     fn wait_loop_event(&mut self) -> Option<ExecutionInputData> {
-        let mut cancel_input = loop {
+        loop {
             let mut input_data_lock = self.input_data.1.lock();
 
             // take current input data, resetting it
             let input_data: ExecutionInputData = input_data_lock.take();
 
             // check for stop signal (in testing mode we wait for all
-            // Check for readonly requests, new blockclique or final slot changes
+            // Check for new blockclique or final slot changes
             // The most frequent triggers are checked first.
             if let Some(should_break) = self.check_input_data(&input_data) {
                 if should_break {
-                    break input_data;
+                    return None;
                 } else {
                     return Some(input_data);
                 }
@@ -473,8 +428,7 @@ impl ExecutionThread {
             // Check for slots to execute.
             // The most frequent triggers are checked first,
             // except for the active slot check which is last because it is more expensive.
-            if !self.readonly_requests.is_empty()
-                || !self.ready_final_slots.is_empty()
+            if !self.ready_final_slots.is_empty()
                 || self.are_there_active_slots_ready_for_execution()
             {
                 return Some(input_data);
@@ -496,23 +450,7 @@ impl ExecutionThread {
                 .input_data
                 .0
                 .wait_for(&mut input_data_lock, time_until_next_slot.to_duration());
-        };
-
-        // The loop needs to quit
-
-        // Cancel pending readonly requests
-        let cancel_err = ExecutionError::ChannelError(
-            "readonly execution cancelled because the execution worker is closing".into(),
-        );
-        cancel_input.readonly_requests.cancel(cancel_err.clone());
-        self.input_data
-            .1
-            .lock()
-            .take()
-            .readonly_requests
-            .cancel(cancel_err);
-
-        None
+        }
     }
 
     /// Main loop of the execution worker
@@ -521,7 +459,7 @@ impl ExecutionThread {
         // It also prioritizes executions in the following order:
         // 1 - final executions
         // 2 - speculative executions
-        // 3 - read-only executions
+        // Read-only executions happen in parallel in the read-only executor pool.
         while let Some(input_data) = self.wait_loop_event() {
             // update the sequence of final slots given the newly finalized blocks
             self.update_final_slots(input_data.finalized_blocks);
@@ -535,9 +473,6 @@ impl ExecutionThread {
             // so that it can remove out-of-date speculative execution results from its history.
             self.truncate_execution_history();
 
-            // update the sequence of read-only requests
-            self.update_readonly_requests(input_data.readonly_requests);
-
             // execute one slot as final, if there is one ready for final execution
             if self.execute_one_final_slot() {
                 // A slot was executed as final: restart the loop
@@ -553,16 +488,6 @@ impl ExecutionThread {
                 // An active slot was executed: restart the loop
                 continue;
             }
-
-            // now all the slots that were ready for final and active execution have been executed
-
-            // Execute a read-only request (note that the queue is of finite length), if there is one ready.
-            // This must be done in this loop because even though read-only executions do not alter consensus state,
-            // they still act temporarily on the static shared execution context.
-            if self.execute_one_readonly_request() {
-                // a read-only request was executed: restart the loop
-                continue;
-            }
         }
     }
 }
@@ -586,19 +511,22 @@ pub fn start_execution_worker(
     // create an execution state
     let execution_state = Arc::new(RwLock::new(ExecutionState::new(
         config.clone(),
-        final_state,
+        final_state.clone(),
         storage,
     )));
 
+    // launch the read-only executors
+    let readonly_executors =
+        ReadOnlyExecutorPool::new(config.clone(), execution_state.clone(), final_state);
+
     // define the input data interface
-    let input_data = Arc::new((
-        Condvar::new(),
-        Mutex::new(ExecutionInputData::new(config.clone())),
-    ));
+    let input_data = Arc::new((Condvar::new(), Mutex::new(ExecutionInputData::new())));
 
     // create a controller
     let controller = ExecutionControllerImpl {
         input_data: input_data.clone(),
+        readonly_input_data: readonly_executors.get_input_data(),
+        readonly_timeout: config.readonly_timeout,
         execution_state: execution_state.clone(),
     };
 
@@ -612,6 +540,7 @@ pub fn start_execution_worker(
     let manager = ExecutionManagerImpl {
         input_data,
         thread_handle: Some(thread_handle),
+        readonly_executors,
     };
 
     // return the execution manager and controller pair
@@ -6,10 +6,14 @@
 //! and need to be bootstrapped by nodes joining the network.
 
 use crate::{config::FinalStateConfig, error::FinalStateError, state_changes::StateChanges};
-use massa_async_pool::{AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
-use massa_ledger::{FinalLedger, LedgerChanges};
+use massa_async_pool::{AsyncMessage, AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
+use massa_ledger::{
+    FinalLedger, LedgerChanges, LedgerEntry, LedgerEntryUpdate, SetOrDelete, SetOrKeep,
+    SetUpdateOrDelete,
+};
 use massa_models::{constants::THREAD_COUNT, Address, Slot};
-use std::collections::VecDeque;
+use rocksdb::WriteBatch;
+use std::collections::{BTreeMap, VecDeque};
 
 /// Represents a final state `(ledger, async pool)`
 #[derive(Debug)]
@@ -25,24 +29,31 @@ pub struct FinalState {
     /// history of recent final state changes, useful for streaming bootstrap
     /// `front = oldest`, `back = newest`
     pub(crate) changes_history: VecDeque<(Slot, StateChanges)>,
+    /// history of the changes reverting recent final state changes, useful to view the state at a past final slot
+    /// `front = oldest`, `back = newest`
+    pub(crate) reverse_changes_history: VecDeque<(Slot, StateChanges)>,
 }
 
 impl FinalState {
-    /// Initializes a new `FinalState`
+    /// Initializes a new `FinalState`, reopening the one stored on disk if there is one
     ///
     /// # Arguments
     /// * `config`: the configuration of the execution state
     pub fn new(config: FinalStateConfig) -> Result<Self, FinalStateError> {
-        // attach at the output of the latest initial final slot, that is the last genesis slot
-        let slot = Slot::new(0, config.thread_count.saturating_sub(1));
-
-        // load the initial final ledger from file
-        let ledger = FinalLedger::new(config.ledger_config.clone()).map_err(|err| {
+        // open the final ledger, loading the initial one from file if it was never written to
+        let ledger = FinalLedger::new(config.ledger_config.clone(), AsyncPool::column_families())
+            .map_err(|err| {
             FinalStateError::LedgerError(format!("could not initialize ledger: {}", err))
         })?;
 
-        // create the async pool
-        let async_pool = AsyncPool::new(config.async_pool_config.clone());
+        // open the async pool, which is stored in the ledger database
+        let async_pool = AsyncPool::new(config.async_pool_config.clone(), ledger.get_db());
+
+        // attach at the output of the latest stored final slot,
+        // or of the latest initial final slot, that is the last genesis slot
+        let slot = ledger
+            .get_slot()
+            .unwrap_or_else(|| Slot::new(0, config.thread_count.saturating_sub(1)));
 
         // generate the final state
         Ok(FinalState {
@@ -51,9 +62,33 @@ impl FinalState {
             async_pool,
             config,
             changes_history: Default::default(), // no changes in history
+            reverse_changes_history: Default::default(),
         })
     }
 
+    /// Deletes the content of the final state stored on disk and its history,
+    /// before receiving a new one from bootstrap
+    pub fn reset(&mut self) {
+        self.ledger.reset();
+        self.async_pool.reset();
+        self.slot = Slot::new(0, self.config.thread_count.saturating_sub(1));
+        self.changes_history.clear();
+        self.reverse_changes_history.clear();
+    }
+
+    /// Applies changes to the ledger and the asynchronous pool in a single write,
+    /// and attaches the state at the output of `slot` without checking slot consistency.
+    /// Used for bootstrap and by `finalize`.
+    pub fn apply_changes_unchecked(&mut self, changes: StateChanges, slot: Slot) {
+        let mut batch = WriteBatch::default();
+        self.ledger
+            .apply_changes_to_batch(changes.ledger_changes, slot, &mut batch);
+        self.async_pool
+            .apply_changes_to_batch(changes.async_pool_changes, &mut batch);
+        self.ledger.write_batch(batch);
+        self.slot = slot;
+    }
+
     /// Applies changes to the execution state at a given slot, and settles that slot forever.
     /// Once this is called, the state is attached at the output of the provided slot.
     ///
@@ -68,14 +103,11 @@ impl FinalState {
             panic!("attempting to apply execution state changes at slot {} while the current slot is {}", slot, self.slot);
         }
 
-        // update current slot
-        self.slot = slot;
+        // compute the changes reverting the new ones before applying them
+        let reverse_changes = self.get_reverse_changes(&changes);
 
-        // apply changes
-        self.ledger
-            .apply_changes(changes.ledger_changes.clone(), self.slot);
-        self.async_pool
-            .apply_changes_unchecked(changes.async_pool_changes.clone());
+        // apply changes and update current slot
+        self.apply_changes_unchecked(changes.clone(), slot);
 
         // push history element and limit history size
         if self.config.final_history_length > 0 {
@@ -83,7 +115,139 @@ impl FinalState {
                 self.changes_history.pop_front();
             }
             self.changes_history.push_back((slot, changes));
+            while self.reverse_changes_history.len() >= self.config.final_history_length {
+                self.reverse_changes_history.pop_front();
+            }
+            self.reverse_changes_history
+                .push_back((slot, reverse_changes));
+        }
+    }
+
+    /// Computes the changes that revert the given changes when applied after them on the current state
+    fn get_reverse_changes(&self, changes: &StateChanges) -> StateChanges {
+        // restore the previous value of everything the ledger changes touch
+        let mut ledger_changes = LedgerChanges::default();
+        for (addr, change) in changes.ledger_changes.0.iter() {
+            let reverse_change = if !self.ledger.entry_exists(addr) {
+                SetUpdateOrDelete::Delete
+            } else {
+                match change {
+                    // only keep the fields and datastore keys that the new entry changes
+                    SetUpdateOrDelete::Set(new_entry) => match self.ledger.get_full_entry(addr) {
+                        Some(entry) => {
+                            SetUpdateOrDelete::Update(get_reverse_entry_update(entry, new_entry))
+                        }
+                        None => SetUpdateOrDelete::Delete,
+                    },
+                    // the whole entry is removed and must be restored
+                    SetUpdateOrDelete::Delete => match self.ledger.get_full_entry(addr) {
+                        Some(entry) => SetUpdateOrDelete::Set(entry),
+                        None => SetUpdateOrDelete::Delete,
+                    },
+                    SetUpdateOrDelete::Update(update) => {
+                        SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                            parallel_balance: match update.parallel_balance {
+                                SetOrKeep::Set(_) => SetOrKeep::Set(
+                                    self.ledger.get_parallel_balance(addr).unwrap_or_default(),
+                                ),
+                                SetOrKeep::Keep => SetOrKeep::Keep,
+                            },
+                            bytecode: match update.bytecode {
+                                SetOrKeep::Set(_) => SetOrKeep::Set(
+                                    self.ledger.get_bytecode(addr).unwrap_or_default(),
+                                ),
+                                SetOrKeep::Keep => SetOrKeep::Keep,
+                            },
+                            datastore: update
+                                .datastore
+                                .keys()
+                                .map(|key| match self.ledger.get_data_entry(addr, key) {
+                                    Some(value) => (*key, SetOrDelete::Set(value)),
+                                    None => (*key, SetOrDelete::Delete),
+                                })
+                                .collect(),
+                            locked_storage_cost: match update.locked_storage_cost {
+                                SetOrKeep::Set(_) => SetOrKeep::Set(
+                                    self.ledger
+                                        .get_locked_storage_cost(addr)
+                                        .unwrap_or_default(),
+                                ),
+                                SetOrKeep::Keep => SetOrKeep::Keep,
+                            },
+                        })
+                    }
+                }
+            };
+            ledger_changes.0.insert(*addr, reverse_change);
+        }
+
+        // restore the previous messages in reverse order,
+        // keeping track of the messages changed by the previous changes of the list
+        let mut changed_messages: BTreeMap<AsyncMessageId, Option<AsyncMessage>> = BTreeMap::new();
+        let mut async_pool_changes = AsyncPoolChanges::default();
+        for change in changes.async_pool_changes.0.iter() {
+            let (id, new_message) = match change {
+                Change::Add(id, message) => (*id, Some(message.clone())),
+                Change::Delete(id) => (*id, None),
+            };
+            let previous_message = match changed_messages.get(&id) {
+                Some(message) => message.clone(),
+                None => self.async_pool.get_message(&id),
+            };
+            async_pool_changes.0.push(match previous_message {
+                Some(message) => Change::Add(id, message),
+                None => Change::Delete(id),
+            });
+            changed_messages.insert(id, new_message);
+        }
+        async_pool_changes.0.reverse();
+
+        StateChanges {
+            ledger_changes,
+            async_pool_changes,
+        }
+    }
+
+    /// Gets the changes to apply on top of the current final state
+    /// to view it as it was at the output of a past final slot.
+    ///
+    /// Error case: when `slot` is after the current final slot or too old for the retained history
+    pub fn get_changes_reverting_to_slot(
+        &self,
+        slot: Slot,
+    ) -> Result<StateChanges, FinalStateError> {
+        if slot > self.slot {
+            return Err(FinalStateError::HistoryError(format!(
+                "slot {} is after the latest final slot {}",
+                slot, self.slot
+            )));
         }
+        if slot == self.slot {
+            return Ok(StateChanges::default());
+        }
+        // the changes of every slot after `slot` must be reverted
+        let first_reverted_slot = slot
+            .get_next_slot(self.config.thread_count)
+            .map_err(|_| FinalStateError::HistoryError("slot overflow".into()))?;
+        if !matches!(
+            self.reverse_changes_history.front(),
+            Some((oldest_slot, _)) if *oldest_slot <= first_reverted_slot
+        ) {
+            return Err(FinalStateError::HistoryError(format!(
+                "slot {} is outside of the retained final history",
+                slot
+            )));
+        }
+        let mut changes = StateChanges::default();
+        for (_, reverse_changes) in self
+            .reverse_changes_history
+            .iter()
+            .rev()
+            .take_while(|(reverted_slot, _)| *reverted_slot > slot)
+        {
+            changes.apply(reverse_changes.clone());
+        }
+        Ok(changes)
     }
 
     /// Used for bootstrap
@@ -158,6 +322,46 @@ impl FinalState {
     }
 }
 
+/// Computes the update restoring `old_entry` over `new_entry`,
+/// keeping only the fields and datastore keys whose value differs between them
+fn get_reverse_entry_update(old_entry: LedgerEntry, new_entry: &LedgerEntry) -> LedgerEntryUpdate {
+    fn set_if_changed<T: PartialEq>(old_value: T, new_value: &T) -> SetOrKeep<T> {
+        if old_value == *new_value {
+            SetOrKeep::Keep
+        } else {
+            SetOrKeep::Set(old_value)
+        }
+    }
+
+    // keys added by the new entry are deleted, changed or removed keys are restored
+    let added_keys: Vec<_> = new_entry
+        .datastore
+        .keys()
+        .filter(|key| !old_entry.datastore.contains_key(key))
+        .copied()
+        .collect();
+    let datastore = added_keys
+        .into_iter()
+        .map(|key| (key, SetOrDelete::Delete))
+        .chain(
+            old_entry
+                .datastore
+                .into_iter()
+                .filter(|(key, value)| new_entry.datastore.get(key) != Some(value))
+                .map(|(key, value)| (key, SetOrDelete::Set(value))),
+        )
+        .collect();
+    LedgerEntryUpdate {
+        parallel_balance: set_if_changed(old_entry.parallel_balance, &new_entry.parallel_balance),
+        bytecode: set_if_changed(old_entry.bytecode, &new_entry.bytecode),
+        datastore,
+        locked_storage_cost: set_if_changed(
+            old_entry.locked_storage_cost,
+            &new_entry.locked_storage_cost,
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -165,8 +369,9 @@ mod tests {
 
     use crate::{FinalState, StateChanges};
     use massa_async_pool::test_exports::get_random_message;
-    use massa_ledger::SetUpdateOrDelete;
-    use massa_models::{Address, Slot};
+    use massa_hash::Hash;
+    use massa_ledger::{LedgerEntry, SetOrDelete, SetOrKeep, SetUpdateOrDelete};
+    use massa_models::{Address, Amount, Slot};
     use massa_signature::{derive_public_key, generate_random_private_key};
 
     fn get_random_address() -> Address {
@@ -209,7 +414,7 @@ mod tests {
             .insert(high_address, SetUpdateOrDelete::Delete);
         history_state_changes.push_front((Slot::new(2, 0), state_changes.clone()));
         history_state_changes.push_front((Slot::new(1, 0), state_changes));
-        let mut final_state: FinalState = Default::default();
+        let (mut final_state, _disk_ledger) = FinalState::sample();
         final_state.changes_history = history_state_changes;
         // Test slot filter
         let part = final_state
@@ -222,4 +427,122 @@ mod tests {
             .unwrap();
         assert_eq!(part.ledger_changes.0.len(), 1);
     }
+
+    #[test]
+    fn get_changes_reverting_to_slot() {
+        let address = get_random_address();
+        let (mut final_state, _disk_ledger) = FinalState::sample();
+        let thread_count = final_state.config.thread_count;
+        let genesis_slot = final_state.slot;
+        let first_slot = genesis_slot.get_next_slot(thread_count).unwrap();
+        let second_slot = first_slot.get_next_slot(thread_count).unwrap();
+        let mut changes = StateChanges::default();
+        changes
+            .ledger_changes
+            .set_parallel_balance(address, Amount::from_raw(10));
+        final_state.finalize(first_slot, changes.clone());
+        changes
+            .ledger_changes
+            .set_parallel_balance(address, Amount::from_raw(20));
+        final_state.finalize(second_slot, changes);
+
+        // nothing to revert at the latest final slot
+        let changes = final_state
+            .get_changes_reverting_to_slot(second_slot)
+            .unwrap();
+        assert!(changes.ledger_changes.0.is_empty());
+        // the balance is restored to its value at the first slot
+        let changes = final_state
+            .get_changes_reverting_to_slot(first_slot)
+            .unwrap();
+        assert_eq!(
+            changes
+                .ledger_changes
+                .get_parallel_balance_or_else(&address, || None),
+            Some(Amount::from_raw(10))
+        );
+        // the entry did not exist before the first slot
+        let changes = final_state
+            .get_changes_reverting_to_slot(genesis_slot)
+            .unwrap();
+        assert!(!changes
+            .ledger_changes
+            .entry_exists_or_else(&address, || true));
+        // future slots and slots outside of the retained history are rejected
+        assert!(final_state
+            .get_changes_reverting_to_slot(second_slot.get_next_slot(thread_count).unwrap())
+            .is_err());
+        assert!(final_state
+            .get_changes_reverting_to_slot(Slot::new(0, 0))
+            .is_err());
+    }
+
+    #[test]
+    fn get_reverse_changes_of_set_entry() {
+        let address = get_random_address();
+        let (mut final_state, _disk_ledger) = FinalState::sample();
+        let thread_count = final_state.config.thread_count;
+        let first_slot = final_state.slot.get_next_slot(thread_count).unwrap();
+        let second_slot = first_slot.get_next_slot(thread_count).unwrap();
+        let (kept_key, changed_key, removed_key, added_key) = (
+            Hash::compute_from(b"kept"),
+            Hash::compute_from(b"changed"),
+            Hash::compute_from(b"removed"),
+            Hash::compute_from(b"added"),
+        );
+        let entry = |balance: u64, datastore: Vec<(Hash, u8)>| LedgerEntry {
+            parallel_balance: Amount::from_raw(balance),
+            bytecode: vec![1, 2, 3],
+            datastore: datastore
+                .into_iter()
+                .map(|(key, value)| (key, vec![value; 100]))
+                .collect(),
+            locked_storage_cost: Default::default(),
+        };
+        let mut changes = StateChanges::default();
+        changes.ledger_changes.0.insert(
+            address,
+            SetUpdateOrDelete::Set(entry(
+                10,
+                vec![(kept_key, 0), (changed_key, 1), (removed_key, 2)],
+            )),
+        );
+        final_state.finalize(first_slot, changes);
+        let mut changes = StateChanges::default();
+        changes.ledger_changes.0.insert(
+            address,
+            SetUpdateOrDelete::Set(entry(
+                20,
+                vec![(kept_key, 0), (changed_key, 3), (added_key, 4)],
+            )),
+        );
+        final_state.finalize(second_slot, changes);
+
+        // overwriting an entry only records the values it changes
+        let changes = final_state
+            .get_changes_reverting_to_slot(first_slot)
+            .unwrap();
+        let update = match changes.ledger_changes.0.get(&address) {
+            Some(SetUpdateOrDelete::Update(update)) => update,
+            _ => panic!("overwriting an entry should be reverted by an update"),
+        };
+        assert!(matches!(
+            update.parallel_balance,
+            SetOrKeep::Set(balance) if balance == Amount::from_raw(10)
+        ));
+        assert!(matches!(update.bytecode, SetOrKeep::Keep));
+        assert_eq!(update.datastore.len(), 3);
+        assert!(matches!(
+            update.datastore.get(&changed_key),
+            Some(SetOrDelete::Set(value)) if *value == vec![1; 100]
+        ));
+        assert!(matches!(
+            update.datastore.get(&removed_key),
+            Some(SetOrDelete::Set(value)) if *value == vec![2; 100]
+        ));
+        assert!(matches!(
+            update.datastore.get(&added_key),
+            Some(SetOrDelete::Delete)
+        ));
+    }
 }
@@ -24,6 +24,7 @@ pub fn create_final_state(
         ledger,
         async_pool,
         changes_history,
+        reverse_changes_history: Default::default(),
     }
 }
 
@@ -3,30 +3,43 @@
 //! This file defines testing tools related to the configuration
 
 use crate::{FinalState, FinalStateConfig};
-use massa_async_pool::{AsyncPool, AsyncPoolConfig};
-use massa_ledger::{FinalLedger, LedgerConfig};
+use massa_async_pool::{test_exports::create_async_pool, AsyncPool, AsyncPoolConfig};
+use massa_ledger::{test_exports::create_final_ledger, LedgerConfig};
 use massa_models::Slot;
+use tempfile::TempDir;
 
-/// Default value of `FinalState` used for tests
-impl Default for FinalState {
-    fn default() -> Self {
+impl FinalState {
+    /// get an empty final state along with its disk directory
+    pub fn sample() -> (Self, TempDir) {
         let config = FinalStateConfig::default();
         let slot = Slot::new(0, config.thread_count.saturating_sub(1));
 
-        // load the initial final ledger from file
-        let ledger = FinalLedger::default();
+        // create an empty final ledger
+        let (ledger, disk_ledger) = create_final_ledger(
+            None,
+            config.ledger_config.clone(),
+            AsyncPool::column_families(),
+        );
 
-        // create the async pool
-        let async_pool = AsyncPool::new(config.async_pool_config.clone());
+        // create the async pool in the ledger database
+        let async_pool = create_async_pool(
+            config.async_pool_config.clone(),
+            ledger.get_db(),
+            Default::default(),
+        );
 
         // generate the final state
-        FinalState {
-            slot,
-            ledger,
-            async_pool,
-            config,
-            changes_history: Default::default(), // no changes in history
-        }
+        (
+            FinalState {
+                slot,
+                ledger,
+                async_pool,
+                config,
+                changes_history: Default::default(), // no changes in history
+                reverse_changes_history: Default::default(),
+            },
+            disk_ledger,
+        )
     }
 }
 
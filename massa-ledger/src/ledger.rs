@@ -10,7 +10,9 @@ use massa_hash::Hash;
 use massa_models::{Address, Amount, ModelsError};
 use massa_models::{DeserializeCompact, Slot};
 use nom::AsBytes;
+use rocksdb::{ColumnFamilyDescriptor, WriteBatch, DB};
 use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 /// Represents a final ledger associating addresses to their balances, bytecode and data.
 /// The final ledger is part of the final state which is attached to a final slot, can be bootstrapped and allows others to bootstrap.
@@ -41,8 +43,15 @@ macro_rules! init_file_error {
 pub(crate) use init_file_error;
 
 impl FinalLedger {
-    /// Initializes a new `FinalLedger` by reading its initial state from file.
-    pub fn new(config: LedgerConfig) -> Result<Self, LedgerError> {
+    /// Opens the `FinalLedger` stored on disk, initializing it from the initial ledger file if it was never written to.
+    ///
+    /// # Arguments
+    /// * `config`: ledger configuration
+    /// * `extra_column_families`: column families of the other components of the final state stored in the ledger database
+    pub fn new(
+        config: LedgerConfig,
+        extra_column_families: Vec<ColumnFamilyDescriptor>,
+    ) -> Result<Self, LedgerError> {
         // load the ledger tree from file
         let initial_ledger: HashMap<Address, LedgerEntry> =
             serde_json::from_str::<HashMap<Address, Amount>>(
@@ -62,9 +71,12 @@ impl FinalLedger {
             })
             .collect();
 
-        // create and initialize the disk ledger
-        let mut sorted_ledger = LedgerDB::new(config.disk_ledger_path.clone());
-        sorted_ledger.set_initial_ledger(initial_ledger);
+        // open the disk ledger, and initialize it if no changes were ever applied to it
+        let mut sorted_ledger =
+            LedgerDB::new(config.disk_ledger_path.clone(), extra_column_families);
+        if sorted_ledger.get_slot().is_none() {
+            sorted_ledger.set_initial_ledger(initial_ledger);
+        }
 
         // generate the final ledger
         Ok(FinalLedger {
@@ -78,6 +90,40 @@ impl FinalLedger {
         self.sorted_ledger.apply_changes(changes, slot);
     }
 
+    /// Adds the operations applying `LedgerChanges` to the final ledger to a batch,
+    /// to be written along with the changes of the other components stored in the ledger database
+    pub fn apply_changes_to_batch(
+        &mut self,
+        changes: LedgerChanges,
+        slot: Slot,
+        batch: &mut WriteBatch,
+    ) {
+        self.sorted_ledger
+            .apply_changes_to_batch(changes, slot, batch);
+    }
+
+    /// Writes a batch of operations to the ledger database
+    pub fn write_batch(&self, batch: WriteBatch) {
+        self.sorted_ledger.write_batch(batch);
+    }
+
+    /// Gets a shared handle to the ledger database,
+    /// in which the other components of the final state are stored
+    pub fn get_db(&self) -> Arc<DB> {
+        self.sorted_ledger.get_db()
+    }
+
+    /// Gets the slot at the output of which the final ledger is attached,
+    /// or None if no changes were ever applied to it
+    pub fn get_slot(&self) -> Option<Slot> {
+        self.sorted_ledger.get_slot()
+    }
+
+    /// Deletes every entry of the final ledger, along with its attached slot
+    pub fn reset(&mut self) {
+        self.sorted_ledger.reset();
+    }
+
     /// Gets the parallel balance of a ledger entry
     ///
     /// # Returns
@@ -101,6 +147,27 @@ impl FinalLedger {
             .get_sub_entry(addr, LedgerSubEntry::Bytecode)
     }
 
+    /// Gets the coins locked for the datastore storage of a ledger entry.
+    /// Entries stored before storage costs were locked have none.
+    ///
+    /// # Returns
+    /// The locked coins, or None if the ledger entry was not found
+    pub fn get_locked_storage_cost(&self, addr: &Address) -> Option<Amount> {
+        if !self.entry_exists(addr) {
+            return None;
+        }
+        Some(
+            self.sorted_ledger
+                .get_sub_entry(addr, LedgerSubEntry::LockedStorageCost)
+                .map(|bytes| {
+                    Amount::from_bytes_compact(&bytes)
+                        .expect("critical: invalid locked storage cost format")
+                        .0
+                })
+                .unwrap_or_default(),
+        )
+    }
+
     /// Checks if a ledger entry exists
     ///
     /// # Returns
@@ -151,6 +218,7 @@ impl FinalLedger {
                 parallel_balance,
                 bytecode: self.get_bytecode(addr).unwrap_or_default(),
                 datastore: self.get_entire_datastore(addr),
+                locked_storage_cost: self.get_locked_storage_cost(addr).unwrap_or_default(),
             })
     }
 
@@ -29,6 +29,8 @@ pub struct LedgerEntryUpdate {
     pub bytecode: SetOrKeep<Vec<u8>>,
     /// change datastore entries
     pub datastore: Map<Hash, SetOrDelete<Vec<u8>>>,
+    /// change the coins locked for datastore storage
+    pub locked_storage_cost: SetOrKeep<Amount>,
 }
 
 struct DatastoreSerializer {
@@ -111,6 +113,7 @@ struct LedgerEntryUpdateSerializer {
     parallel_balance_serializer: SetOrKeepSerializer<Amount, AmountSerializer>,
     bytecode_serializer: SetOrKeepSerializer<Vec<u8>, VecU8Serializer>,
     datastore_serializer: DatastoreSerializer,
+    locked_storage_cost_serializer: SetOrKeepSerializer<Amount, AmountSerializer>,
 }
 
 impl LedgerEntryUpdateSerializer {
@@ -125,6 +128,10 @@ impl LedgerEntryUpdateSerializer {
                 Included(u64::MAX),
             )),
             datastore_serializer: DatastoreSerializer::new(),
+            locked_storage_cost_serializer: SetOrKeepSerializer::new(AmountSerializer::new(
+                Included(u64::MIN),
+                Included(u64::MAX),
+            )),
         }
     }
 }
@@ -138,6 +145,10 @@ impl Serializer<LedgerEntryUpdate> for LedgerEntryUpdateSerializer {
         );
         res.extend(self.bytecode_serializer.serialize(&value.bytecode)?);
         res.extend(self.datastore_serializer.serialize(&value.datastore)?);
+        res.extend(
+            self.locked_storage_cost_serializer
+                .serialize(&value.locked_storage_cost)?,
+        );
         Ok(res)
     }
 }
@@ -146,6 +157,7 @@ struct LedgerEntryUpdateDeserializer {
     parallel_balance_deserializer: SetOrKeepDeserializer<Amount, AmountDeserializer>,
     bytecode_deserializer: SetOrKeepDeserializer<Vec<u8>, VecU8Deserializer>,
     datastore_deserializer: DatastoreDeserializer,
+    locked_storage_cost_deserializer: SetOrKeepDeserializer<Amount, AmountDeserializer>,
 }
 
 impl LedgerEntryUpdateDeserializer {
@@ -160,6 +172,10 @@ impl LedgerEntryUpdateDeserializer {
                 Included(u64::MAX),
             )),
             datastore_deserializer: DatastoreDeserializer::new(),
+            locked_storage_cost_deserializer: SetOrKeepDeserializer::new(AmountDeserializer::new(
+                Included(u64::MIN),
+                Included(u64::MAX),
+            )),
         }
     }
 }
@@ -170,14 +186,16 @@ impl Deserializer<LedgerEntryUpdate> for LedgerEntryUpdateDeserializer {
             |input| self.parallel_balance_deserializer.deserialize(input),
             |input| self.bytecode_deserializer.deserialize(input),
             |input| self.datastore_deserializer.deserialize(input),
+            |input| self.locked_storage_cost_deserializer.deserialize(input),
         ));
-        let (rest, (parallel_balance, bytecode, datastore)) = parser(buffer)?;
+        let (rest, (parallel_balance, bytecode, datastore, locked_storage_cost)) = parser(buffer)?;
         Ok((
             rest,
             LedgerEntryUpdate {
                 parallel_balance,
                 bytecode,
                 datastore,
+                locked_storage_cost,
             },
         ))
     }
@@ -189,6 +207,7 @@ impl Applicable<LedgerEntryUpdate> for LedgerEntryUpdate {
         self.parallel_balance.apply(update.parallel_balance);
         self.bytecode.apply(update.bytecode);
         self.datastore.extend(update.datastore);
+        self.locked_storage_cost.apply(update.locked_storage_cost);
     }
 }
 
@@ -360,6 +379,36 @@ impl LedgerChanges {
         }
     }
 
+    /// Tries to return the coins locked for the datastore storage of an entry
+    /// or gets them from a function if the entry's status is unknown.
+    ///
+    /// # Arguments
+    /// * `addr`: address for which to get the value
+    /// * `f`: fallback function with no arguments and returning `Option<Amount>`
+    ///
+    /// # Returns
+    /// * Some(v) if a value is present, where v is a copy of the value
+    /// * None if the entry is absent
+    /// * f() if the value is unknown
+    pub fn get_locked_storage_cost_or_else<F: FnOnce() -> Option<Amount>>(
+        &self,
+        addr: &Address,
+        f: F,
+    ) -> Option<Amount> {
+        match self.0.get(addr) {
+            Some(SetUpdateOrDelete::Set(v)) => Some(v.locked_storage_cost),
+            Some(SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                locked_storage_cost,
+                ..
+            })) => match locked_storage_cost {
+                SetOrKeep::Set(v) => Some(*v),
+                SetOrKeep::Keep => f(),
+            },
+            Some(SetUpdateOrDelete::Delete) => None,
+            None => f(),
+        }
+    }
+
     /// Tries to return the executable bytecode of an entry
     /// or gets it from a function if the entry's status is unknown.
     ///
@@ -488,6 +537,35 @@ impl LedgerChanges {
         }
     }
 
+    /// Set the coins locked for the datastore storage of an address.
+    /// If the address doesn't exist, its ledger entry is created.
+    ///
+    /// # Arguments
+    /// * `addr`: target address
+    /// * `locked_storage_cost`: locked coins to set for the provided address
+    pub fn set_locked_storage_cost(&mut self, addr: Address, locked_storage_cost: Amount) {
+        match self.0.entry(addr) {
+            hash_map::Entry::Occupied(mut occ) => match occ.get_mut() {
+                SetUpdateOrDelete::Set(v) => v.locked_storage_cost = locked_storage_cost,
+                SetUpdateOrDelete::Update(u) => {
+                    u.locked_storage_cost = SetOrKeep::Set(locked_storage_cost)
+                }
+                d @ SetUpdateOrDelete::Delete => {
+                    *d = SetUpdateOrDelete::Set(LedgerEntry {
+                        locked_storage_cost,
+                        ..Default::default()
+                    });
+                }
+            },
+            hash_map::Entry::Vacant(vac) => {
+                vac.insert(SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                    locked_storage_cost: SetOrKeep::Set(locked_storage_cost),
+                    ..Default::default()
+                }));
+            }
+        }
+    }
+
     /// Set the executable bytecode of an address.
     /// If the address doesn't exist, its ledger entry is created.
     ///
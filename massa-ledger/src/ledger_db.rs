@@ -6,7 +6,8 @@ use massa_hash::{Hash, HashDeserializer, HASH_SIZE_BYTES};
 use massa_models::address::AddressDeserializer;
 use massa_models::constants::LEDGER_PART_SIZE_MESSAGE_BYTES;
 use massa_models::{
-    Address, ModelsError, SerializeCompact, Slot, VecU8Deserializer, VecU8Serializer,
+    Address, DeserializeCompact, ModelsError, SerializeCompact, Slot, VecU8Deserializer,
+    VecU8Serializer,
 };
 use massa_serialization::{Deserializer, Serializer};
 use nom::multi::many0;
@@ -17,13 +18,14 @@ use rocksdb::{
 use std::collections::HashMap;
 use std::ops::Bound;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::{collections::BTreeMap, path::PathBuf};
 
 use crate::ledger_changes::LedgerEntryUpdate;
 use crate::{LedgerChanges, LedgerEntry, SetOrDelete, SetOrKeep, SetUpdateOrDelete};
 
 #[cfg(feature = "testing")]
-use massa_models::{Amount, DeserializeCompact};
+use massa_models::Amount;
 
 // TODO: remove rocks_db dir when sled is cut out
 const LEDGER_CF: &str = "ledger";
@@ -34,6 +36,7 @@ const CF_ERROR: &str = "critical: rocksdb column family operation failed";
 const BALANCE_IDENT: u8 = 0u8;
 const BYTECODE_IDENT: u8 = 1u8;
 const DATASTORE_IDENT: u8 = 2u8;
+const LOCKED_STORAGE_COST_IDENT: u8 = 3u8;
 const SLOT_KEY: &[u8; 1] = b"s";
 
 /// Ledger sub entry enum
@@ -44,13 +47,15 @@ pub enum LedgerSubEntry {
     Bytecode,
     /// Datastore entry
     Datastore(Hash),
+    /// Coins locked for datastore storage
+    LockedStorageCost,
 }
 
 /// Disk ledger DB module
 ///
-/// Contains a RocksDB DB instance
+/// Contains a RocksDB DB instance, shared with the other components of the final state stored in it
 #[derive(Debug)]
-pub(crate) struct LedgerDB(DB);
+pub(crate) struct LedgerDB(Arc<DB>);
 
 /// Balance key formatting macro
 macro_rules! balance_key {
@@ -68,6 +73,13 @@ macro_rules! bytecode_key {
     };
 }
 
+/// Locked storage cost key formatting macro
+macro_rules! locked_storage_cost_key {
+    ($addr:expr) => {
+        [&$addr.to_bytes()[..], &[LOCKED_STORAGE_COST_IDENT]].concat()
+    };
+}
+
 /// Datastore entry key formatting macro
 ///
 /// TODO: add a separator identifier if the need comes to have multiple datastores
@@ -138,6 +150,7 @@ impl Deserializer<Vec<u8>> for KeyDeserializer {
             Some(ident) => match *ident {
                 BALANCE_IDENT => Ok((&rest[1..], balance_key!(address))),
                 BYTECODE_IDENT => Ok((&rest[1..], bytecode_key!(address))),
+                LOCKED_STORAGE_COST_IDENT => Ok((&rest[1..], locked_storage_cost_key!(address))),
                 DATASTORE_IDENT => {
                     let (rest, hash) = self.hash_deserializer.deserialize(&rest[1..])?;
                     Ok((rest, data_key!(address, hash)))
@@ -172,28 +185,44 @@ fn test_end_prefix() {
     assert_eq!(end_prefix(&[5, 6, 255]), Some(vec![5, 7]));
 }
 
-// TODO: save attached slot in metadata for a lighter bootstrap after disconnection
 impl LedgerDB {
-    /// Create and initialize a new LedgerDB.
+    /// Open the LedgerDB stored at the given path, creating it if there is none.
     ///
     /// # Arguments
     /// * path: path to the desired disk ledger db directory
-    pub fn new(path: PathBuf) -> Self {
+    /// * extra_column_families: column families of the other components stored in the same database
+    pub fn new(path: PathBuf, extra_column_families: Vec<ColumnFamilyDescriptor>) -> Self {
         let mut db_opts = Options::default();
         db_opts.create_if_missing(true);
         db_opts.create_missing_column_families(true);
 
-        let db = DB::open_cf_descriptors(
-            &db_opts,
-            path,
-            vec![
-                ColumnFamilyDescriptor::new(LEDGER_CF, Options::default()),
-                ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
-            ],
-        )
-        .expect(OPEN_ERROR);
-
-        LedgerDB(db)
+        let mut column_families = vec![
+            ColumnFamilyDescriptor::new(LEDGER_CF, Options::default()),
+            ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+        ];
+        column_families.extend(extra_column_families);
+        let db = DB::open_cf_descriptors(&db_opts, path, column_families).expect(OPEN_ERROR);
+
+        LedgerDB(Arc::new(db))
+    }
+
+    /// Get a shared handle to the underlying database
+    pub fn get_db(&self) -> Arc<DB> {
+        self.0.clone()
+    }
+
+    /// Get the slot attached to the disk ledger,
+    /// or None if no changes were ever applied to it
+    pub fn get_slot(&self) -> Option<Slot> {
+        let handle = self.0.cf_handle(METADATA_CF).expect(CF_ERROR);
+        self.0
+            .get_cf(handle, SLOT_KEY)
+            .expect(CRUD_ERROR)
+            .map(|bytes| {
+                Slot::from_bytes_compact(&bytes)
+                    .expect("critical: invalid slot format")
+                    .0
+            })
     }
 
     /// Set the initial disk ledger
@@ -216,40 +245,68 @@ impl LedgerDB {
     pub fn apply_changes(&mut self, changes: LedgerChanges, slot: Slot) {
         // create the batch
         let mut batch = WriteBatch::default();
+        // fill the batch
+        self.apply_changes_to_batch(changes, slot, &mut batch);
+        // write the batch
+        self.write_batch(batch);
+    }
+
+    /// Add the operations applying `LedgerChanges` to the disk ledger to a batch
+    ///
+    /// # Arguments
+    /// * changes: ledger changes to be applied
+    /// * slot: new slot associated to the final ledger
+    /// * batch: the given operation batch to update
+    pub fn apply_changes_to_batch(
+        &mut self,
+        changes: LedgerChanges,
+        slot: Slot,
+        batch: &mut WriteBatch,
+    ) {
         // for all incoming changes
         for (addr, change) in changes.0 {
             match change {
                 // the incoming change sets a ledger entry to a new one
                 SetUpdateOrDelete::Set(new_entry) => {
                     // inserts/overwrites the entry with the incoming one
-                    self.put_entry(&addr, new_entry, &mut batch);
+                    self.put_entry(&addr, new_entry, batch);
                 }
                 // the incoming change updates an existing ledger entry
                 SetUpdateOrDelete::Update(entry_update) => {
                     // applies the updates to the entry
                     // if the entry does not exist, inserts a default one and applies the updates to it
-                    self.update_entry(&addr, entry_update, &mut batch);
+                    self.update_entry(&addr, entry_update, batch);
                 }
                 // the incoming change deletes a ledger entry
                 SetUpdateOrDelete::Delete => {
                     // delete the entry, if it exists
-                    self.delete_entry(&addr, &mut batch);
+                    self.delete_entry(&addr, batch);
                 }
             }
         }
         // set the associated slot in metadata
-        self.set_metadata(slot, &mut batch);
-        // write the batch
-        self.write_batch(batch);
+        self.set_metadata(slot, batch);
     }
 
     /// Apply the given operation batch to the disk ledger.
     ///
     /// NOTE: the batch is not saved within the object because it cannot be shared between threads safely
-    fn write_batch(&self, batch: WriteBatch) {
+    pub fn write_batch(&self, batch: WriteBatch) {
         self.0.write(batch).expect(CRUD_ERROR);
     }
 
+    /// Delete every entry and the metadata of the disk ledger
+    pub fn reset(&mut self) {
+        let mut batch = WriteBatch::default();
+        for cf in [LEDGER_CF, METADATA_CF] {
+            let handle = self.0.cf_handle(cf).expect(CF_ERROR);
+            for (key, _) in self.0.iterator_cf(handle, IteratorMode::Start) {
+                batch.delete_cf(handle, key);
+            }
+        }
+        self.write_batch(batch);
+    }
+
     /// Set the disk ledger metadata
     ///
     /// # Arguments
@@ -288,6 +345,14 @@ impl LedgerDB {
         for (hash, entry) in ledger_entry.datastore {
             batch.put_cf(handle, data_key!(addr, hash), entry);
         }
+
+        // locked storage cost
+        batch.put_cf(
+            handle,
+            locked_storage_cost_key!(addr),
+            // Amount::to_bytes_compact() never fails
+            ledger_entry.locked_storage_cost.to_bytes_compact().unwrap(),
+        );
     }
 
     /// Get the given sub-entry of a given address.
@@ -311,6 +376,10 @@ impl LedgerDB {
                 .0
                 .get_cf(handle, data_key!(addr, hash))
                 .expect(CRUD_ERROR),
+            LedgerSubEntry::LockedStorageCost => self
+                .0
+                .get_cf(handle, locked_storage_cost_key!(addr))
+                .expect(CRUD_ERROR),
         }
     }
 
@@ -399,6 +468,16 @@ impl LedgerDB {
                 SetOrDelete::Delete => batch.delete_cf(handle, data_key!(addr, hash)),
             }
         }
+
+        // locked storage cost
+        if let SetOrKeep::Set(locked_storage_cost) = entry_update.locked_storage_cost {
+            batch.put_cf(
+                handle,
+                locked_storage_cost_key!(addr),
+                // Amount::to_bytes_compact() never fails
+                locked_storage_cost.to_bytes_compact().unwrap(),
+            );
+        }
     }
 
     /// Delete every sub-entry associated to the given address.
@@ -414,6 +493,9 @@ impl LedgerDB {
         // bytecode
         batch.delete_cf(handle, balance_key!(addr));
 
+        // locked storage cost
+        batch.delete_cf(handle, locked_storage_cost_key!(addr));
+
         // datastore
         let mut opt = ReadOptions::default();
         opt.set_iterate_upper_bound(end_prefix(data_prefix!(addr)).unwrap());
@@ -550,7 +632,7 @@ mod tests {
 
         // write data
         let temp_dir = TempDir::new().unwrap();
-        let mut db = LedgerDB::new(temp_dir.path().to_path_buf());
+        let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), Vec::new());
         let mut batch = WriteBatch::default();
         db.put_entry(&addr, entry, &mut batch);
         db.update_entry(&addr, entry_update, &mut batch);
@@ -32,6 +32,11 @@ pub struct LedgerEntry {
 
     /// A key-value store associating a hash to arbitrary bytes
     pub datastore: BTreeMap<Hash, Vec<u8>>,
+
+    /// Coins locked from the parallel balance to pay for the storage of the datastore bytes.
+    /// Only this amount can be refunded when datastore bytes are removed.
+    #[serde(default)]
+    pub locked_storage_cost: Amount,
 }
 
 struct DatastoreSerializer {
@@ -120,6 +125,10 @@ impl Serializer<LedgerEntry> for LedgerEntrySerializer {
         res.extend(self.amount_serializer.serialize(&value.parallel_balance)?);
         res.extend(self.vec_u8_serializer.serialize(&value.bytecode)?);
         res.extend(self.datastore_serializer.serialize(&value.datastore)?);
+        res.extend(
+            self.amount_serializer
+                .serialize(&value.locked_storage_cost)?,
+        );
         Ok(res)
     }
 }
@@ -146,14 +155,16 @@ impl Deserializer<LedgerEntry> for LedgerEntryDeserializer {
             |input| self.amount_deserializer.deserialize(input),
             |input| self.vec_u8_deserializer.deserialize(input),
             |input| self.datastore_deserializer.deserialize(input),
+            |input| self.amount_deserializer.deserialize(input),
         ));
-        let (rest, (parallel_balance, bytecode, datastore)) = parser(buffer)?;
+        let (rest, (parallel_balance, bytecode, datastore, locked_storage_cost)) = parser(buffer)?;
         Ok((
             rest,
             LedgerEntry {
                 parallel_balance,
                 bytecode,
                 datastore,
+                locked_storage_cost,
             },
         ))
     }
@@ -168,6 +179,11 @@ impl Applicable<LedgerEntryUpdate> for LedgerEntry {
         // apply updates to the executable bytecode
         update.bytecode.apply_to(&mut self.bytecode);
 
+        // apply updates to the locked storage cost
+        update
+            .locked_storage_cost
+            .apply_to(&mut self.locked_storage_cost);
+
         // iterate over all datastore updates
         for (key, value_update) in update.datastore {
             match value_update {
@@ -226,6 +242,9 @@ impl SerializeCompact for LedgerEntry {
             res.extend(value);
         }
 
+        // locked storage cost
+        res.extend(self.locked_storage_cost.to_bytes_compact()?);
+
         Ok(res)
     }
 }
@@ -295,11 +314,16 @@ impl DeserializeCompact for LedgerEntry {
             datastore.insert(key, value);
         }
 
+        // locked storage cost
+        let (locked_storage_cost, delta) = Amount::from_bytes_compact(&buffer[cursor..])?;
+        cursor += delta;
+
         Ok((
             LedgerEntry {
                 parallel_balance,
                 bytecode,
                 datastore,
+                locked_storage_cost,
             },
             cursor,
         ))
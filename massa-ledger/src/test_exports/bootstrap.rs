@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::Address;
+use rocksdb::ColumnFamilyDescriptor;
 use std::collections::HashMap;
 use tempfile::TempDir;
 
@@ -8,17 +9,22 @@ use crate::{ledger_db::LedgerDB, FinalLedger, LedgerConfig, LedgerEntry};
 
 /// This file defines tools to test the ledger bootstrap
 
+/// Creates a `FinalLedger` from pre-set values, along with its disk directory
 pub fn create_final_ledger(
     initial_ledger: Option<HashMap<Address, LedgerEntry>>,
     config: LedgerConfig,
-) -> FinalLedger {
+    extra_column_families: Vec<ColumnFamilyDescriptor>,
+) -> (FinalLedger, TempDir) {
     let temp_dir = TempDir::new().unwrap();
-    let mut db = LedgerDB::new(temp_dir.path().to_path_buf());
+    let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), extra_column_families);
     db.set_initial_ledger(initial_ledger.unwrap_or_default());
-    FinalLedger {
-        _config: config,
-        sorted_ledger: db,
-    }
+    (
+        FinalLedger {
+            _config: config,
+            sorted_ledger: db,
+        },
+        temp_dir,
+    )
 }
 
 /// asserts that two ledger entries are the same
@@ -38,6 +44,10 @@ pub fn assert_eq_ledger_entry(v1: &LedgerEntry, v2: &LedgerEntry) {
         let itm2 = v2.datastore.get(k).expect("datastore key mismatch");
         assert_eq!(itm1, itm2, "datastore entry mismatch");
     }
+    assert_eq!(
+        v1.locked_storage_cost, v2.locked_storage_cost,
+        "locked storage cost mismatch"
+    );
 }
 
 /// asserts that two `FinalLedgerBootstrapState` are equal
@@ -54,6 +64,7 @@ pub fn assert_eq_ledger(v1: &FinalLedger, v2: &FinalLedger) {
                     parallel_balance: *balance,
                     bytecode: v1.get_bytecode(addr).unwrap_or_default(),
                     datastore: v1.get_entire_datastore(addr),
+                    locked_storage_cost: v1.get_locked_storage_cost(addr).unwrap_or_default(),
                 },
             )
         })
@@ -69,6 +80,7 @@ pub fn assert_eq_ledger(v1: &FinalLedger, v2: &FinalLedger) {
                     parallel_balance: *balance,
                     bytecode: v2.get_bytecode(addr).unwrap_or_default(),
                     datastore: v2.get_entire_datastore(addr),
+                    locked_storage_cost: v2.get_locked_storage_cost(addr).unwrap_or_default(),
                 },
             )
         })
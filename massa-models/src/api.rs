@@ -3,9 +3,10 @@
 use crate::address::AddressCycleProductionStats;
 use crate::ledger_models::LedgerData;
 use crate::node::NodeId;
+use crate::output_event::SCOutputEvent;
 use crate::prehash::Map;
 use crate::prehash::Set;
-use crate::stats::{ConsensusStats, NetworkStats, PoolStats};
+use crate::stats::{ConsensusStats, ExecutionStats, NetworkStats, PoolStats};
 use crate::SignedEndorsement;
 use crate::SignedOperation;
 use crate::{
@@ -14,11 +15,11 @@ use crate::{
 use massa_hash::Hash;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::net::IpAddr;
 
 /// node status
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NodeStatus {
     /// our node id
     pub node_id: NodeId,
@@ -42,6 +43,8 @@ pub struct NodeStatus {
     pub pool_stats: PoolStats,
     /// network stats
     pub network_stats: NetworkStats,
+    /// execution stats
+    pub execution_stats: ExecutionStats,
     /// compact configuration
     pub config: CompactConfig,
 }
@@ -74,6 +77,8 @@ impl std::fmt::Display for NodeStatus {
 
         writeln!(f, "{}", self.network_stats)?;
 
+        writeln!(f, "{}", self.execution_stats)?;
+
         writeln!(f, "Connected nodes:")?;
         for (node_id, (ip_addr, is_outgoing)) in &self.connected_nodes {
             writeln!(
@@ -89,7 +94,7 @@ impl std::fmt::Display for NodeStatus {
 }
 
 /// Operation and contextual info about it
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OperationInfo {
     /// id
     pub id: OperationId,
@@ -196,7 +201,7 @@ impl std::fmt::Display for SCELedgerInfo {
 }
 
 /// All you ever dream to know about an address
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AddressInfo {
     /// the address
     pub address: Address,
@@ -308,7 +313,7 @@ impl AddressInfo {
 }
 
 /// When an address is drawn to create an endorsement it is selected for a specific index
-#[derive(Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct IndexedSlot {
     /// slot
     pub slot: Slot,
@@ -385,7 +390,7 @@ impl std::fmt::Display for EndorsementInfo {
 }
 
 /// refactor to delete
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BlockInfo {
     /// block id
     pub id: BlockId,
@@ -394,7 +399,7 @@ pub struct BlockInfo {
 }
 
 /// Block content
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BlockInfoContent {
     /// true if final
     pub is_final: bool,
@@ -515,6 +520,153 @@ pub struct EventFilter {
     pub original_operation_id: Option<OperationId>,
 }
 
+impl EventFilter {
+    /// Check whether an event matches the filter
+    pub fn matches(&self, event: &SCOutputEvent) -> bool {
+        if let Some(start) = self.start {
+            if event.context.slot < start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end {
+            if event.context.slot >= end {
+                return false;
+            }
+        }
+        match (self.emitter_address, event.context.call_stack.front()) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_caller_address,
+            event.context.call_stack.back(),
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_operation_id,
+            event.context.origin_operation_id,
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        true
+    }
+}
+
+/// filter used when retrieving pending asynchronous messages
+#[derive(Default, Debug, Deserialize, Clone, Serialize)]
+pub struct AsyncMessageFilter {
+    /// optional sender address
+    pub sender: Option<Address>,
+    /// optional destination address
+    pub destination: Option<Address>,
+    /// optional start slot: messages whose validity ends at or before it are ignored
+    pub start: Option<Slot>,
+    /// optional end slot: messages whose validity starts at or after it are ignored
+    pub end: Option<Slot>,
+}
+
+impl AsyncMessageFilter {
+    /// Check whether an asynchronous message matches the filter
+    pub fn matches(&self, message: &AsyncMessageInfo) -> bool {
+        if let Some(sender) = self.sender {
+            if message.sender != sender {
+                return false;
+            }
+        }
+        if let Some(destination) = self.destination {
+            if message.destination != destination {
+                return false;
+            }
+        }
+        if let Some(start) = self.start {
+            if message.validity_end <= start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end {
+            if message.validity_start >= end {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// pending asynchronous message
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AsyncMessageInfo {
+    /// slot at which the message was emitted
+    pub emission_slot: Slot,
+    /// index of the message among those emitted at `emission_slot`
+    pub emission_index: u64,
+    /// sender address
+    pub sender: Address,
+    /// destination address
+    pub destination: Address,
+    /// name of the handler function in the destination bytecode
+    pub handler: String,
+    /// maximum gas for the handler execution
+    pub max_gas: u64,
+    /// gas price
+    pub gas_price: Amount,
+    /// coins sent to the destination
+    pub coins: Amount,
+    /// first slot of the validity range (included)
+    pub validity_start: Slot,
+    /// end of the validity range (excluded)
+    pub validity_end: Slot,
+    /// raw message data
+    pub data: Vec<u8>,
+    /// true if the message is in the final pool, false if it was only emitted by active executions
+    pub is_final: bool,
+}
+
+impl std::fmt::Display for AsyncMessageInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Message {} of slot {}{}:",
+            self.emission_index,
+            self.emission_slot,
+            if self.is_final { ", final" } else { "" }
+        )?;
+        writeln!(f, "	Sender: {}", self.sender)?;
+        writeln!(f, "	Destination: {}", self.destination)?;
+        writeln!(f, "	Handler: {}", self.handler)?;
+        writeln!(f, "	Max gas: {}", self.max_gas)?;
+        writeln!(f, "	Gas price: {}", self.gas_price)?;
+        writeln!(f, "	Coins: {}", self.coins)?;
+        writeln!(
+            f,
+            "	Validity: from {} to {}",
+            self.validity_start, self.validity_end
+        )?;
+        writeln!(f, "	Data size: {} bytes", self.data.len())
+    }
+}
+
+/// ledger state pretended for an address during a read-only execution
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct AddressStateOverride {
+    /// overridden address
+    pub address: Address,
+    /// parallel balance to pretend, optional
+    #[serde(default)]
+    pub balance: Option<Amount>,
+    /// bytecode to pretend, optional
+    #[serde(default)]
+    pub bytecode: Option<Vec<u8>>,
+    /// datastore entries to pretend, by hashed key
+    #[serde(default)]
+    pub datastore: BTreeMap<Hash, Vec<u8>>,
+}
+
 /// read only bytecode execution request
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct ReadOnlyBytecodeExecution {
@@ -526,6 +678,18 @@ pub struct ReadOnlyBytecodeExecution {
     pub bytecode: Vec<u8>,
     /// caller's address, optional
     pub address: Option<Address>,
+    /// true to get the trace of the execution
+    #[serde(default)]
+    pub trace: bool,
+    /// ledger state overrides applied during the execution only
+    #[serde(default)]
+    pub state_overrides: Vec<AddressStateOverride>,
+    /// slot at which to simulate the execution, optional
+    #[serde(default)]
+    pub simulated_slot: Option<Slot>,
+    /// recent final slot at the output of which to execute, optional
+    #[serde(default)]
+    pub final_slot: Option<Slot>,
 }
 
 /// read SC call request
@@ -543,4 +707,53 @@ pub struct ReadOnlyCall {
     pub parameter: String,
     /// caller's address, optional
     pub caller_address: Option<Address>,
+    /// true to get the trace of the execution
+    #[serde(default)]
+    pub trace: bool,
+    /// ledger state overrides applied during the execution only
+    #[serde(default)]
+    pub state_overrides: Vec<AddressStateOverride>,
+    /// slot at which to simulate the execution, optional
+    #[serde(default)]
+    pub simulated_slot: Option<Slot>,
+    /// recent final slot at the output of which to execute, optional
+    #[serde(default)]
+    pub final_slot: Option<Slot>,
+    /// coins transferred from the caller to the target address for the call
+    #[serde(default)]
+    pub coins: Amount,
+}
+
+/// JSON-RPC error codes of the errors returned by the node API
+pub mod error_codes {
+    /// pool error
+    pub const POOL_ERROR: i64 = -32001;
+    /// too many arguments
+    pub const TOO_MANY_ARGUMENTS: i64 = -32002;
+    /// send channel error
+    pub const SEND_CHANNEL_ERROR: i64 = -32003;
+    /// receive channel error
+    pub const RECEIVE_CHANNEL_ERROR: i64 = -32004;
+    /// `massa_hash` error
+    pub const MASSA_HASH_ERROR: i64 = -32005;
+    /// consensus error
+    pub const CONSENSUS_ERROR: i64 = -32006;
+    /// execution error
+    pub const EXECUTION_ERROR: i64 = -32007;
+    /// network error
+    pub const NETWORK_ERROR: i64 = -32008;
+    /// models error
+    pub const MODELS_ERROR: i64 = -32009;
+    /// time error
+    pub const TIME_ERROR: i64 = -32010;
+    /// not found
+    pub const NOT_FOUND: i64 = -32011;
+    /// inconsistency
+    pub const INCONSISTENCY_ERROR: i64 = -32012;
+    /// missing command sender
+    pub const MISSING_COMMAND_SENDER: i64 = -32013;
+    /// missing configuration
+    pub const MISSING_CONFIG: i64 = -32014;
+    /// the wrong API (either Public or Private) was called
+    pub const WRONG_API: i64 = -32015;
 }
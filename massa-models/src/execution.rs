@@ -1,6 +1,6 @@
 use std::{collections::VecDeque, fmt::Display};
 
-use crate::{output_event::SCOutputEvent, Slot};
+use crate::{output_event::SCOutputEvent, Address, Amount, OperationId, Slot};
 use serde::{Deserialize, Serialize};
 
 /// The result of the read-only execution.
@@ -22,6 +22,9 @@ pub struct ExecuteReadOnlyResponse {
     pub result: ReadOnlyResult,
     /// The output events generated by the read-only execution.
     pub output_events: VecDeque<SCOutputEvent>,
+    /// The trace of the execution, if it was requested
+    #[serde(default)]
+    pub trace: Option<ExecutionTrace>,
 }
 
 impl Display for ExecuteReadOnlyResponse {
@@ -42,6 +45,179 @@ impl Display for ExecuteReadOnlyResponse {
                 writeln!(f, "{}", event)?; // id already displayed in event
             }
         }
+        if let Some(trace) = &self.trace {
+            write!(f, "{}", trace)?;
+        }
+        Ok(())
+    }
+}
+
+/// Access to a ledger entry made by an ABI call
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum LedgerAccess {
+    /// the entry was read
+    Read {
+        /// address of the ledger entry
+        address: Address,
+        /// accessed part of the entry: "balance", "bytecode" or the datastore key
+        field: String,
+    },
+    /// the entry was written
+    Write {
+        /// address of the ledger entry
+        address: Address,
+        /// accessed part of the entry: "balance", "bytecode" or the datastore key
+        field: String,
+    },
+}
+
+impl Display for LedgerAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerAccess::Read { address, field } => write!(f, "read {} of {}", field, address),
+            LedgerAccess::Write { address, field } => {
+                write!(f, "write {} of {}", field, address)
+            }
+        }
+    }
+}
+
+/// A call made by the executed bytecode to an ABI function
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AbiCallTrace {
+    /// name of the ABI function
+    pub name: String,
+    /// arguments of the call
+    pub arguments: String,
+    /// call stack at the time of the call, top of the stack last
+    pub call_stack: Vec<Address>,
+    /// ledger entries read or written by the call
+    pub ledger_accesses: Vec<LedgerAccess>,
+    /// error returned by the call, if any
+    pub error: Option<String>,
+}
+
+impl Display for AbiCallTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.name, self.arguments)?;
+        if let Some(address) = self.call_stack.last() {
+            write!(f, " in {}", address)?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, " failed: {}", error)?;
+        }
+        for access in &self.ledger_accesses {
+            write!(f, "\n\t\t{}", access)?;
+        }
+        Ok(())
+    }
+}
+
+/// Trace of an execution: the ABI calls it made, in order
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ExecutionTrace {
+    /// operation that caused the execution, if any
+    pub origin_operation_id: Option<OperationId>,
+    /// ABI calls made during the execution
+    pub abi_calls: Vec<AbiCallTrace>,
+    /// gas consumed by the execution, unknown if it failed
+    pub gas_used: Option<u64>,
+    /// error that stopped the execution, if any
+    pub error: Option<String>,
+}
+
+impl Display for ExecutionTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(operation_id) = &self.origin_operation_id {
+            writeln!(f, "Execution trace of operation {}:", operation_id)?;
+        } else {
+            writeln!(f, "Execution trace:")?;
+        }
+        if let Some(gas_used) = self.gas_used {
+            writeln!(f, "\tGas used: {}", gas_used)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "\tError: {}", error)?;
+        }
+        for call in &self.abi_calls {
+            writeln!(f, "\t{}", call)?;
+        }
+        Ok(())
+    }
+}
+
+/// What caused an execution described by a receipt
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ReceiptOrigin {
+    /// an `ExecuteSC` or `CallSC` operation
+    Operation(OperationId),
+    /// an asynchronous message
+    AsyncMessage {
+        /// slot at which the message was emitted
+        emission_slot: Slot,
+        /// index of the message among the ones emitted at that slot
+        emission_index: u64,
+    },
+}
+
+impl Display for ReceiptOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReceiptOrigin::Operation(id) => write!(f, "operation {}", id),
+            ReceiptOrigin::AsyncMessage {
+                emission_slot,
+                emission_index,
+            } => write!(
+                f,
+                "async message {} emitted at slot {}",
+                emission_index, emission_slot
+            ),
+        }
+    }
+}
+
+/// Outcome of the execution of an operation or asynchronous message
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExecutionReceipt {
+    /// what was executed
+    pub origin: ReceiptOrigin,
+    /// slot at which it was executed
+    pub slot: Slot,
+    /// true if the receipt is final
+    pub is_final: bool,
+    /// error that made the execution fail, None on success
+    pub error: Option<String>,
+    /// gas consumed by the execution, unknown if it failed
+    pub gas_used: Option<u64>,
+    /// coins spent by the sender: gas fees, plus the coins sent with the call if it succeeded
+    pub coins_spent: Amount,
+    /// smart contract addresses created by the execution, empty if it failed
+    pub created_addresses: Vec<Address>,
+}
+
+impl Display for ExecutionReceipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Receipt of {} (slot {}{}):",
+            self.origin,
+            self.slot,
+            if self.is_final { ", final" } else { "" }
+        )?;
+        match &self.error {
+            Some(err) => writeln!(f, "\tFailed: {}", err)?,
+            None => writeln!(f, "\tSucceeded")?,
+        }
+        if let Some(gas_used) = self.gas_used {
+            writeln!(f, "\tGas used: {}", gas_used)?;
+        }
+        writeln!(f, "\tCoins spent: {}", self.coins_spent)?;
+        if !self.created_addresses.is_empty() {
+            writeln!(f, "\tCreated addresses:")?;
+            for address in &self.created_addresses {
+                writeln!(f, "\t\t{}", address)?;
+            }
+        }
         Ok(())
     }
 }
@@ -31,6 +31,9 @@ pub struct CompactConfig {
     pub roll_price: Amount,
     /// Max total size of a block
     pub max_block_size: u32,
+    /// Max number of operations per network message (0 if not exposed by the node)
+    #[serde(default)]
+    pub max_operations_per_message: u32,
 }
 
 impl Default for CompactConfig {
@@ -56,6 +59,7 @@ impl Default for CompactConfig {
             block_reward: BLOCK_REWARD,
             roll_price: ROLL_PRICE,
             max_block_size: MAX_BLOCK_SIZE,
+            max_operations_per_message: MAX_OPERATIONS_PER_MESSAGE,
         }
     }
 }
@@ -92,6 +96,11 @@ impl Display for CompactConfig {
         writeln!(f, "    Block reward: {}", self.block_reward)?;
         writeln!(f, "    Periods per cycle: {}", self.periods_per_cycle)?;
         writeln!(f, "    Max block size (in bytes): {}", self.max_block_size)?;
+        writeln!(
+            f,
+            "    Max operations per message: {}",
+            self.max_operations_per_message
+        )?;
         Ok(())
     }
 }
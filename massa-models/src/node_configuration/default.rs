@@ -148,6 +148,8 @@ pub const BOOTSTRAP_RANDOMNESS_SIZE_BYTES: usize = 32;
 pub const MAX_GAS_PER_BLOCK: u64 = 1_000_000_000;
 /// Maximum of GAS allowed for asynchronous messages execution on one slot
 pub const MAX_ASYNC_GAS: u64 = 1_000_000_000;
+/// Price locked from the balance of an address for each byte stored in its datastore
+pub const STORAGE_BYTE_COST: Amount = Amount::from_raw(AMOUNT_DECIMAL_FACTOR / 10_000);
 
 //
 // Constants used in network
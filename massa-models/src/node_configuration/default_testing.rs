@@ -115,6 +115,8 @@ pub const MAX_BOOTSTRAP_POS_CYCLES: u32 = 5;
 pub const MAX_GAS_PER_BLOCK: u64 = 100_000_000;
 /// max asynchronous gas
 pub const MAX_ASYNC_GAS: u64 = 10_000_000;
+/// price locked for each byte stored in a datastore
+pub const STORAGE_BYTE_COST: Amount = Amount::from_raw(AMOUNT_DECIMAL_FACTOR / 10_000);
 /// max message size 3 * 1024 * 1024
 pub const MAX_MESSAGE_SIZE: u32 = 3145728;
 /// max number of operation per block
@@ -179,6 +181,10 @@ pub const MAX_DEPENDENCY_BLOCK: usize = 10;
 pub const MAX_DISCARDED_BLOCKS: usize = 10;
 /// normally in `config.toml`, max final events kept
 pub const MAX_FINAL_EVENTS: usize = 10;
+/// normally in `config.toml`, max final execution receipts kept
+pub const MAX_FINAL_RECEIPTS: usize = 10;
+/// normally in `config.toml`, max final operation execution traces kept
+pub const MAX_FINAL_TRACES: usize = 10;
 /// normally in `config.toml`, max in the future kept blocks
 pub const MAX_FUTURE_PROCESSING_BLOCK: usize = 10;
 /// normally in `config.toml`, max item count returned
@@ -191,6 +197,10 @@ pub const OPERATION_BATCH_SIZE: usize = 3;
 pub const POS_DRAW_CACHED_CYCLE: usize = 10;
 /// normally in `config.toml`, read only queue length
 pub const READONLY_QUEUE_LENGTH: usize = 10;
+/// normally in `config.toml`, number of parallel read only executors
+pub const READONLY_EXECUTOR_COUNT: usize = 2;
+/// normally in `config.toml`, number of latest executed slots over which execution stats are aggregated
+pub const EXECUTION_STATS_HISTORY_LENGTH: usize = 100;
 
 // Note: In the `massa-network`, the default values are defined in the `settings.rs` of the
 // `massa-network` crate.
@@ -202,6 +212,10 @@ lazy_static::lazy_static! {
     pub static ref LEDGER_FLUSH_INTERVAL: Option<MassaTime> = Some(200.into());
     /// we wait `MAX_SEND_WAIT` milliseconds to send a message
     pub static ref MAX_SEND_WAIT: MassaTime = 500.into();
+    /// read only executions time out after `READONLY_TIMEOUT` milliseconds
+    pub static ref READONLY_TIMEOUT: MassaTime = 10000.into();
+    /// a warning is logged when a slot takes more than `SLOW_SLOT_THRESHOLD` milliseconds to execute
+    pub static ref SLOW_SLOT_THRESHOLD: MassaTime = 1000.into();
     /// stats are considered for `STATS_TIMESPAN` milliseconds
     pub static ref STATS_TIMESPAN: MassaTime = 60000.into();
 }
@@ -74,6 +74,19 @@ where
         ))
     }
 
+    /// build a signed structure from its content and a signature produced elsewhere, and compute its id.
+    /// The signature is not checked.
+    pub fn new_with_signature(content: T, signature: Signature) -> Result<(U, Self), ModelsError> {
+        Ok((
+            content.compute_id()?,
+            Self {
+                content,
+                signature,
+                phantom: PhantomData,
+            },
+        ))
+    }
+
     /// check if self has been signed by public key
     pub fn verify_signature(&self, public_key: &PublicKey) -> Result<(), ModelsError> {
         Ok(verify_signature(
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
 /// stats produced by network module
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NetworkStats {
     /// in connections count
     pub in_connection_count: u64,
@@ -73,7 +73,7 @@ impl std::fmt::Display for ConsensusStats {
 }
 
 /// stats produced by pool module
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PoolStats {
     /// number of operations in the pool
     pub operation_count: u64,
@@ -89,3 +89,78 @@ impl std::fmt::Display for PoolStats {
         Ok(())
     }
 }
+
+/// stats produced by execution module, aggregated separately over the latest final and active slot executions
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ExecutionStats {
+    /// stats of the executions of final slots whose speculative execution could not be reused
+    pub final_slots: SlotExecutionStats,
+    /// stats of the speculative executions of active slots
+    pub active_slots: SlotExecutionStats,
+}
+
+impl std::fmt::Display for ExecutionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Execution stats:")?;
+        writeln!(f, "\tFinal slots:")?;
+        write!(f, "{}", self.final_slots)?;
+        writeln!(f, "\tActive slots:")?;
+        write!(f, "{}", self.active_slots)?;
+        Ok(())
+    }
+}
+
+/// execution stats of one kind of slot executions (final or active), aggregated over the latest ones
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SlotExecutionStats {
+    /// number of executed slots the stats are aggregated over
+    pub slot_count: u64,
+    /// wall time spent executing those slots, in microseconds
+    pub total_time_us: u64,
+    /// highest wall time spent executing one of those slots, in microseconds
+    pub max_slot_time_us: u64,
+    /// number of executed operations
+    pub operation_count: u64,
+    /// wall time spent executing operations, in microseconds
+    pub operation_time_us: u64,
+    /// gas used by the executed operations
+    pub operation_gas_used: u64,
+    /// number of executed asynchronous messages
+    pub async_message_count: u64,
+    /// wall time spent executing asynchronous messages, in microseconds
+    pub async_message_time_us: u64,
+    /// gas used by the executed asynchronous messages
+    pub async_message_gas_used: u64,
+    /// number of reads of the speculative ledger
+    pub ledger_read_count: u64,
+    /// number of speculative ledger reads that were not served by speculative changes and hit the final ledger
+    pub final_ledger_read_count: u64,
+}
+
+impl std::fmt::Display for SlotExecutionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\t\tExecuted slots: {}", self.slot_count)?;
+        writeln!(
+            f,
+            "\t\tAverage slot time: {} us",
+            self.total_time_us.checked_div(self.slot_count).unwrap_or(0)
+        )?;
+        writeln!(f, "\t\tMax slot time: {} us", self.max_slot_time_us)?;
+        writeln!(
+            f,
+            "\t\tOperations: {} in {} us using {} gas",
+            self.operation_count, self.operation_time_us, self.operation_gas_used
+        )?;
+        writeln!(
+            f,
+            "\t\tAsync messages: {} in {} us using {} gas",
+            self.async_message_count, self.async_message_time_us, self.async_message_gas_used
+        )?;
+        writeln!(
+            f,
+            "\t\tLedger reads: {} ({} from the final ledger)",
+            self.ledger_read_count, self.final_ledger_read_count
+        )?;
+        Ok(())
+    }
+}
@@ -22,7 +22,7 @@ use massa_logging::massa_trace;
 use massa_models::{
     constants::{
         END_TIMESTAMP, GENESIS_TIMESTAMP, MAX_ASYNC_GAS, MAX_ASYNC_POOL_LENGTH, MAX_GAS_PER_BLOCK,
-        OPERATION_VALIDITY_PERIODS, T0, THREAD_COUNT, VERSION,
+        OPERATION_VALIDITY_PERIODS, STORAGE_BYTE_COST, T0, THREAD_COUNT, VERSION,
     },
     init_serialization_context, SerializationContext,
 };
@@ -95,14 +95,7 @@ async fn launch() -> (
     // Init the global serialization context
     init_serialization_context(SerializationContext::default());
 
-    // Remove current disk ledger if there is one
-    // NOTE: this is temporary, since we cannot currently handle bootstrap from remaining ledger
-    if SETTINGS.ledger.disk_ledger_path.exists() {
-        std::fs::remove_dir_all(SETTINGS.ledger.disk_ledger_path.clone())
-            .expect("disk ledger delete failed");
-    }
-
-    // Create final state
+    // Create final state, reopening the one stored on disk if there is one
     let final_state = Arc::new(RwLock::new(
         FinalState::new(final_state_config).expect("could not init final state"),
     ));
@@ -171,13 +164,30 @@ async fn launch() -> (
     // launch execution module
     let execution_config = ExecutionConfig {
         max_final_events: SETTINGS.execution.max_final_events,
+        final_events_db_path: SETTINGS
+            .execution
+            .persistent_final_events
+            .then(|| SETTINGS.execution.final_events_db_path.clone()),
+        final_events_retention_slots: Some(SETTINGS.execution.final_events_retention_slots)
+            .filter(|slots| *slots > 0),
+        final_events_max_disk_size: Some(SETTINGS.execution.final_events_max_disk_size)
+            .filter(|size| *size > 0),
+        max_item_return_count: SETTINGS.consensus.max_item_return_count,
+        max_final_receipts: SETTINGS.execution.max_final_receipts,
         readonly_queue_length: SETTINGS.execution.readonly_queue_length,
+        readonly_executor_count: SETTINGS.execution.readonly_executor_count,
+        readonly_timeout: SETTINGS.execution.readonly_timeout,
         cursor_delay: SETTINGS.execution.cursor_delay,
         clock_compensation: bootstrap_state.compensation_millis,
         max_async_gas: MAX_ASYNC_GAS,
+        storage_byte_cost: STORAGE_BYTE_COST,
         thread_count,
         t0,
         genesis_timestamp: *GENESIS_TIMESTAMP,
+        trace_operations: SETTINGS.execution.trace_operations,
+        max_final_traces: SETTINGS.execution.max_final_traces,
+        stats_history_length: SETTINGS.execution.stats_history_length,
+        slow_slot_threshold: SETTINGS.execution.slow_slot_threshold,
     };
     let (execution_manager, execution_controller) = start_execution_worker(
         execution_config,
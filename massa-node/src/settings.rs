@@ -41,8 +41,19 @@ pub struct LoggingSettings {
 #[derive(Clone, Debug, Deserialize)]
 pub struct ExecutionSettings {
     pub max_final_events: usize,
+    pub persistent_final_events: bool,
+    pub final_events_db_path: PathBuf,
+    pub final_events_retention_slots: u64,
+    pub final_events_max_disk_size: u64,
+    pub max_final_receipts: usize,
     pub readonly_queue_length: usize,
+    pub readonly_executor_count: usize,
+    pub readonly_timeout: MassaTime,
     pub cursor_delay: MassaTime,
+    pub trace_operations: bool,
+    pub max_final_traces: usize,
+    pub stats_history_length: usize,
+    pub slow_slot_threshold: MassaTime,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -0,0 +1,36 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::time::Duration;
+
+/// Configuration of the connection to the node API
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// max duration of one request to one endpoint
+    pub request_timeout: Duration,
+    /// number of times all the endpoints are tried again after they all failed
+    pub max_retries: u32,
+    /// delay before the first retry, doubled at each retry
+    pub retry_base_delay: Duration,
+    /// max delay between two retries
+    pub retry_max_delay: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            request_timeout: Duration::from_secs(10),
+            max_retries: 2,
+            retry_base_delay: Duration::from_millis(200),
+            retry_max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// delay before the given retry (starting at 0)
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.retry_max_delay)
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use displaydoc::Display;
+use jsonrpc_core_client::RpcError;
+use thiserror::Error;
+
+/// SDK result
+pub type SdkResult<T, E = SdkError> = core::result::Result<T, E>;
+
+/// SDK errors
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum SdkError {
+    /// no node endpoint configured
+    NoEndpoint,
+    /// invalid node endpoint {0}: {1}
+    InvalidEndpoint(String, String),
+    /// node API error {code}: {message}
+    ApiError {
+        /// JSON-RPC error code, see `massa_models::api::error_codes` for the codes of the node errors
+        code: i64,
+        /// error message
+        message: String,
+        /// optional error data
+        data: Option<serde_json::Value>,
+    },
+    /// timeout during {0}
+    Timeout(String),
+    /// transport error: {0}
+    TransportError(String),
+    /// invalid node response to {0}: {1}
+    ParseError(String, String),
+    /// invalid request arguments: {0}
+    SerializationError(#[from] serde_json::Error),
+    /// invalid argument: {0}
+    InvalidArgument(String),
+    /// models error: {0}
+    ModelsError(#[from] massa_models::ModelsError),
+    /// time error: {0}
+    TimeError(#[from] massa_time::TimeError),
+    /// wallet error: {0}
+    WalletError(#[from] massa_wallet::WalletError),
+    /// operation {0} expired: it was not included in a block before the end of period {1}
+    OperationExpired(massa_models::OperationId, u64),
+    /// operation {0} is in block {1}, which did not become final in time
+    OperationNotFinal(massa_models::OperationId, massa_models::BlockId),
+}
+
+impl SdkError {
+    /// true if the error is caused by the connection to the node, so that the request may be retried
+    pub fn is_transient(&self) -> bool {
+        matches!(self, SdkError::Timeout(_) | SdkError::TransportError(_))
+    }
+
+    /// JSON-RPC error code returned by the node, if any
+    pub fn api_error_code(&self) -> Option<i64> {
+        match self {
+            SdkError::ApiError { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcError> for SdkError {
+    fn from(err: RpcError) -> Self {
+        match err {
+            RpcError::JsonRpcError(err) => SdkError::ApiError {
+                code: err.code.code(),
+                message: err.message,
+                data: err.data,
+            },
+            RpcError::ParseError(method, err) => SdkError::ParseError(method, err.to_string()),
+            RpcError::Timeout => SdkError::Timeout("request".into()),
+            RpcError::Client(err) => SdkError::TransportError(err),
+            RpcError::Other(err) => SdkError::TransportError(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Generates private keys whose address lands in a given thread
+
+use crate::error::{SdkError, SdkResult};
+use massa_models::Address;
+use massa_signature::{derive_public_key, generate_random_private_key, PrivateKey};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+
+/// Generates random private keys until the address of one of them is in `thread`,
+/// using `workers` threads to search in parallel.
+///
+/// As addresses are uniformly spread across threads, about `thread_count` keys are tried.
+pub fn generate_private_key_in_thread(
+    thread: u8,
+    thread_count: u8,
+    workers: usize,
+) -> SdkResult<PrivateKey> {
+    if thread >= thread_count {
+        return Err(SdkError::InvalidArgument(format!(
+            "thread {} does not exist, there are {} threads",
+            thread, thread_count
+        )));
+    }
+    if workers == 0 {
+        return Err(SdkError::InvalidArgument(
+            "at least one worker is needed".into(),
+        ));
+    }
+    let found = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let found = found.clone();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                while !found.load(Ordering::Relaxed) {
+                    let key = generate_random_private_key();
+                    let address = Address::from_public_key(&derive_public_key(&key));
+                    if address.get_thread(thread_count) == thread {
+                        found.store(true, Ordering::Relaxed);
+                        // the receiver only keeps the first key found
+                        let _ = tx.send(key);
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+    let key = rx
+        .recv()
+        .map_err(|_| SdkError::InvalidArgument("key search workers stopped".into()))?;
+    for handle in handles {
+        let _ = handle.join();
+    }
+    Ok(key)
+}
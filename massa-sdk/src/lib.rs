@@ -4,23 +4,40 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+pub use config::ClientConfig;
+pub use error::{SdkError, SdkResult};
 use jsonrpc_core_client::transports::http;
-use jsonrpc_core_client::{RpcChannel, RpcError, RpcResult, TypedClient};
+use jsonrpc_core_client::TypedClient;
+pub use keys::generate_private_key_in_thread;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, EndorsementInfo, EventFilter, NodeStatus, OperationInfo,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, AsyncMessageFilter, AsyncMessageInfo, BlockInfo, BlockSummary, EndorsementInfo,
+    EventFilter, NodeStatus, OperationInfo, ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionReceipt, ExecutionTrace};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{Map, Set};
 use massa_models::{Address, BlockId, EndorsementId, OperationId, SignedOperation};
 use massa_signature::PrivateKey;
+pub use operation_builder::{
+    get_expire_period, get_finality_margin, wait_for_operation, OperationBuilder, OperationOutcome,
+    WaitMode,
+};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::OnceCell;
+
+mod config;
+mod error;
+mod keys;
+mod operation_builder;
+
+#[cfg(test)]
+mod tests;
 
 /// Client
 pub struct Client {
@@ -32,123 +49,278 @@ pub struct Client {
 
 impl Client {
     /// creates a new client
-    pub async fn new(ip: IpAddr, public_port: u16, private_port: u16) -> Client {
-        let public_socket_addr = SocketAddr::new(ip, public_port);
-        let private_socket_addr = SocketAddr::new(ip, private_port);
-        let public_url = format!("http://{}", public_socket_addr);
-        let private_url = format!("http://{}", private_socket_addr);
-        Client {
-            public: RpcClient::from_url(&public_url).await,
-            private: RpcClient::from_url(&private_url).await,
-        }
+    pub fn new(ip: IpAddr, public_port: u16, private_port: u16) -> SdkResult<Client> {
+        Client::with_failover(ip, public_port, private_port, &[])
+    }
+
+    /// creates a new client whose read-only public calls fail over to the given public API addresses,
+    /// in order, when the node cannot be reached
+    pub fn with_failover(
+        ip: IpAddr,
+        public_port: u16,
+        private_port: u16,
+        public_failover: &[SocketAddr],
+    ) -> SdkResult<Client> {
+        let public_urls: Vec<String> = std::iter::once(SocketAddr::new(ip, public_port))
+            .chain(public_failover.iter().copied())
+            .map(|addr| format!("http://{}", addr))
+            .collect();
+        let private_url = format!("http://{}", SocketAddr::new(ip, private_port));
+        Client::with_config(public_urls, vec![private_url], ClientConfig::default())
+    }
+
+    /// creates a new client from the URLs of the public and private API endpoints
+    pub fn with_config(
+        public_urls: Vec<String>,
+        private_urls: Vec<String>,
+        config: ClientConfig,
+    ) -> SdkResult<Client> {
+        Ok(Client {
+            public: RpcClient::new(public_urls, config.clone())?,
+            private: RpcClient::new(private_urls, config)?,
+        })
     }
 }
 
-/// TODO ask @yvan-sraka
-pub struct RpcClient {
-    client: TypedClient,
-    timeout: u64,
+/// A node API endpoint, connected on first use
+struct Endpoint {
+    /// endpoint URL
+    url: String,
+    /// client, once connected
+    client: OnceCell<TypedClient>,
 }
 
-/// This is required by `jsonrpc_core_client::transports::http::connect`
-impl From<RpcChannel> for RpcClient {
-    fn from(channel: RpcChannel) -> Self {
-        RpcClient {
-            client: channel.into(),
-            timeout: 10000,
-        }
+impl Endpoint {
+    /// gets the client, connecting first if needed
+    async fn client(&self) -> SdkResult<&TypedClient> {
+        self.client
+            .get_or_try_init(|| async {
+                http::connect::<TypedClient>(&self.url)
+                    .await
+                    .map_err(|e| SdkError::InvalidEndpoint(self.url.clone(), e.to_string()))
+            })
+            .await
     }
 }
 
+/// Whether a request can be sent again after a transient failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Retry {
+    /// read-only or idempotent request: failed over and retried
+    Allowed,
+    /// request with side effects, which may have been applied even if it timed out:
+    /// sent once, to the current endpoint
+    Never,
+}
+
+/// Client of one API (public or private) of a node.
+///
+/// Connections are opened lazily.
+/// Read-only and idempotent requests go to the endpoint that answered last, and move to the next endpoints
+/// when it cannot be reached. When all the endpoints failed, the request is retried
+/// with an exponential backoff.
+/// Requests with side effects (sending operations, stopping the node, changing staking keys or peers)
+/// are sent once, to the endpoint that answered last.
+pub struct RpcClient {
+    /// node endpoints, tried in order
+    endpoints: Vec<Endpoint>,
+    /// index of the endpoint that answered last
+    current: AtomicUsize,
+    /// timeouts and retries
+    config: ClientConfig,
+}
+
 impl RpcClient {
-    /// Default constructor
-    pub async fn from_url(url: &str) -> RpcClient {
-        match http::connect::<RpcClient>(url).await {
-            Ok(client) => client,
-            Err(_) => panic!("unable to connect to Node."),
+    /// Creates a client for the given endpoint URLs, without connecting yet
+    pub fn new(urls: Vec<String>, config: ClientConfig) -> SdkResult<RpcClient> {
+        if urls.is_empty() {
+            return Err(SdkError::NoEndpoint);
         }
+        Ok(RpcClient {
+            endpoints: urls
+                .into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    client: OnceCell::new(),
+                })
+                .collect(),
+            current: AtomicUsize::new(0),
+            config,
+        })
+    }
+
+    /// Creates a client for one endpoint URL with the default configuration
+    pub fn from_url(url: &str) -> SdkResult<RpcClient> {
+        RpcClient::new(vec![url.to_string()], ClientConfig::default())
     }
 
     /// Typed wrapper to API calls based on the method given by `jsonrpc_core_client`
+    ///
+    /// Errors returned by a node are returned as is,
+    /// while timeouts and transport errors trigger a failover to the next endpoint
+    /// if `retry_policy` allows it.
     async fn call_method<T: Serialize, R: DeserializeOwned>(
         &self,
         method: &str,
         returns: &str,
         args: T,
-    ) -> RpcResult<R> {
-        tokio::time::timeout(
-            tokio::time::Duration::from_millis(self.timeout),
-            self.client.call_method(method, returns, args),
+        retry_policy: Retry,
+    ) -> SdkResult<R> {
+        let args = serde_json::to_value(args)?;
+        if retry_policy == Retry::Never {
+            let index = self.current.load(Ordering::Relaxed);
+            return self
+                .call_endpoint(&self.endpoints[index], method, returns, args)
+                .await;
+        }
+        let mut retry = 0;
+        loop {
+            let first = self.current.load(Ordering::Relaxed);
+            let mut last_error = SdkError::NoEndpoint;
+            for attempt in 0..self.endpoints.len() {
+                let index = (first + attempt) % self.endpoints.len();
+                match self
+                    .call_endpoint(&self.endpoints[index], method, returns, args.clone())
+                    .await
+                {
+                    Err(err) if err.is_transient() => last_error = err,
+                    res => {
+                        self.current.store(index, Ordering::Relaxed);
+                        return res;
+                    }
+                }
+            }
+            if retry >= self.config.max_retries {
+                return Err(last_error);
+            }
+            tokio::time::sleep(self.config.retry_delay(retry)).await;
+            retry += 1;
+        }
+    }
+
+    /// Calls a method on one endpoint
+    async fn call_endpoint<R: DeserializeOwned>(
+        &self,
+        endpoint: &Endpoint,
+        method: &str,
+        returns: &str,
+        args: serde_json::Value,
+    ) -> SdkResult<R> {
+        let client = endpoint.client().await?;
+        match tokio::time::timeout(
+            self.config.request_timeout,
+            client.call_method(method, returns, args),
         )
         .await
-        .map_err(|e| RpcError::Client(format!("timeout during {}: {}", method, e)))?
+        {
+            Ok(res) => res.map_err(|e| match SdkError::from(e) {
+                SdkError::Timeout(_) => SdkError::Timeout(method.to_string()),
+                err => err,
+            }),
+            Err(_) => Err(SdkError::Timeout(method.to_string())),
+        }
     }
 
     /// Gracefully stop the node.
-    pub async fn stop_node(&self) -> RpcResult<()> {
-        self.call_method("stop_node", "()", ()).await
+    pub async fn stop_node(&self) -> SdkResult<()> {
+        self.call_method("stop_node", "()", (), Retry::Never).await
     }
 
     /// Sign message with node's key.
     /// Returns the public key that signed the message and the signature.
-    pub async fn node_sign_message(&self, message: Vec<u8>) -> RpcResult<PubkeySig> {
-        self.call_method("node_sign_message", "PubkeySig", vec![message])
-            .await
+    pub async fn node_sign_message(&self, message: Vec<u8>) -> SdkResult<PubkeySig> {
+        self.call_method(
+            "node_sign_message",
+            "PubkeySig",
+            vec![message],
+            Retry::Allowed,
+        )
+        .await
     }
 
     /// Add a vector of new private keys for the node to use to stake.
     /// No confirmation to expect.
-    pub async fn add_staking_private_keys(&self, private_keys: Vec<PrivateKey>) -> RpcResult<()> {
-        self.call_method("add_staking_private_keys", "()", vec![private_keys])
-            .await
+    pub async fn add_staking_private_keys(&self, private_keys: Vec<PrivateKey>) -> SdkResult<()> {
+        self.call_method(
+            "add_staking_private_keys",
+            "()",
+            vec![private_keys],
+            Retry::Never,
+        )
+        .await
     }
 
     /// Remove a vector of addresses used to stake.
     /// No confirmation to expect.
-    pub async fn remove_staking_addresses(&self, addresses: Vec<Address>) -> RpcResult<()> {
-        self.call_method("remove_staking_addresses", "()", vec![addresses])
-            .await
+    pub async fn remove_staking_addresses(&self, addresses: Vec<Address>) -> SdkResult<()> {
+        self.call_method(
+            "remove_staking_addresses",
+            "()",
+            vec![addresses],
+            Retry::Never,
+        )
+        .await
     }
 
     /// Return hash-set of staking addresses.
-    pub async fn get_staking_addresses(&self) -> RpcResult<Set<Address>> {
-        self.call_method("get_staking_addresses", "Set<Address>", ())
+    pub async fn get_staking_addresses(&self) -> SdkResult<Set<Address>> {
+        self.call_method("get_staking_addresses", "Set<Address>", (), Retry::Allowed)
             .await
     }
 
+    /// Returns the execution traces of recently finalized operations.
+    /// Requires operation tracing to be enabled on the node.
+    pub async fn get_operation_execution_traces(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> SdkResult<Vec<ExecutionTrace>> {
+        self.call_method(
+            "get_operation_execution_traces",
+            "Vec<ExecutionTrace>",
+            vec![operation_ids],
+            Retry::Allowed,
+        )
+        .await
+    }
+
     /// Bans given ip address(es)
     /// No confirmation to expect.
-    pub async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.call_method("node_ban_by_ip", "()", vec![ips]).await
+    pub async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> SdkResult<()> {
+        self.call_method("node_ban_by_ip", "()", vec![ips], Retry::Never)
+            .await
     }
 
     /// Bans given node id(s)
     /// No confirmation to expect.
-    pub async fn node_ban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
-        self.call_method("node_ban_by_id", "()", vec![ids]).await
+    pub async fn node_ban_by_id(&self, ids: Vec<NodeId>) -> SdkResult<()> {
+        self.call_method("node_ban_by_id", "()", vec![ids], Retry::Never)
+            .await
     }
 
     /// Unbans given ip address(es)
     /// No confirmation to expect.
-    pub async fn node_unban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.call_method("node_unban_by_ip", "()", vec![ips]).await
+    pub async fn node_unban_by_ip(&self, ips: Vec<IpAddr>) -> SdkResult<()> {
+        self.call_method("node_unban_by_ip", "()", vec![ips], Retry::Never)
+            .await
     }
 
     /// Unbans given node id(s)
     /// No confirmation to expect.
-    pub async fn node_unban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
-        self.call_method("node_unban_by_id", "()", vec![ids]).await
+    pub async fn node_unban_by_id(&self, ids: Vec<NodeId>) -> SdkResult<()> {
+        self.call_method("node_unban_by_id", "()", vec![ids], Retry::Never)
+            .await
     }
 
     /// add ips to whitelist
     /// create peer if it was unknown
-    pub async fn node_whitelist(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.call_method("node_whitelist", "()", vec![ips]).await
+    pub async fn node_whitelist(&self, ips: Vec<IpAddr>) -> SdkResult<()> {
+        self.call_method("node_whitelist", "()", vec![ips], Retry::Never)
+            .await
     }
 
     /// remove IPs from whitelist
-    pub async fn node_remove_from_whitelist(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.call_method("node_remove_from_whitelist", "()", vec![ips])
+    pub async fn node_remove_from_whitelist(&self, ips: Vec<IpAddr>) -> SdkResult<()> {
+        self.call_method("node_remove_from_whitelist", "()", vec![ips], Retry::Never)
             .await
     }
 
@@ -159,19 +331,21 @@ impl RpcClient {
     // Explorer (aggregated stats)
 
     /// summary of the current state: time, last final blocks (hash, thread, slot, timestamp), clique count, connected nodes count
-    pub async fn get_status(&self) -> RpcResult<NodeStatus> {
-        self.call_method("get_status", "NodeStatus", ()).await
+    pub async fn get_status(&self) -> SdkResult<NodeStatus> {
+        self.call_method("get_status", "NodeStatus", (), Retry::Allowed)
+            .await
     }
 
-    pub(crate) async fn _get_cliques(&self) -> RpcResult<Vec<Clique>> {
-        self.call_method("get_cliques", "Vec<Clique>", ()).await
+    pub(crate) async fn _get_cliques(&self) -> SdkResult<Vec<Clique>> {
+        self.call_method("get_cliques", "Vec<Clique>", (), Retry::Allowed)
+            .await
     }
 
     // Debug (specific information)
 
     /// Returns the active stakers and their roll counts for the current cycle.
-    pub(crate) async fn _get_stakers(&self) -> RpcResult<Map<Address, u64>> {
-        self.call_method("get_stakers", "Map<Address, u64>", ())
+    pub(crate) async fn _get_stakers(&self) -> SdkResult<Map<Address, u64>> {
+        self.call_method("get_stakers", "Map<Address, u64>", (), Retry::Allowed)
             .await
     }
 
@@ -179,27 +353,33 @@ impl RpcClient {
     pub async fn get_operations(
         &self,
         operation_ids: Vec<OperationId>,
-    ) -> RpcResult<Vec<OperationInfo>> {
-        self.call_method("get_operations", "Vec<OperationInfo>", vec![operation_ids])
-            .await
+    ) -> SdkResult<Vec<OperationInfo>> {
+        self.call_method(
+            "get_operations",
+            "Vec<OperationInfo>",
+            vec![operation_ids],
+            Retry::Allowed,
+        )
+        .await
     }
 
     /// get info on endorsements by ids
     pub async fn get_endorsements(
         &self,
         endorsement_ids: Vec<EndorsementId>,
-    ) -> RpcResult<Vec<EndorsementInfo>> {
+    ) -> SdkResult<Vec<EndorsementInfo>> {
         self.call_method(
             "get_endorsements",
             "Vec<EndorsementInfo>",
             vec![endorsement_ids],
+            Retry::Allowed,
         )
         .await
     }
 
     /// Get information on a block given its `BlockId`
-    pub async fn get_block(&self, block_id: BlockId) -> RpcResult<BlockInfo> {
-        self.call_method("get_block", "BlockInfo", vec![block_id])
+    pub async fn get_block(&self, block_id: BlockId) -> SdkResult<BlockInfo> {
+        self.call_method("get_block", "BlockInfo", vec![block_id], Retry::Allowed)
             .await
     }
 
@@ -207,11 +387,41 @@ impl RpcClient {
     pub async fn get_filtered_sc_output_event(
         &self,
         filter: EventFilter,
-    ) -> RpcResult<Vec<SCOutputEvent>> {
+    ) -> SdkResult<Vec<SCOutputEvent>> {
         self.call_method(
             "get_filtered_sc_output_event",
             "Vec<SCOutputEvent>",
             vec![filter],
+            Retry::Allowed,
+        )
+        .await
+    }
+
+    /// Get the execution receipts of smart contract operations
+    pub async fn get_operation_receipts(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> SdkResult<Vec<ExecutionReceipt>> {
+        self.call_method(
+            "get_operation_receipts",
+            "Vec<ExecutionReceipt>",
+            vec![operation_ids],
+            Retry::Allowed,
+        )
+        .await
+    }
+
+    /// Get the pending asynchronous messages, optionally filtered by
+    /// sender address, destination address and validity window
+    pub async fn get_async_messages(
+        &self,
+        filter: AsyncMessageFilter,
+    ) -> SdkResult<Vec<AsyncMessageInfo>> {
+        self.call_method(
+            "get_async_messages",
+            "Vec<AsyncMessageInfo>",
+            vec![filter],
+            Retry::Allowed,
         )
         .await
     }
@@ -221,15 +431,25 @@ impl RpcClient {
     pub(crate) async fn _get_graph_interval(
         &self,
         time_interval: TimeInterval,
-    ) -> RpcResult<Vec<BlockSummary>> {
-        self.call_method("get_graph_interval", "Vec<BlockSummary>", time_interval)
-            .await
+    ) -> SdkResult<Vec<BlockSummary>> {
+        self.call_method(
+            "get_graph_interval",
+            "Vec<BlockSummary>",
+            time_interval,
+            Retry::Allowed,
+        )
+        .await
     }
 
     /// Get info by addresses
-    pub async fn get_addresses(&self, addresses: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
-        self.call_method("get_addresses", "Vec<AddressInfo>", vec![addresses])
-            .await
+    pub async fn get_addresses(&self, addresses: Vec<Address>) -> SdkResult<Vec<AddressInfo>> {
+        self.call_method(
+            "get_addresses",
+            "Vec<AddressInfo>",
+            vec![addresses],
+            Retry::Allowed,
+        )
+        .await
     }
 
     // User (interaction with the node)
@@ -238,25 +458,34 @@ impl RpcClient {
     pub async fn send_operations(
         &self,
         operations: Vec<SignedOperation>,
-    ) -> RpcResult<Vec<OperationId>> {
-        self.call_method("send_operations", "Vec<OperationId>", vec![operations])
-            .await
+    ) -> SdkResult<Vec<OperationId>> {
+        self.call_method(
+            "send_operations",
+            "Vec<OperationId>",
+            vec![operations],
+            Retry::Never,
+        )
+        .await
     }
 
     /// execute read only bytecode
     pub async fn execute_read_only_bytecode(
         &self,
         read_only_execution: ReadOnlyBytecodeExecution,
-    ) -> RpcResult<ExecuteReadOnlyResponse> {
+    ) -> SdkResult<ExecuteReadOnlyResponse> {
         self.call_method::<Vec<Vec<ReadOnlyBytecodeExecution>>, Vec<ExecuteReadOnlyResponse>>(
             "execute_read_only_bytecode",
             "Vec<ExecuteReadOnlyResponse>",
             vec![vec![read_only_execution]],
+            Retry::Allowed,
         )
         .await?
         .pop()
         .ok_or_else(|| {
-            RpcError::Client("missing return value on execute_read_only_bytecode".into())
+            SdkError::ParseError(
+                "execute_read_only_bytecode".into(),
+                "missing return value".into(),
+            )
         })
     }
 
@@ -264,14 +493,20 @@ impl RpcClient {
     pub async fn execute_read_only_call(
         &self,
         read_only_execution: ReadOnlyCall,
-    ) -> RpcResult<ExecuteReadOnlyResponse> {
+    ) -> SdkResult<ExecuteReadOnlyResponse> {
         self.call_method::<Vec<Vec<ReadOnlyCall>>, Vec<ExecuteReadOnlyResponse>>(
             "execute_read_only_call",
             "Vec<ExecuteReadOnlyResponse>",
             vec![vec![read_only_execution]],
+            Retry::Allowed,
         )
         .await?
         .pop()
-        .ok_or_else(|| RpcError::Client("missing return value on execute_read_only_call".into()))
+        .ok_or_else(|| {
+            SdkError::ParseError(
+                "execute_read_only_call".into(),
+                "missing return value".into(),
+            )
+        })
     }
 }
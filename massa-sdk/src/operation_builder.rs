@@ -0,0 +1,366 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Builds, signs and sends operations.
+//!
+//! ```ignore
+//! let outcome = OperationBuilder::transaction(recipient_address, amount)
+//!     .fee(fee)
+//!     .send_and_wait(&client.public, &wallet, sender_address, WaitMode::Final)
+//!     .await?;
+//! ```
+
+use crate::error::{SdkError, SdkResult};
+use crate::RpcClient;
+use massa_models::api::EventFilter;
+use massa_models::output_event::SCOutputEvent;
+use massa_models::signed::{Signable, Signed};
+use massa_models::timeslots::get_current_latest_block_slot;
+use massa_models::{
+    Address, Amount, BlockId, CompactConfig, Operation, OperationId, OperationType,
+    SignedOperation, Slot,
+};
+use massa_signature::{derive_public_key, PrivateKey};
+use massa_wallet::Wallet;
+use serde::Serialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Computes the expire period of an operation sent now from the given address,
+/// so that it remains valid for `operation_validity_periods` periods
+pub fn get_expire_period(cfg: &CompactConfig, addr: Address) -> SdkResult<u64> {
+    let slot = get_current_latest_block_slot(cfg.thread_count, cfg.t0, cfg.genesis_timestamp, 0)? // clock compensation is zero
+        .unwrap_or_else(|| Slot::new(0, 0));
+    let mut expire_period = slot.period + cfg.operation_validity_periods;
+    if slot.thread >= addr.get_thread(cfg.thread_count) {
+        expire_period += 1;
+    };
+    Ok(expire_period)
+}
+
+/// Number of periods an operation included in a block is waited for after its expire period
+/// before giving up on its finality.
+///
+/// A block is final once about `delta_f0` blocks, i.e. `delta_f0 / thread_count` periods,
+/// are built on top of it: the margin is twice that, to tolerate misses.
+pub fn get_finality_margin(cfg: &CompactConfig) -> u64 {
+    2 * (cfg.delta_f0 / cfg.thread_count as u64 + 1)
+}
+
+/// When to consider a sent operation done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitMode {
+    /// the operation is in a block
+    Included,
+    /// the operation is in a final block
+    Final,
+}
+
+impl FromStr for WaitMode {
+    type Err = SdkError;
+
+    fn from_str(s: &str) -> SdkResult<Self> {
+        match s {
+            "included" => Ok(WaitMode::Included),
+            "final" => Ok(WaitMode::Final),
+            _ => Err(SdkError::InvalidArgument(format!(
+                "invalid wait mode '{}', expected 'included' or 'final'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Display for WaitMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitMode::Included => write!(f, "included"),
+            WaitMode::Final => write!(f, "final"),
+        }
+    }
+}
+
+/// Where and how a sent operation was executed
+#[derive(Debug, Serialize)]
+pub struct OperationOutcome {
+    /// operation id
+    pub operation_id: OperationId,
+    /// true if the block containing the operation is final
+    pub is_final: bool,
+    /// block containing the operation
+    pub block_id: BlockId,
+    /// slot of that block, if the node still knows it
+    pub slot: Option<Slot>,
+    /// events emitted by the operation execution
+    pub events: Vec<SCOutputEvent>,
+}
+
+impl Display for OperationOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation: {}", self.operation_id)?;
+        writeln!(
+            f,
+            "Status: {}",
+            if self.is_final {
+                "final"
+            } else {
+                "included in a block"
+            }
+        )?;
+        writeln!(f, "Block: {}", self.block_id)?;
+        if let Some(slot) = self.slot {
+            writeln!(f, "Slot: {}", slot)?;
+        }
+        if !self.events.is_empty() {
+            writeln!(f, "Events:")?;
+            for event in &self.events {
+                writeln!(f, "{}", event)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder of an operation of any type.
+///
+/// The fee defaults to zero, and the expire period defaults to the current period
+/// of the node plus `operation_validity_periods`.
+#[derive(Debug, Clone)]
+pub struct OperationBuilder {
+    /// operation content
+    op: OperationType,
+    /// fee paid to the block producer
+    fee: Amount,
+    /// explicit expire period
+    expire_period: Option<u64>,
+}
+
+impl OperationBuilder {
+    /// Builder of an operation of the given type
+    pub fn new(op: OperationType) -> Self {
+        OperationBuilder {
+            op,
+            fee: Amount::default(),
+            expire_period: None,
+        }
+    }
+
+    /// Transfer of coins to `recipient_address`
+    pub fn transaction(recipient_address: Address, amount: Amount) -> Self {
+        OperationBuilder::new(OperationType::Transaction {
+            recipient_address,
+            amount,
+        })
+    }
+
+    /// Purchase of `roll_count` rolls
+    pub fn roll_buy(roll_count: u64) -> Self {
+        OperationBuilder::new(OperationType::RollBuy { roll_count })
+    }
+
+    /// Sale of `roll_count` rolls
+    pub fn roll_sell(roll_count: u64) -> Self {
+        OperationBuilder::new(OperationType::RollSell { roll_count })
+    }
+
+    /// Execution of a smart contract bytecode
+    pub fn execute_sc(data: Vec<u8>, max_gas: u64, gas_price: Amount, coins: Amount) -> Self {
+        OperationBuilder::new(OperationType::ExecuteSC {
+            data,
+            max_gas,
+            coins,
+            gas_price,
+        })
+    }
+
+    /// Call of a function of a stored smart contract, transferring `parallel_coins` to it.
+    /// Use `sequential_coins` to also transfer sequential coins.
+    pub fn call_sc(
+        target_addr: Address,
+        target_func: String,
+        param: String,
+        max_gas: u64,
+        gas_price: Amount,
+        parallel_coins: Amount,
+    ) -> Self {
+        OperationBuilder::new(OperationType::CallSC {
+            target_addr,
+            target_func,
+            param,
+            max_gas,
+            sequential_coins: Amount::default(),
+            parallel_coins,
+            gas_price,
+        })
+    }
+
+    /// Sets the sequential coins transferred by a smart contract call, ignored by other operations
+    pub fn sequential_coins(mut self, coins: Amount) -> Self {
+        if let OperationType::CallSC {
+            sequential_coins, ..
+        } = &mut self.op
+        {
+            *sequential_coins = coins;
+        }
+        self
+    }
+
+    /// Sets the fee
+    pub fn fee(mut self, fee: Amount) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Sets the expire period instead of computing it from the node status
+    pub fn expire_period(mut self, expire_period: u64) -> Self {
+        self.expire_period = Some(expire_period);
+        self
+    }
+
+    /// Operation type and content
+    pub fn operation_type(&self) -> &OperationType {
+        &self.op
+    }
+
+    /// Signs the operation with the given private key
+    pub async fn sign(
+        self,
+        client: &RpcClient,
+        private_key: &PrivateKey,
+    ) -> SdkResult<SignedOperation> {
+        let sender_public_key = derive_public_key(private_key);
+        let sender = Address::from_public_key(&sender_public_key);
+        let expire_period = match self.expire_period {
+            Some(expire_period) => expire_period,
+            None => get_expire_period(&client.get_status().await?.config, sender)?,
+        };
+        let (_, operation) = Signed::new_signed(
+            Operation {
+                sender_public_key,
+                fee: self.fee,
+                expire_period,
+                op: self.op,
+            },
+            private_key,
+        )?;
+        Ok(operation)
+    }
+
+    /// Signs the operation with the key of a wallet address
+    pub async fn sign_with_wallet(
+        self,
+        client: &RpcClient,
+        wallet: &Wallet,
+        address: Address,
+    ) -> SdkResult<SignedOperation> {
+        let private_key = wallet
+            .find_associated_private_key(address)
+            .ok_or(massa_wallet::WalletError::MissingKeyError(address))?;
+        self.sign(client, private_key).await
+    }
+
+    /// Signs the operation with the key of a wallet address and sends it
+    pub async fn send(
+        self,
+        client: &RpcClient,
+        wallet: &Wallet,
+        address: Address,
+    ) -> SdkResult<OperationId> {
+        let operation = self.sign_with_wallet(client, wallet, address).await?;
+        let operation_id = operation.content.compute_id()?;
+        client.send_operations(vec![operation]).await?;
+        Ok(operation_id)
+    }
+
+    /// Signs the operation with the key of a wallet address, sends it,
+    /// and waits until it is included in a block or final, depending on `mode`
+    pub async fn send_and_wait(
+        self,
+        client: &RpcClient,
+        wallet: &Wallet,
+        address: Address,
+        mode: WaitMode,
+    ) -> SdkResult<OperationOutcome> {
+        let operation = self.sign_with_wallet(client, wallet, address).await?;
+        let operation_id = operation.content.compute_id()?;
+        let expire_period = operation.content.expire_period;
+        client.send_operations(vec![operation]).await?;
+        wait_for_operation(client, operation_id, expire_period, mode).await
+    }
+}
+
+/// Polls the node once per slot until the operation is included in a block,
+/// or in a final block, depending on the wait mode.
+///
+/// Fails with `SdkError::OperationExpired` if the operation expires without being included,
+/// and with `SdkError::OperationNotFinal` if it is included but still not final
+/// `get_finality_margin` periods after its expire period.
+pub async fn wait_for_operation(
+    client: &RpcClient,
+    operation_id: OperationId,
+    expire_period: u64,
+    mode: WaitMode,
+) -> SdkResult<OperationOutcome> {
+    let cfg = client.get_status().await?.config;
+    let interval = cfg
+        .t0
+        .checked_div_u64(cfg.thread_count as u64)?
+        .to_duration();
+    loop {
+        tokio::time::sleep(interval).await;
+        let info = match client.get_operations(vec![operation_id]).await {
+            Ok(operations_info) => operations_info.into_iter().reduce(|mut acc, info| {
+                acc.extend(&info);
+                acc
+            }),
+            Err(err) if err.is_transient() => continue,
+            Err(err) => return Err(err),
+        };
+        let block_id = info
+            .as_ref()
+            .and_then(|info| info.in_blocks.first().copied());
+        let is_final = info.as_ref().map_or(false, |info| info.is_final);
+        match block_id {
+            Some(block_id) if is_final || mode == WaitMode::Included => {
+                let slot = client
+                    .get_block(block_id)
+                    .await?
+                    .content
+                    .map(|content| content.block.header.content.slot);
+                let events = client
+                    .get_filtered_sc_output_event(EventFilter {
+                        original_operation_id: Some(operation_id),
+                        ..Default::default()
+                    })
+                    .await?;
+                return Ok(OperationOutcome {
+                    operation_id,
+                    is_final,
+                    block_id,
+                    slot,
+                    events,
+                });
+            }
+            Some(block_id) => {
+                if current_period(&cfg)? > expire_period + get_finality_margin(&cfg) {
+                    return Err(SdkError::OperationNotFinal(operation_id, block_id));
+                }
+            }
+            None => {
+                if current_period(&cfg)? > expire_period {
+                    return Err(SdkError::OperationExpired(operation_id, expire_period));
+                }
+            }
+        }
+    }
+}
+
+/// Period of the current wall-clock slot, 0 before genesis
+fn current_period(cfg: &CompactConfig) -> SdkResult<u64> {
+    Ok(get_current_latest_block_slot(
+        cfg.thread_count,
+        cfg.t0,
+        cfg.genesis_timestamp,
+        0, // clock compensation is zero
+    )?
+    .map_or(0, |slot| slot.period))
+}
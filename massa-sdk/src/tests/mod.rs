@@ -0,0 +1,153 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{
+    generate_private_key_in_thread, get_finality_margin, wait_for_operation, ClientConfig,
+    OperationBuilder, RpcClient, SdkError, WaitMode,
+};
+use massa_api::test_exports::{MockNode, MockNodeState};
+use massa_models::{Address, Amount, CompactConfig};
+use massa_signature::{derive_public_key, generate_random_private_key};
+use massa_time::MassaTime;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+/// serves the state on a free local port and returns a client connected to it
+fn start_mock_node(
+    state: MockNodeState,
+) -> Result<(MockNode, RpcClient, massa_api::StopHandle), Box<dyn Error>> {
+    let node = MockNode::new(state);
+    let stop_handle = node.clone().serve(&SocketAddr::from(([127, 0, 0, 1], 0)))?;
+    let client = RpcClient::from_url(&format!("http://{}", stop_handle.local_addr()))?;
+    Ok((node, client, stop_handle))
+}
+
+#[tokio::test]
+async fn test_get_status_from_mock_node() {
+    let state = MockNodeState::new(CompactConfig::default());
+    let node_id = state.status.node_id;
+    let (_, client, stop_handle) = start_mock_node(state).unwrap();
+
+    let status = client.get_status().await.unwrap();
+    assert_eq!(status.node_id, node_id);
+    assert_eq!(status.pool_stats.operation_count, 0);
+
+    stop_handle.stop();
+}
+
+#[tokio::test]
+async fn test_only_read_only_calls_fail_over() {
+    let (_, live_client, stop_handle) =
+        start_mock_node(MockNodeState::new(CompactConfig::default())).unwrap();
+    let live_url = format!("http://{}", stop_handle.local_addr());
+    // nothing listens on a port that was just released
+    let dead_url = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let config = ClientConfig {
+        max_retries: 0,
+        ..Default::default()
+    };
+
+    // read-only calls fail over to the live endpoint
+    let client = RpcClient::new(vec![dead_url.clone(), live_url.clone()], config.clone()).unwrap();
+    client.get_status().await.unwrap();
+
+    let private_key = generate_random_private_key();
+    let recipient = Address::from_public_key(&derive_public_key(&generate_random_private_key()));
+    let operation = OperationBuilder::transaction(recipient, Amount::from_str("1").unwrap())
+        .expire_period(10)
+        .sign(&live_client, &private_key)
+        .await
+        .unwrap();
+    // operations are sent once, to the endpoint in use
+    let client = RpcClient::new(vec![dead_url, live_url], config).unwrap();
+    assert!(client
+        .send_operations(vec![operation])
+        .await
+        .unwrap_err()
+        .is_transient());
+
+    stop_handle.stop();
+}
+
+#[tokio::test]
+async fn test_wait_for_scripted_inclusion() {
+    let mut state = MockNodeState::new(CompactConfig::default());
+    state.include_sent_operations = Some(false);
+    let (node, client, stop_handle) = start_mock_node(state).unwrap();
+
+    let private_key = generate_random_private_key();
+    let recipient = Address::from_public_key(&derive_public_key(&generate_random_private_key()));
+    let operation = OperationBuilder::transaction(recipient, Amount::from_str("1").unwrap())
+        .expire_period(10)
+        .sign(&client, &private_key)
+        .await
+        .unwrap();
+    let operation_ids = client.send_operations(vec![operation]).await.unwrap();
+    assert_eq!(operation_ids.len(), 1);
+
+    // the operation is in a block, but not final yet
+    let outcome = wait_for_operation(&client, operation_ids[0], 10, WaitMode::Included)
+        .await
+        .unwrap();
+    assert!(!outcome.is_final);
+
+    node.state()
+        .lock()
+        .unwrap()
+        .finalize_block(outcome.block_id)
+        .unwrap();
+    let outcome = wait_for_operation(&client, operation_ids[0], 10, WaitMode::Final)
+        .await
+        .unwrap();
+    assert!(outcome.is_final);
+    assert!(outcome.slot.is_some());
+
+    stop_handle.stop();
+}
+
+#[tokio::test]
+async fn test_wait_for_never_final_operation() {
+    let cfg = CompactConfig {
+        genesis_timestamp: MassaTime::now().unwrap(),
+        t0: 100.into(),
+        ..Default::default()
+    };
+    let mut state = MockNodeState::new(cfg);
+    state.include_sent_operations = Some(false);
+    let (_, client, stop_handle) = start_mock_node(state).unwrap();
+
+    let private_key = generate_random_private_key();
+    let recipient = Address::from_public_key(&derive_public_key(&generate_random_private_key()));
+    let operation = OperationBuilder::transaction(recipient, Amount::from_str("1").unwrap())
+        .expire_period(1)
+        .sign(&client, &private_key)
+        .await
+        .unwrap();
+    let operation_ids = client.send_operations(vec![operation]).await.unwrap();
+
+    // the operation stays in a block that never becomes final
+    let res = tokio::time::timeout(
+        std::time::Duration::from_millis(cfg.t0.to_millis() * (2 + get_finality_margin(&cfg)) * 2),
+        wait_for_operation(&client, operation_ids[0], 1, WaitMode::Final),
+    )
+    .await
+    .expect("waiting for a never final operation did not end");
+    assert!(matches!(res, Err(SdkError::OperationNotFinal(id, _)) if id == operation_ids[0]));
+
+    stop_handle.stop();
+}
+
+#[test]
+fn test_generate_private_key_in_thread() {
+    let thread_count = CompactConfig::default().thread_count;
+    for (thread, workers) in [(0, 1), (thread_count - 1, 4)] {
+        let key = generate_private_key_in_thread(thread, thread_count, workers).unwrap();
+        let address = Address::from_public_key(&derive_public_key(&key));
+        assert_eq!(address.get_thread(thread_count), thread);
+    }
+    assert!(generate_private_key_in_thread(thread_count, thread_count, 1).is_err());
+    assert!(generate_private_key_in_thread(0, thread_count, 0).is_err());
+}
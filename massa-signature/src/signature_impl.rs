@@ -792,6 +792,31 @@ pub fn verify_signature(
     Ok(SECP256K1.verify_schnorr(&signature.0, &message, &public_key.0)?)
 }
 
+/// Computes a secret shared between the owner of a `PrivateKey`
+/// and the owner of the private key associated to a `PublicKey` (ECDH).
+/// Both sides get the same secret, which nobody else can compute.
+///
+/// # Example
+///  ```
+/// # use massa_signature::{compute_shared_secret, derive_public_key, generate_random_private_key};
+/// let private_key_a = generate_random_private_key();
+/// let private_key_b = generate_random_private_key();
+/// let secret_a = compute_shared_secret(&private_key_a, &derive_public_key(&private_key_b)).unwrap();
+/// let secret_b = compute_shared_secret(&private_key_b, &derive_public_key(&private_key_a)).unwrap();
+/// assert_eq!(secret_a, secret_b);
+/// ```
+pub fn compute_shared_secret(
+    private_key: &PrivateKey,
+    public_key: &PublicKey,
+) -> Result<Hash, MassaSignatureError> {
+    // lift the x-only public key to a full point, and only keep the x coordinate of the result:
+    // the parity of the points does not change it, so both sides agree
+    let point = secp256k1::PublicKey::from_slice(&[&[0x02], &public_key.to_bytes()[..]].concat())?;
+    let secret_key = secp256k1::SecretKey::from_keypair(&private_key.0);
+    let shared_point = secp256k1::ecdh::shared_secret_point(&point, &secret_key);
+    Ok(Hash::compute_from(&shared_point[..32]))
+}
+
 /// Generate a random private key from a RNG.
 pub fn generate_random_private_key() -> PrivateKey {
     use secp256k1::rand::rngs::OsRng;
@@ -819,6 +844,25 @@ mod tests {
         assert!(verify_signature(&hash, &signature, &public_key).is_ok())
     }
 
+    #[test]
+    #[serial]
+    fn test_shared_secret() {
+        let private_key_a = generate_random_private_key();
+        let private_key_b = generate_random_private_key();
+        let public_key_a = derive_public_key(&private_key_a);
+        let public_key_b = derive_public_key(&private_key_b);
+        let secret = compute_shared_secret(&private_key_a, &public_key_b).unwrap();
+        assert_eq!(
+            secret,
+            compute_shared_secret(&private_key_b, &public_key_a).unwrap()
+        );
+        let other_public_key = derive_public_key(&generate_random_private_key());
+        assert_ne!(
+            secret,
+            compute_shared_secret(&private_key_a, &other_public_key).unwrap()
+        );
+    }
+
     #[test]
     #[serial]
     fn test_serde_private_key() {
@@ -0,0 +1,157 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Node side connection to a remote signer
+
+use crate::{
+    error::{SignerError, SignerResult},
+    protocol::{client_handshake, SignerMessage, SignerSession, SignerStream},
+    settings::{RemoteSignerSettings, SignerEndpoint},
+};
+use massa_models::{signed::Signable, BlockHeader, Endorsement};
+use massa_signature::{
+    derive_public_key, generate_random_private_key, verify_signature, PrivateKey, PublicKey,
+    Signature,
+};
+use tokio::{io::AsyncWriteExt, time::timeout};
+use tracing::{info, warn};
+
+/// Client of a remote signer.
+///
+/// The connection is opened lazily and re-opened on the next request after any failure.
+pub struct RemoteSigner {
+    /// configuration
+    settings: RemoteSignerSettings,
+    /// key the node authenticates with
+    auth_key: PrivateKey,
+    /// authenticated connection and its session, if any
+    connection: Option<(Box<dyn SignerStream>, SignerSession)>,
+}
+
+impl RemoteSigner {
+    /// Creates a client, loading the node authentication key or generating it if missing.
+    /// Does not connect to the signer yet.
+    pub async fn new(settings: RemoteSignerSettings) -> SignerResult<Self> {
+        let auth_key = load_or_generate_auth_key(&settings.auth_key_file).await?;
+        info!(
+            "remote signer authentication public key of this node: {}",
+            derive_public_key(&auth_key)
+        );
+        Ok(RemoteSigner {
+            settings,
+            auth_key,
+            connection: None,
+        })
+    }
+
+    /// Lists the staking public keys held by the signer
+    pub async fn get_public_keys(&mut self) -> SignerResult<Vec<PublicKey>> {
+        match self.request(SignerMessage::GetPublicKeys).await? {
+            SignerMessage::PublicKeys(keys) => Ok(keys),
+            msg => Err(unexpected_response(msg)),
+        }
+    }
+
+    /// Asks the signer to sign a block header with the key of its creator.
+    /// The returned signature is checked against the creator public key.
+    pub async fn sign_block_header(&mut self, header: &BlockHeader) -> SignerResult<Signature> {
+        let message = header.get_signature_message()?;
+        let signature = match self
+            .request(SignerMessage::SignBlockHeader(header.clone()))
+            .await?
+        {
+            SignerMessage::Signature(signature) => signature,
+            msg => return Err(unexpected_response(msg)),
+        };
+        verify_signature(&message, &signature, &header.creator)?;
+        Ok(signature)
+    }
+
+    /// Asks the signer to sign an endorsement with the key of its sender.
+    /// The returned signature is checked against the sender public key.
+    pub async fn sign_endorsement(&mut self, endorsement: &Endorsement) -> SignerResult<Signature> {
+        let message = endorsement.get_signature_message()?;
+        let signature = match self
+            .request(SignerMessage::SignEndorsement(endorsement.clone()))
+            .await?
+        {
+            SignerMessage::Signature(signature) => signature,
+            msg => return Err(unexpected_response(msg)),
+        };
+        verify_signature(&message, &signature, &endorsement.sender_public_key)?;
+        Ok(signature)
+    }
+
+    /// Sends a request and waits for the answer, (re)connecting first if needed.
+    /// On any failure the connection is dropped so that the next request reconnects.
+    async fn request(&mut self, request: SignerMessage) -> SignerResult<SignerMessage> {
+        let res = timeout(
+            self.settings.request_timeout.to_duration(),
+            self.connect_and_request(request),
+        )
+        .await
+        .unwrap_or(Err(SignerError::Timeout));
+        match res {
+            Ok(SignerMessage::Refused(reason)) => Err(SignerError::Refused(reason)),
+            Ok(msg) => Ok(msg),
+            Err(err) => {
+                if self.connection.take().is_some() {
+                    warn!("connection to the remote signer lost: {}", err);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn connect_and_request(&mut self, request: SignerMessage) -> SignerResult<SignerMessage> {
+        if self.connection.is_none() {
+            let mut stream = connect(&self.settings.endpoint).await?;
+            let session = client_handshake(
+                &mut stream,
+                &self.auth_key,
+                &self.settings.signer_public_key,
+            )
+            .await?;
+            info!("connected to remote signer {}", self.settings.endpoint);
+            self.connection = Some((stream, session));
+        }
+        // does not panic: set above
+        let (stream, session) = self.connection.as_mut().unwrap();
+        session.write_message(stream, &request).await?;
+        session.read_message(stream).await
+    }
+}
+
+/// Opens a raw connection to a signer endpoint
+async fn connect(endpoint: &SignerEndpoint) -> SignerResult<Box<dyn SignerStream>> {
+    match endpoint {
+        SignerEndpoint::Tcp(addr) => Ok(Box::new(tokio::net::TcpStream::connect(addr).await?)),
+        #[cfg(unix)]
+        SignerEndpoint::Unix(path) => Ok(Box::new(tokio::net::UnixStream::connect(path).await?)),
+        #[cfg(not(unix))]
+        SignerEndpoint::Unix(_) => Err(SignerError::InvalidEndpoint(
+            "unix sockets are not supported on this platform".into(),
+        )),
+    }
+}
+
+/// Loads an authentication key file, or generates it if the file does not exist
+pub async fn load_or_generate_auth_key(path: &std::path::Path) -> SignerResult<PrivateKey> {
+    if path.is_file() {
+        let key = tokio::fs::read_to_string(path).await?;
+        return Ok(PrivateKey::from_bs58_check(key.trim())?);
+    }
+    let key = generate_random_private_key();
+    // the key grants signing rights: keep it readable by its owner only
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    file.write_all(key.to_bs58_check().as_bytes()).await?;
+    file.flush().await?;
+    Ok(key)
+}
+
+fn unexpected_response(msg: SignerMessage) -> SignerError {
+    SignerError::ProtocolError(format!("unexpected signer response: {:?}", msg))
+}
@@ -0,0 +1,35 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! this file defines all possible remote signer error categories
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Remote signer result
+pub type SignerResult<T, E = SignerError> = core::result::Result<T, E>;
+
+/// Errors of the remote signer and of its clients.
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum SignerError {
+    /// io error: {0}
+    IOError(#[from] std::io::Error),
+    /// serde error: {0}
+    SerdeError(#[from] serde_json::Error),
+    /// models error: {0}
+    ModelsError(#[from] massa_models::ModelsError),
+    /// signature error: {0}
+    SignatureError(#[from] massa_signature::MassaSignatureError),
+    /// authentication error: {0}
+    AuthenticationError(String),
+    /// protocol error: {0}
+    ProtocolError(String),
+    /// signing request refused by the signer: {0}
+    Refused(String),
+    /// double signing attempt prevented: {0}
+    DoubleSigning(String),
+    /// signer request timed out
+    Timeout,
+    /// invalid endpoint: {0}
+    InvalidEndpoint(String),
+}
@@ -0,0 +1,207 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Anti double-signing rules enforced by the signer.
+//!
+//! The guard remembers the hash of every block header and endorsement it allowed to be signed,
+//! per signing key and slot:
+//! * a block header can only be signed once per creator and slot
+//! * an endorsement can only be signed once per sender, slot and index
+//!
+//! Asking again for the exact same content is allowed, so that a node can retry a request
+//! whose answer it did not receive. Only the last `history_periods` periods before the current
+//! wall-clock period are remembered: requests targeting older slots are refused since they could
+//! not be checked. Requests more than `max_future_periods` periods ahead of the current period
+//! are refused too, so that far-future requests cannot fill the history. The history is only ever
+//! pruned according to the wall clock, never according to the slots of the requests.
+
+use crate::error::{SignerError, SignerResult};
+use massa_hash::Hash;
+use massa_models::{BlockHeader, Endorsement, SerializeCompact, Slot};
+use massa_signature::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{btree_map, BTreeMap};
+use std::path::Path;
+
+/// Record of the block headers and endorsements already signed
+#[derive(Debug, Clone)]
+pub struct SigningGuard {
+    /// number of periods of history kept
+    history_periods: u64,
+    /// max number of periods a request can be ahead of the current period
+    max_future_periods: u64,
+    /// signed block headers: `(creator, slot) -> header hash`
+    block_headers: BTreeMap<(PublicKey, Slot), Hash>,
+    /// signed endorsements: `(sender, slot, index) -> endorsement hash`
+    endorsements: BTreeMap<(PublicKey, Slot, u32), Hash>,
+    /// requests for slots with a period strictly below that one are refused
+    min_period: u64,
+}
+
+/// Serializable form of a `SigningGuard`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SigningHistory {
+    block_headers: Vec<(PublicKey, Slot, Hash)>,
+    endorsements: Vec<(PublicKey, Slot, u32, Hash)>,
+    min_period: u64,
+}
+
+impl SigningGuard {
+    /// Creates an empty guard
+    pub fn new(history_periods: u64, max_future_periods: u64) -> Self {
+        SigningGuard {
+            history_periods,
+            max_future_periods,
+            block_headers: Default::default(),
+            endorsements: Default::default(),
+            min_period: 0,
+        }
+    }
+
+    /// Loads the guard from its history file, or creates an empty one if the file does not exist
+    pub fn load(path: &Path, history_periods: u64, max_future_periods: u64) -> SignerResult<Self> {
+        let mut guard = SigningGuard::new(history_periods, max_future_periods);
+        if !path.is_file() {
+            return Ok(guard);
+        }
+        let history: SigningHistory = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        guard.block_headers = history
+            .block_headers
+            .into_iter()
+            .map(|(creator, slot, hash)| ((creator, slot), hash))
+            .collect();
+        guard.endorsements = history
+            .endorsements
+            .into_iter()
+            .map(|(sender, slot, index, hash)| ((sender, slot, index), hash))
+            .collect();
+        guard.min_period = history.min_period;
+        Ok(guard)
+    }
+
+    /// Writes the guard to its history file.
+    /// Must succeed before a signature allowed by the guard is released.
+    pub fn save(&self, path: &Path) -> SignerResult<()> {
+        let history = SigningHistory {
+            block_headers: self
+                .block_headers
+                .iter()
+                .map(|((creator, slot), hash)| (*creator, *slot, *hash))
+                .collect(),
+            endorsements: self
+                .endorsements
+                .iter()
+                .map(|((sender, slot, index), hash)| (*sender, *slot, *index, *hash))
+                .collect(),
+            min_period: self.min_period,
+        };
+        // write to a temporary file first so that a crash never leaves a truncated history
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec(&history)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Checks that a block header can be signed, and records it
+    ///
+    /// # Arguments
+    /// * `header`: header to sign
+    /// * `current_period`: period of the current wall-clock slot
+    pub fn allow_block_header(
+        &mut self,
+        header: &BlockHeader,
+        current_period: u64,
+    ) -> SignerResult<()> {
+        self.prune(current_period);
+        self.check_period(header.slot, current_period)?;
+        let hash = Hash::compute_from(&header.to_bytes_compact()?);
+        check_and_record(
+            self.block_headers.entry((header.creator, header.slot)),
+            hash,
+            || {
+                format!(
+                    "a different block header was already signed for creator {} at slot {}",
+                    header.creator, header.slot
+                )
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Checks that an endorsement can be signed, and records it
+    ///
+    /// # Arguments
+    /// * `endorsement`: endorsement to sign
+    /// * `current_period`: period of the current wall-clock slot
+    pub fn allow_endorsement(
+        &mut self,
+        endorsement: &Endorsement,
+        current_period: u64,
+    ) -> SignerResult<()> {
+        self.prune(current_period);
+        self.check_period(endorsement.slot, current_period)?;
+        let hash = Hash::compute_from(&endorsement.to_bytes_compact()?);
+        check_and_record(
+            self.endorsements.entry((
+                endorsement.sender_public_key,
+                endorsement.slot,
+                endorsement.index,
+            )),
+            hash,
+            || {
+                format!(
+                    "a different endorsement was already signed for sender {} at slot {} index {}",
+                    endorsement.sender_public_key, endorsement.slot, endorsement.index
+                )
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Refuses slots that are older than the remembered history,
+    /// or too far ahead of the current period
+    fn check_period(&self, slot: Slot, current_period: u64) -> SignerResult<()> {
+        if slot.period < self.min_period {
+            return Err(SignerError::Refused(format!(
+                "slot {} is older than the signing history (starts at period {})",
+                slot, self.min_period
+            )));
+        }
+        let max_period = current_period.saturating_add(self.max_future_periods);
+        if slot.period > max_period {
+            return Err(SignerError::Refused(format!(
+                "slot {} is too far in the future (current period is {}, at most {} periods ahead are allowed)",
+                slot, current_period, self.max_future_periods
+            )));
+        }
+        Ok(())
+    }
+
+    /// Forgets the entries that are more than `history_periods` periods older than `current_period`
+    fn prune(&mut self, current_period: u64) {
+        let min_period = current_period.saturating_sub(self.history_periods);
+        if min_period <= self.min_period {
+            return;
+        }
+        self.min_period = min_period;
+        self.block_headers
+            .retain(|(_, slot), _| slot.period >= min_period);
+        self.endorsements
+            .retain(|(_, slot, _), _| slot.period >= min_period);
+    }
+}
+
+/// Allows signing if nothing was signed for that entry yet, or if the same content was signed
+fn check_and_record<K: Ord, F: FnOnce() -> String>(
+    entry: btree_map::Entry<K, Hash>,
+    hash: Hash,
+    conflict_message: F,
+) -> SignerResult<()> {
+    match entry {
+        btree_map::Entry::Vacant(v) => {
+            v.insert(hash);
+            Ok(())
+        }
+        btree_map::Entry::Occupied(o) if *o.get() == hash => Ok(()),
+        btree_map::Entry::Occupied(_) => Err(SignerError::DoubleSigning(conflict_message())),
+    }
+}
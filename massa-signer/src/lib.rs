@@ -0,0 +1,52 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! # General description
+//!
+//! Remote signing of block headers and endorsements, so that staking private keys
+//! do not have to live on the node host.
+//!
+//! The signer is a separate process (`massa-signer` binary) holding the staking keys.
+//! Nodes connect to it over TCP or a Unix socket, authenticate mutually with it
+//! (see `protocol.rs`), and ask it to sign the block headers and endorsements
+//! they create for the keys it holds.
+//!
+//! The signer refuses to sign two different block headers for the same creator and slot,
+//! or two different endorsements for the same sender, slot and index (see `guard.rs`).
+//! What it signed is persisted before each signature is released, so that the rule
+//! also holds across restarts.
+//!
+//! # Architecture
+//!
+//! ## protocol.rs
+//! Messages, framing, authentication handshake and message integrity.
+//!
+//! ## client.rs
+//! `RemoteSigner`: node side connection used by consensus to request signatures.
+//!
+//! ## guard.rs
+//! `SigningGuard`: anti double-signing record.
+//!
+//! ## server.rs
+//! Signer side request processing.
+
+#![warn(missing_docs)]
+
+mod client;
+mod error;
+mod guard;
+mod protocol;
+mod server;
+mod settings;
+
+pub use client::{load_or_generate_auth_key, RemoteSigner};
+pub use error::{SignerError, SignerResult};
+pub use guard::SigningGuard;
+pub use protocol::{
+    client_handshake, read_message, server_handshake, write_message, SignerMessage, SignerSession,
+    SignerStream,
+};
+pub use server::{run_signer_server, SignerState};
+pub use settings::{RemoteSignerSettings, SignerEndpoint, SignerSettings};
+
+#[cfg(test)]
+mod tests;
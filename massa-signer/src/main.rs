@@ -0,0 +1,65 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Remote signer process: holds staking keys and signs block headers and endorsements
+//! for authenticated nodes, refusing double-signing.
+
+#![warn(missing_docs)]
+use massa_models::{
+    constants::build_massa_settings, init_serialization_context, SerializationContext,
+};
+use massa_signature::{derive_public_key, PrivateKey};
+use massa_signer::{load_or_generate_auth_key, run_signer_server, SignerSettings, SignerState};
+use tracing::{error, info};
+use tracing_subscriber::filter::{filter_fn, LevelFilter};
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: SignerSettings = build_massa_settings("massa-signer", "MASSA_SIGNER");
+}
+
+#[tokio::main]
+async fn main() {
+    use tracing_subscriber::prelude::*;
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_filter(match SETTINGS.logging_level {
+                    4 => LevelFilter::TRACE,
+                    3 => LevelFilter::DEBUG,
+                    2 => LevelFilter::INFO,
+                    1 => LevelFilter::WARN,
+                    _ => LevelFilter::ERROR,
+                })
+                .with_filter(filter_fn(|metadata| {
+                    metadata.target().starts_with("massa") // ignore non-massa logs
+                })),
+        )
+        .init();
+
+    // required to serialize the block headers and endorsements to sign
+    init_serialization_context(SerializationContext::default());
+
+    let auth_key = load_or_generate_auth_key(&SETTINGS.auth_key_file)
+        .await
+        .expect("could not load the signer authentication key");
+    info!(
+        "signer authentication public key: {}",
+        derive_public_key(&auth_key)
+    );
+
+    let staking_keys = serde_json::from_str::<Vec<PrivateKey>>(
+        &tokio::fs::read_to_string(&SETTINGS.staking_keys_path)
+            .await
+            .expect("could not read the staking keys file"),
+    )
+    .expect("could not parse the staking keys file");
+
+    let state = SignerState::new(&SETTINGS, auth_key, staking_keys)
+        .expect("could not initialize the signer");
+    for (address, _) in state.staking_keys.iter() {
+        info!("signing for address {}", address);
+    }
+
+    if let Err(err) = run_signer_server(&SETTINGS.bind, state).await {
+        error!("signer stopped: {}", err);
+    }
+}
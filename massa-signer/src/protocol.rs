@@ -0,0 +1,399 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Messages exchanged between a node and its remote signer, their framing,
+//! and the mutual authentication handshake.
+//!
+//! Every message is a big-endian `u32` length followed by the JSON encoding of a `SignerMessage`.
+//!
+//! Handshake:
+//! * the node sends `Hello` with its public key and a random nonce
+//! * the signer checks that the node is authorized, and answers `Challenge`
+//!   with its own public key, a random nonce and its signature of the node nonce
+//! * the node checks the signer identity and signature, and answers `ChallengeResponse`
+//!   with its signature of the signer nonce
+//! * the signer checks that signature and answers `Ready`
+//!
+//! Nonces are signed with a role-specific prefix so that a signature produced by one side
+//! can never be replayed as a signature of the other side.
+//!
+//! Once authenticated, both sides derive a session key from the ECDH secret of their
+//! authentication keys and from the two nonces. Every following message carries a keyed
+//! BLAKE3 MAC of its direction, its sequence number and its content, so that frames can
+//! neither be forged, altered, replayed, reordered nor reflected back to their sender.
+
+use crate::error::{SignerError, SignerResult};
+use massa_hash::Hash;
+use massa_models::{BlockHeader, Endorsement};
+use massa_signature::{
+    compute_shared_secret, derive_public_key, sign, verify_signature, PrivateKey, PublicKey,
+    Signature,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Max size of a serialized message
+pub const MAX_MESSAGE_SIZE: u32 = 1_048_576;
+
+/// prefix of the node nonce signed by the signer
+const SIGNER_AUTH_PREFIX: &[u8] = b"massa-signer-auth-signer";
+/// prefix of the signer nonce signed by the node
+const NODE_AUTH_PREFIX: &[u8] = b"massa-signer-auth-node";
+/// prefix of the session key derivation
+const SESSION_KEY_PREFIX: &[u8] = b"massa-signer-session";
+/// prefix of the MAC of the frames sent by the node
+const NODE_FRAME_PREFIX: &[u8] = b"massa-signer-frame-node";
+/// prefix of the MAC of the frames sent by the signer
+const SIGNER_FRAME_PREFIX: &[u8] = b"massa-signer-frame-signer";
+/// size of the MAC of a frame
+const MAC_SIZE: usize = 32;
+
+/// Messages exchanged between a node and its signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SignerMessage {
+    /// node → signer: start of the handshake
+    Hello {
+        /// node authentication public key
+        public_key: PublicKey,
+        /// random nonce the signer has to sign
+        nonce: Hash,
+    },
+    /// signer → node: signer identity proof and challenge
+    Challenge {
+        /// signer authentication public key
+        public_key: PublicKey,
+        /// random nonce the node has to sign
+        nonce: Hash,
+        /// signature of the node nonce
+        signature: Signature,
+    },
+    /// node → signer: node identity proof
+    ChallengeResponse {
+        /// signature of the signer nonce
+        signature: Signature,
+    },
+    /// signer → node: handshake completed
+    Ready,
+    /// node → signer: list the staking public keys held by the signer
+    GetPublicKeys,
+    /// signer → node: staking public keys held by the signer
+    PublicKeys(Vec<PublicKey>),
+    /// node → signer: sign a block header with the key of its creator
+    SignBlockHeader(BlockHeader),
+    /// node → signer: sign an endorsement with the key of its sender
+    SignEndorsement(Endorsement),
+    /// signer → node: requested signature
+    Signature(Signature),
+    /// signer → node: request refused
+    Refused(String),
+}
+
+/// Any stream a signer connection can run on
+pub trait SignerStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SignerStream for T {}
+
+/// Writes a length-prefixed message to the stream, without authentication.
+/// Only used during the handshake.
+pub async fn write_message<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    message: &SignerMessage,
+) -> SignerResult<()> {
+    let bytes = serde_json::to_vec(message)?;
+    let len = frame_len(bytes.len())?;
+    stream.write_u32(len).await?;
+    stream.write_all(&bytes).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Reads a length-prefixed message from the stream, without authentication.
+/// Only used during the handshake.
+pub async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> SignerResult<SignerMessage> {
+    let bytes = read_frame(stream).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Integrity protection of the messages of an authenticated connection.
+///
+/// Obtained at the end of the handshake, it must be used for all the following messages.
+#[derive(Clone)]
+pub struct SignerSession {
+    /// key of the frame MACs
+    key: [u8; 32],
+    /// MAC prefix of the frames sent by this side
+    send_prefix: &'static [u8],
+    /// MAC prefix of the frames received by this side
+    receive_prefix: &'static [u8],
+    /// sequence number of the next sent frame
+    sent_count: u64,
+    /// sequence number of the next received frame
+    received_count: u64,
+}
+
+impl SignerSession {
+    /// Derives the session of one side of a connection
+    ///
+    /// # Arguments
+    /// * `auth_key`: authentication key of this side
+    /// * `peer_public_key`: authentication public key of the other side
+    /// * `node_nonce`, `signer_nonce`: the nonces exchanged during the handshake
+    /// * `is_node`: whether this side is the node
+    fn new(
+        auth_key: &PrivateKey,
+        peer_public_key: &PublicKey,
+        node_nonce: &Hash,
+        signer_nonce: &Hash,
+        is_node: bool,
+    ) -> SignerResult<Self> {
+        let shared_secret = compute_shared_secret(auth_key, peer_public_key)?;
+        let key = Hash::compute_from(
+            &[
+                SESSION_KEY_PREFIX,
+                &shared_secret.to_bytes()[..],
+                &node_nonce.to_bytes()[..],
+                &signer_nonce.to_bytes()[..],
+            ]
+            .concat(),
+        )
+        .into_bytes();
+        let (send_prefix, receive_prefix) = if is_node {
+            (NODE_FRAME_PREFIX, SIGNER_FRAME_PREFIX)
+        } else {
+            (SIGNER_FRAME_PREFIX, NODE_FRAME_PREFIX)
+        };
+        Ok(SignerSession {
+            key,
+            send_prefix,
+            receive_prefix,
+            sent_count: 0,
+            received_count: 0,
+        })
+    }
+
+    /// MAC of a frame
+    fn mac(&self, prefix: &[u8], count: u64, bytes: &[u8]) -> blake3::Hash {
+        blake3::keyed_hash(
+            &self.key,
+            &[prefix, &count.to_be_bytes()[..], bytes].concat(),
+        )
+    }
+
+    /// Writes a length-prefixed message followed by its MAC to the stream
+    pub async fn write_message<S: AsyncWrite + Unpin>(
+        &mut self,
+        stream: &mut S,
+        message: &SignerMessage,
+    ) -> SignerResult<()> {
+        let bytes = serde_json::to_vec(message)?;
+        let len = frame_len(bytes.len())?;
+        let mac = self.mac(self.send_prefix, self.sent_count, &bytes);
+        self.sent_count += 1;
+        stream.write_u32(len).await?;
+        stream.write_all(&bytes).await?;
+        stream.write_all(mac.as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    /// Reads a length-prefixed message followed by its MAC from the stream,
+    /// failing if the MAC does not match
+    pub async fn read_message<S: AsyncRead + Unpin>(
+        &mut self,
+        stream: &mut S,
+    ) -> SignerResult<SignerMessage> {
+        let bytes = read_frame(stream).await?;
+        let mut mac = [0u8; MAC_SIZE];
+        stream.read_exact(&mut mac).await?;
+        // blake3::Hash equality is constant-time
+        if self.mac(self.receive_prefix, self.received_count, &bytes) != blake3::Hash::from(mac) {
+            return Err(SignerError::AuthenticationError(
+                "invalid message authentication code".into(),
+            ));
+        }
+        self.received_count += 1;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// Checks the size of a frame
+fn frame_len(len: usize) -> SignerResult<u32> {
+    len.try_into()
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| SignerError::ProtocolError("message too large".into()))
+}
+
+/// Reads the length-prefixed content of a frame
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> SignerResult<Vec<u8>> {
+    let len = stream.read_u32().await?;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(SignerError::ProtocolError(format!(
+            "message of {} bytes exceeds the {} bytes limit",
+            len, MAX_MESSAGE_SIZE
+        )));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    stream.read_exact(&mut bytes).await?;
+    Ok(bytes)
+}
+
+/// Hash of a nonce prefixed by the role of the signing side
+fn auth_message(prefix: &[u8], nonce: &Hash) -> Hash {
+    Hash::compute_from(&[prefix, &nonce.to_bytes()[..]].concat())
+}
+
+/// Random nonce
+fn generate_nonce() -> Hash {
+    Hash::compute_from(&rand::random::<[u8; 32]>())
+}
+
+/// Node side of the handshake.
+///
+/// # Arguments
+/// * `stream`: fresh connection to the signer
+/// * `auth_key`: key the node authenticates with
+/// * `signer_public_key`: expected signer identity
+///
+/// # Returns
+/// The session protecting the following messages
+pub async fn client_handshake<S: SignerStream>(
+    stream: &mut S,
+    auth_key: &PrivateKey,
+    signer_public_key: &PublicKey,
+) -> SignerResult<SignerSession> {
+    let node_nonce = generate_nonce();
+    write_message(
+        stream,
+        &SignerMessage::Hello {
+            public_key: derive_public_key(auth_key),
+            nonce: node_nonce,
+        },
+    )
+    .await?;
+
+    let signer_nonce = match read_message(stream).await? {
+        SignerMessage::Challenge {
+            public_key,
+            nonce,
+            signature,
+        } => {
+            if public_key != *signer_public_key {
+                return Err(SignerError::AuthenticationError(format!(
+                    "unexpected signer public key {}",
+                    public_key
+                )));
+            }
+            verify_signature(
+                &auth_message(SIGNER_AUTH_PREFIX, &node_nonce),
+                &signature,
+                &public_key,
+            )
+            .map_err(|err| {
+                SignerError::AuthenticationError(format!("invalid signer signature: {}", err))
+            })?;
+            nonce
+        }
+        SignerMessage::Refused(reason) => return Err(SignerError::AuthenticationError(reason)),
+        msg => {
+            return Err(SignerError::ProtocolError(format!(
+                "unexpected handshake message: {:?}",
+                msg
+            )))
+        }
+    };
+
+    write_message(
+        stream,
+        &SignerMessage::ChallengeResponse {
+            signature: sign(&auth_message(NODE_AUTH_PREFIX, &signer_nonce), auth_key)?,
+        },
+    )
+    .await?;
+
+    match read_message(stream).await? {
+        SignerMessage::Ready => SignerSession::new(
+            auth_key,
+            signer_public_key,
+            &node_nonce,
+            &signer_nonce,
+            true,
+        ),
+        SignerMessage::Refused(reason) => Err(SignerError::AuthenticationError(reason)),
+        msg => Err(SignerError::ProtocolError(format!(
+            "unexpected handshake message: {:?}",
+            msg
+        ))),
+    }
+}
+
+/// Signer side of the handshake.
+///
+/// # Arguments
+/// * `stream`: fresh connection from a node
+/// * `auth_key`: key the signer authenticates with
+/// * `authorized_nodes`: public keys of the nodes allowed to connect
+///
+/// # Returns
+/// The authentication public key of the connected node,
+/// and the session protecting the following messages
+pub async fn server_handshake<S: SignerStream>(
+    stream: &mut S,
+    auth_key: &PrivateKey,
+    authorized_nodes: &[PublicKey],
+) -> SignerResult<(PublicKey, SignerSession)> {
+    let (node_public_key, node_nonce) = match read_message(stream).await? {
+        SignerMessage::Hello { public_key, nonce } => (public_key, nonce),
+        msg => {
+            return Err(SignerError::ProtocolError(format!(
+                "unexpected handshake message: {:?}",
+                msg
+            )))
+        }
+    };
+    if !authorized_nodes.contains(&node_public_key) {
+        let reason = format!("node {} is not authorized", node_public_key);
+        write_message(stream, &SignerMessage::Refused(reason.clone())).await?;
+        return Err(SignerError::AuthenticationError(reason));
+    }
+
+    let signer_nonce = generate_nonce();
+    write_message(
+        stream,
+        &SignerMessage::Challenge {
+            public_key: derive_public_key(auth_key),
+            nonce: signer_nonce,
+            signature: sign(&auth_message(SIGNER_AUTH_PREFIX, &node_nonce), auth_key)?,
+        },
+    )
+    .await?;
+
+    match read_message(stream).await? {
+        SignerMessage::ChallengeResponse { signature } => {
+            if let Err(err) = verify_signature(
+                &auth_message(NODE_AUTH_PREFIX, &signer_nonce),
+                &signature,
+                &node_public_key,
+            ) {
+                let reason = format!("invalid node signature: {}", err);
+                write_message(stream, &SignerMessage::Refused(reason.clone())).await?;
+                return Err(SignerError::AuthenticationError(reason));
+            }
+        }
+        msg => {
+            return Err(SignerError::ProtocolError(format!(
+                "unexpected handshake message: {:?}",
+                msg
+            )))
+        }
+    }
+
+    let session = SignerSession::new(
+        auth_key,
+        &node_public_key,
+        &node_nonce,
+        &signer_nonce,
+        false,
+    )?;
+    write_message(stream, &SignerMessage::Ready).await?;
+    Ok((node_public_key, session))
+}
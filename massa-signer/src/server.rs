@@ -0,0 +1,203 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Signer side: serves signing requests from authenticated nodes
+
+use crate::{
+    error::{SignerError, SignerResult},
+    guard::SigningGuard,
+    protocol::{server_handshake, SignerMessage, SignerStream},
+    settings::{SignerEndpoint, SignerSettings},
+};
+use massa_models::{
+    constants::{GENESIS_TIMESTAMP, T0, THREAD_COUNT},
+    prehash::Map,
+    signed::Signable,
+    timeslots::get_current_latest_block_slot,
+    Address,
+};
+use massa_signature::{derive_public_key, sign, PrivateKey, PublicKey, Signature};
+use std::{path::PathBuf, sync::Arc};
+use tokio::{sync::Mutex, time::timeout};
+use tracing::{debug, info, warn};
+
+/// State shared by all the connections of a signer
+pub struct SignerState {
+    /// key the signer authenticates with
+    pub auth_key: PrivateKey,
+    /// nodes allowed to connect
+    pub authorized_nodes: Vec<PublicKey>,
+    /// staking keys indexed by public key
+    pub staking_keys: Map<Address, (PublicKey, PrivateKey)>,
+    /// anti double-signing guard, shared by all connections
+    pub guard: Mutex<SigningGuard>,
+    /// where the guard is saved after every signature
+    pub signing_history_path: PathBuf,
+    /// max duration of the authentication handshake
+    pub handshake_timeout: std::time::Duration,
+}
+
+impl SignerState {
+    /// Builds the signer state from its settings
+    pub fn new(
+        settings: &SignerSettings,
+        auth_key: PrivateKey,
+        staking_keys: Vec<PrivateKey>,
+    ) -> SignerResult<Self> {
+        Ok(SignerState {
+            auth_key,
+            authorized_nodes: settings.authorized_nodes.clone(),
+            staking_keys: staking_keys
+                .into_iter()
+                .map(|private_key| {
+                    let public_key = derive_public_key(&private_key);
+                    (
+                        Address::from_public_key(&public_key),
+                        (public_key, private_key),
+                    )
+                })
+                .collect(),
+            guard: Mutex::new(SigningGuard::load(
+                &settings.signing_history_path,
+                settings.signing_history_periods,
+                settings.signing_max_future_periods,
+            )?),
+            signing_history_path: settings.signing_history_path.clone(),
+            handshake_timeout: settings.handshake_timeout.to_duration(),
+        })
+    }
+
+    /// Staking private key associated to a public key
+    fn get_staking_key(&self, public_key: &PublicKey) -> SignerResult<&PrivateKey> {
+        self.staking_keys
+            .get(&Address::from_public_key(public_key))
+            .map(|(_, private_key)| private_key)
+            .ok_or_else(|| SignerError::Refused(format!("unknown staking key {}", public_key)))
+    }
+
+    /// Answers a request from an authenticated node
+    async fn process_request(&self, request: SignerMessage) -> SignerResult<SignerMessage> {
+        match request {
+            SignerMessage::GetPublicKeys => Ok(SignerMessage::PublicKeys(
+                self.staking_keys.values().map(|(pk, _)| *pk).collect(),
+            )),
+            SignerMessage::SignBlockHeader(header) => {
+                let private_key = self.get_staking_key(&header.creator)?;
+                let signature = sign(&header.get_signature_message()?, private_key)?;
+                let current_period = current_period()?;
+                self.record(
+                    |guard| guard.allow_block_header(&header, current_period),
+                    signature,
+                )
+                .await
+            }
+            SignerMessage::SignEndorsement(endorsement) => {
+                let private_key = self.get_staking_key(&endorsement.sender_public_key)?;
+                let signature = sign(&endorsement.get_signature_message()?, private_key)?;
+                let current_period = current_period()?;
+                self.record(
+                    |guard| guard.allow_endorsement(&endorsement, current_period),
+                    signature,
+                )
+                .await
+            }
+            msg => Err(SignerError::ProtocolError(format!(
+                "unexpected request: {:?}",
+                msg
+            ))),
+        }
+    }
+
+    /// Checks the request against the guard and persists the guard
+    /// before releasing the signature
+    async fn record<F: FnOnce(&mut SigningGuard) -> SignerResult<()>>(
+        &self,
+        check: F,
+        signature: Signature,
+    ) -> SignerResult<SignerMessage> {
+        let mut guard = self.guard.lock().await;
+        let mut updated_guard = guard.clone();
+        check(&mut updated_guard)?;
+        updated_guard.save(&self.signing_history_path)?;
+        *guard = updated_guard;
+        Ok(SignerMessage::Signature(signature))
+    }
+}
+
+/// Period of the current wall-clock slot, 0 before genesis
+fn current_period() -> SignerResult<u64> {
+    Ok(
+        get_current_latest_block_slot(THREAD_COUNT, *T0, *GENESIS_TIMESTAMP, 0)?
+            .map_or(0, |slot| slot.period),
+    )
+}
+
+/// Serves one node connection until it closes
+async fn serve_connection<S: SignerStream>(state: Arc<SignerState>, mut stream: S) {
+    let (node, mut session) = match timeout(
+        state.handshake_timeout,
+        server_handshake(&mut stream, &state.auth_key, &state.authorized_nodes),
+    )
+    .await
+    .unwrap_or(Err(SignerError::Timeout))
+    {
+        Ok(res) => res,
+        Err(err) => {
+            warn!("node authentication failed: {}", err);
+            return;
+        }
+    };
+    info!("node {} connected", node);
+    loop {
+        let request = match session.read_message(&mut stream).await {
+            Ok(request) => request,
+            Err(err) => {
+                debug!("connection with node {} closed: {}", node, err);
+                break;
+            }
+        };
+        let response = match state.process_request(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("refused request from node {}: {}", node, err);
+                SignerMessage::Refused(err.to_string())
+            }
+        };
+        if let Err(err) = session.write_message(&mut stream, &response).await {
+            debug!("connection with node {} closed: {}", node, err);
+            break;
+        }
+    }
+    info!("node {} disconnected", node);
+}
+
+/// Accepts node connections on the given endpoint and serves them until the process stops
+pub async fn run_signer_server(endpoint: &SignerEndpoint, state: SignerState) -> SignerResult<()> {
+    let state = Arc::new(state);
+    info!("signer listening on {}", endpoint);
+    match endpoint {
+        SignerEndpoint::Tcp(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            loop {
+                let (stream, remote_addr) = listener.accept().await?;
+                debug!("incoming connection from {}", remote_addr);
+                tokio::spawn(serve_connection(state.clone(), stream));
+            }
+        }
+        #[cfg(unix)]
+        SignerEndpoint::Unix(path) => {
+            // remove the socket file left by a previous run
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            let listener = tokio::net::UnixListener::bind(path)?;
+            loop {
+                let (stream, _) = listener.accept().await?;
+                tokio::spawn(serve_connection(state.clone(), stream));
+            }
+        }
+        #[cfg(not(unix))]
+        SignerEndpoint::Unix(_) => Err(SignerError::InvalidEndpoint(
+            "unix sockets are not supported on this platform".into(),
+        )),
+    }
+}
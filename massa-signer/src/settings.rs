@@ -0,0 +1,103 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Settings of the remote signer, on the signer side and on the node side
+
+use crate::error::SignerError;
+use massa_signature::PublicKey;
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, net::SocketAddr, path::PathBuf, str::FromStr};
+
+/// Address of a remote signer.
+///
+/// Written `tcp:<ip>:<port>` or `unix:<socket path>` in configuration files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SignerEndpoint {
+    /// TCP socket address
+    Tcp(SocketAddr),
+    /// Unix domain socket path
+    Unix(PathBuf),
+}
+
+impl FromStr for SignerEndpoint {
+    type Err = SignerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tcp:") {
+            addr.parse()
+                .map(SignerEndpoint::Tcp)
+                .map_err(|err| SignerError::InvalidEndpoint(format!("{}: {}", s, err)))
+        } else if let Some(path) = s.strip_prefix("unix:") {
+            Ok(SignerEndpoint::Unix(PathBuf::from(path)))
+        } else {
+            Err(SignerError::InvalidEndpoint(format!(
+                "{}: expected tcp:<ip>:<port> or unix:<path>",
+                s
+            )))
+        }
+    }
+}
+
+impl TryFrom<String> for SignerEndpoint {
+    type Error = SignerError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SignerEndpoint> for String {
+    fn from(endpoint: SignerEndpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+impl Display for SignerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerEndpoint::Tcp(addr) => write!(f, "tcp:{}", addr),
+            SignerEndpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Node side configuration of the remote signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteSignerSettings {
+    /// where the signer listens
+    pub endpoint: SignerEndpoint,
+    /// path to the key the node authenticates itself with (not a staking key).
+    /// Generated on first start if missing.
+    pub auth_key_file: PathBuf,
+    /// public key the signer is expected to authenticate with
+    pub signer_public_key: PublicKey,
+    /// max time to wait for the signer to answer a request
+    pub request_timeout: MassaTime,
+}
+
+/// Signer side configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignerSettings {
+    /// logging level
+    pub logging_level: usize,
+    /// where to listen for nodes
+    pub bind: SignerEndpoint,
+    /// path to the key the signer authenticates itself with (not a staking key).
+    /// Generated on first start if missing.
+    pub auth_key_file: PathBuf,
+    /// public keys of the nodes allowed to request signatures
+    pub authorized_nodes: Vec<PublicKey>,
+    /// path to the staking private keys held by the signer
+    pub staking_keys_path: PathBuf,
+    /// path to the record of what has already been signed
+    pub signing_history_path: PathBuf,
+    /// number of periods of signing history kept to prevent double signing.
+    /// Requests for slots older than that are refused.
+    pub signing_history_periods: u64,
+    /// max number of periods a signing request can be ahead of the current wall-clock period.
+    /// Requests for slots further in the future are refused.
+    pub signing_max_future_periods: u64,
+    /// max time to wait for a node to complete the authentication handshake
+    pub handshake_timeout: MassaTime,
+}
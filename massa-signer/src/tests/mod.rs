@@ -0,0 +1,234 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{
+    client_handshake, load_or_generate_auth_key, server_handshake, write_message, SignerError,
+    SignerMessage, SigningGuard,
+};
+use massa_hash::Hash;
+use massa_models::{BlockHeader, Endorsement, Slot};
+use massa_signature::{derive_public_key, generate_random_private_key, PublicKey};
+use tokio::io::AsyncWriteExt;
+
+fn header(creator: PublicKey, slot: Slot, root: &[u8]) -> BlockHeader {
+    BlockHeader {
+        creator,
+        slot,
+        parents: Vec::new(),
+        operation_merkle_root: Hash::compute_from(root),
+        endorsements: Vec::new(),
+    }
+}
+
+#[test]
+fn test_guard_refuses_double_block_signing() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    let creator = derive_public_key(&generate_random_private_key());
+    let mut guard = SigningGuard::new(10, 2);
+
+    let first = header(creator, Slot::new(5, 1), b"first");
+    guard.allow_block_header(&first, 5).unwrap();
+    // the same header can be signed again
+    guard.allow_block_header(&first, 5).unwrap();
+    // but not a different header at the same slot
+    assert!(matches!(
+        guard.allow_block_header(&header(creator, Slot::new(5, 1), b"second"), 5),
+        Err(SignerError::DoubleSigning(_))
+    ));
+    // another slot is fine
+    guard
+        .allow_block_header(&header(creator, Slot::new(5, 2), b"second"), 5)
+        .unwrap();
+    // another creator is fine
+    let other_creator = derive_public_key(&generate_random_private_key());
+    guard
+        .allow_block_header(&header(other_creator, Slot::new(5, 1), b"second"), 5)
+        .unwrap();
+}
+
+#[test]
+fn test_guard_refuses_double_endorsement_and_old_slots() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    let sender = derive_public_key(&generate_random_private_key());
+    let endorsement = |period, index, block: &[u8]| Endorsement {
+        sender_public_key: sender,
+        slot: Slot::new(period, 0),
+        index,
+        endorsed_block: massa_models::BlockId(Hash::compute_from(block)),
+    };
+    let mut guard = SigningGuard::new(10, 2);
+
+    guard
+        .allow_endorsement(&endorsement(3, 0, b"a"), 3)
+        .unwrap();
+    guard
+        .allow_endorsement(&endorsement(3, 1, b"a"), 3)
+        .unwrap();
+    assert!(matches!(
+        guard.allow_endorsement(&endorsement(3, 0, b"b"), 3),
+        Err(SignerError::DoubleSigning(_))
+    ));
+
+    // time moving forward prunes the history, older slots can no longer be checked
+    guard
+        .allow_endorsement(&endorsement(20, 0, b"c"), 20)
+        .unwrap();
+    assert!(matches!(
+        guard.allow_endorsement(&endorsement(3, 2, b"a"), 20),
+        Err(SignerError::Refused(_))
+    ));
+}
+
+#[test]
+fn test_guard_refuses_far_future_slots() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    let creator = derive_public_key(&generate_random_private_key());
+    let mut guard = SigningGuard::new(10, 2);
+
+    guard
+        .allow_block_header(&header(creator, Slot::new(5, 0), b"first"), 5)
+        .unwrap();
+    // a request far ahead of the current period is refused without touching the history
+    assert!(matches!(
+        guard.allow_block_header(&header(creator, Slot::new(1000, 0), b"future"), 5),
+        Err(SignerError::Refused(_))
+    ));
+    assert!(matches!(
+        guard.allow_block_header(&header(creator, Slot::new(5, 0), b"second"), 5),
+        Err(SignerError::DoubleSigning(_))
+    ));
+    // slots slightly ahead are accepted but do not prune the history either
+    guard
+        .allow_block_header(&header(creator, Slot::new(7, 0), b"first"), 5)
+        .unwrap();
+    guard
+        .allow_block_header(&header(creator, Slot::new(5, 1), b"first"), 5)
+        .unwrap();
+}
+
+#[test]
+fn test_guard_persistence() {
+    massa_models::init_serialization_context(massa_models::SerializationContext::default());
+    let creator = derive_public_key(&generate_random_private_key());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("signing_history.json");
+
+    let mut guard = SigningGuard::load(&path, 10, 2).unwrap();
+    guard
+        .allow_block_header(&header(creator, Slot::new(1, 0), b"first"), 1)
+        .unwrap();
+    guard.save(&path).unwrap();
+
+    let mut reloaded = SigningGuard::load(&path, 10, 2).unwrap();
+    assert!(matches!(
+        reloaded.allow_block_header(&header(creator, Slot::new(1, 0), b"second"), 1),
+        Err(SignerError::DoubleSigning(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_handshake() {
+    let node_key = generate_random_private_key();
+    let signer_key = generate_random_private_key();
+    let signer_public_key = derive_public_key(&signer_key);
+
+    // authorized node
+    let (mut node_stream, mut signer_stream) = tokio::io::duplex(4096);
+    let authorized = vec![derive_public_key(&node_key)];
+    let (node_res, signer_res) = tokio::join!(
+        client_handshake(&mut node_stream, &node_key, &signer_public_key),
+        server_handshake(&mut signer_stream, &signer_key, &authorized)
+    );
+    let mut node_session = node_res.unwrap();
+    let (node_public_key, mut signer_session) = signer_res.unwrap();
+    assert_eq!(node_public_key, derive_public_key(&node_key));
+
+    // authenticated messages flow both ways once authenticated
+    node_session
+        .write_message(&mut node_stream, &SignerMessage::GetPublicKeys)
+        .await
+        .unwrap();
+    assert!(matches!(
+        signer_session
+            .read_message(&mut signer_stream)
+            .await
+            .unwrap(),
+        SignerMessage::GetPublicKeys
+    ));
+    signer_session
+        .write_message(&mut signer_stream, &SignerMessage::PublicKeys(Vec::new()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        node_session.read_message(&mut node_stream).await.unwrap(),
+        SignerMessage::PublicKeys(_)
+    ));
+
+    // a replayed frame is rejected
+    let mut replaying_session = node_session.clone();
+    node_session
+        .write_message(&mut node_stream, &SignerMessage::GetPublicKeys)
+        .await
+        .unwrap();
+    signer_session
+        .read_message(&mut signer_stream)
+        .await
+        .unwrap();
+    replaying_session
+        .write_message(&mut node_stream, &SignerMessage::GetPublicKeys)
+        .await
+        .unwrap();
+    assert!(matches!(
+        signer_session.read_message(&mut signer_stream).await,
+        Err(SignerError::AuthenticationError(_))
+    ));
+
+    // a frame without a valid MAC is rejected
+    write_message(&mut node_stream, &SignerMessage::GetPublicKeys)
+        .await
+        .unwrap();
+    node_stream.write_all(&[0u8; 32]).await.unwrap();
+    assert!(matches!(
+        signer_session.read_message(&mut signer_stream).await,
+        Err(SignerError::AuthenticationError(_))
+    ));
+
+    // unauthorized node
+    let (mut node_stream, mut signer_stream) = tokio::io::duplex(4096);
+    let (node_res, signer_res) = tokio::join!(
+        client_handshake(&mut node_stream, &node_key, &signer_public_key),
+        server_handshake(&mut signer_stream, &signer_key, &[])
+    );
+    assert!(matches!(node_res, Err(SignerError::AuthenticationError(_))));
+    assert!(matches!(
+        signer_res,
+        Err(SignerError::AuthenticationError(_))
+    ));
+
+    // impersonated signer
+    let (mut node_stream, mut signer_stream) = tokio::io::duplex(4096);
+    let (node_res, _) = tokio::join!(
+        // the node stream is dropped when the node gives up, so that the signer stops waiting
+        async move { client_handshake(&mut node_stream, &node_key, &signer_public_key).await },
+        server_handshake(
+            &mut signer_stream,
+            &generate_random_private_key(),
+            &authorized
+        )
+    );
+    assert!(matches!(node_res, Err(SignerError::AuthenticationError(_))));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn test_generated_auth_key_is_owner_only() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("auth_key");
+
+    let key = load_or_generate_auth_key(&path).await.unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // the generated key is loaded back on the next start
+    assert_eq!(load_or_generate_auth_key(&path).await.unwrap(), key);
+}